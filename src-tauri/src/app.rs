@@ -0,0 +1,1482 @@
+// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+use rfd::FileDialog;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use tauri::Emitter;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+use crate::process::ProcessSpec;
+use crate::{
+    clipboard, diagnostics, engine, error, export, file_manager, history, hot_folder, job_log, library, logging,
+    notifications, output, path_policy, probe, storage, trash, watcher,
+};
+use engine::{
+    DownloadResult, DownloadedModel, InputType, ModelInfo, SeparationResult, SeparationSettings, VideoInfo,
+};
+use history::AudioFileInfoFrontend;
+use library::Library;
+use error::AppError;
+use path_policy::PathPolicy;
+use probe::ProbeCache;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DownloadProgress {
+    progress: f64,
+    message: String,
+    status: String, // "downloading", "processing", "completed", "error"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GPUInfo {
+    gpu_type: String,
+    is_available: bool,
+    description: String,
+}
+
+struct LibraryState(Mutex<Library>);
+struct ProbeState(Mutex<ProbeCache>);
+struct HotFolderState(Mutex<hot_folder::HotFolderStore>);
+/// Files chosen through `select_file` during this session
+struct PickedFiles(Mutex<std::collections::HashSet<PathBuf>>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryProbeUpdate {
+    id: String,
+    duration: Option<String>,
+    #[serde(flatten)]
+    properties: probe::AudioProperties,
+}
+
+/// Emitted as `history-changed` after the watcher sees the managed folders change.
+/// Renamed files show up as a removal plus an addition with the same id.
+#[derive(Debug, Clone, Serialize)]
+struct HistoryChangedEvent {
+    added: Vec<AudioFileInfoFrontend>,
+    modified: Vec<AudioFileInfoFrontend>,
+    /// Library ids of files that disappeared
+    removed: Vec<String>,
+}
+
+/// Emitted as `models-changed` with the model filenames that appeared or disappeared.
+#[derive(Debug, Clone, Serialize)]
+struct ModelsChangedEvent {
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+/// Emitted as `hot-folder-job` when a hot folder file is queued, finishes or fails.
+#[derive(Debug, Clone, Serialize)]
+struct HotFolderJobEvent {
+    folder_id: String,
+    file: String,
+    /// "processing", "done" or "failed"
+    status: String,
+    output_files: Vec<String>,
+    error: Option<String>,
+}
+
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+/// How long the watcher waits for a change notification before checking whether
+/// the folders were moved in settings
+const WATCH_IDLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The Tauri-free core, pointed at this app's data and resource folders.
+fn engine(app_handle: &tauri::AppHandle) -> engine::Engine {
+    engine::Engine::new(
+        app_handle.path().app_data_dir().unwrap_or_default(),
+        app_handle.path().resource_dir().ok(),
+    )
+}
+
+fn get_download_base(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    engine(app_handle).download_base()
+}
+
+fn get_model_directory(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    engine(app_handle).model_directory()
+}
+
+/// The saved settings.json as JSON, or `Value::Null` if there is none yet.
+fn read_saved_settings(app_handle: &tauri::AppHandle) -> Value {
+    engine(app_handle).settings()
+}
+
+fn history_scan_options(app_handle: &tauri::AppHandle) -> history::HistoryScanOptions {
+    engine(app_handle).history_scan_options()
+}
+
+#[tauri::command]
+fn detect_input_type(input: String) -> Result<InputType, AppError> {
+    engine::detect_input_type(input)
+}
+
+#[tauri::command]
+async fn fetch_video_info(app_handle: tauri::AppHandle, url: String) -> Result<VideoInfo, AppError> {
+    // The stop button also cuts a rate-limit wait during the lookup short
+    engine::DOWNLOAD_CANCELLED.store(false, Ordering::SeqCst);
+    engine::fetch_video_info(&engine(&app_handle), url).await
+}
+
+#[tauri::command]
+async fn get_local_file_info(app_handle: tauri::AppHandle, file_path: String) -> Result<VideoInfo, AppError> {
+    engine::local_file_info(&engine(&app_handle), file_path)
+}
+
+#[tauri::command]
+async fn list_audio_separator_models(app_handle: tauri::AppHandle) -> Result<Vec<ModelInfo>, AppError> {
+    engine::list_models(&engine(&app_handle))
+}
+
+#[tauri::command]
+async fn list_downloaded_models(app_handle: tauri::AppHandle, modelDirectory: String) -> Result<Vec<DownloadedModel>, AppError> {
+    let model_dir = checked_model_directory(&app_handle, &modelDirectory)?;
+    engine::list_downloaded_models(&model_dir)
+}
+
+#[tauri::command]
+async fn download_audio_separator_model(app_handle: tauri::AppHandle, model_filename: String, modelDirectory: String) -> Result<(), AppError> {
+    path_policy::validate_model_filename(&model_filename)?;
+    let model_dir = checked_model_directory(&app_handle, &modelDirectory)?;
+    engine::download_model(&engine(&app_handle), &model_filename, &model_dir)
+}
+
+/// A model directory sent by the webview, checked against the path policy. The
+/// configured model directory is created first, so a fresh install passes.
+fn checked_model_directory(app_handle: &tauri::AppHandle, model_directory: &str) -> Result<PathBuf, AppError> {
+    let configured = get_model_directory(app_handle)?;
+    fs::create_dir_all(&configured).map_err(|e| format!("Failed to create model directory: {}", e))?;
+    path_policy(app_handle)?.check(model_directory)
+}
+
+#[tauri::command]
+fn load_settings(app_handle: tauri::AppHandle) -> Result<String, AppError> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let settings_path = app_dir.join("settings.json");
+
+    if settings_path.exists() {
+        let content = fs::read_to_string(&settings_path)
+            .map_err(|e| format!("Failed to read settings file: {}", e))?;
+        Ok(content)
+    } else {
+        // Return default settings if file doesn't exist
+        Ok(engine::default_settings_json())
+    }
+}
+
+#[tauri::command]
+fn save_settings(app_handle: tauri::AppHandle, settings: String) -> Result<(), AppError> {
+    engine(&app_handle).save_settings(&settings)
+}
+
+#[tauri::command]
+async fn select_folder() -> Result<String, AppError> {
+    let dialog = FileDialog::new()
+        .set_title("Select Folder")
+        .set_directory("/");
+
+    match dialog.pick_folder() {
+        Some(path) => Ok(path.to_string_lossy().to_string()),
+        None => Err(AppError::cancelled("No folder selected")),
+    }
+}
+
+#[tauri::command]
+async fn select_file(app_handle: tauri::AppHandle) -> Result<String, AppError> {
+    let dialog = FileDialog::new()
+        .set_title("Select File")
+        .add_filter("Audio Files", &["mp3", "wav", "flac", "m4a", "aac", "ogg"])
+        .add_filter("Video Files", &["mp4", "avi", "mkv", "mov", "wmv", "flv"])
+        .add_filter("All Files", &["*"]);
+
+    match dialog.pick_file() {
+        Some(path) => {
+            // Files the user picked themselves may be opened, copied or deleted later
+            if let Ok(canonical) = path.canonicalize() {
+                let state = app_handle.state::<PickedFiles>();
+                if let Ok(mut picked) = state.0.lock() {
+                    picked.insert(canonical);
+                };
+            }
+            Ok(path.to_string_lossy().to_string())
+        }
+        None => Err(AppError::cancelled("No file selected")),
+    }
+}
+
+fn newest_file_in_dir(dir: &Path) -> Option<PathBuf> {
+    let mut newest: Option<(SystemTime, PathBuf)> = None;
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if let Ok(meta) = entry.metadata() {
+                if meta.is_file() {
+                    if let Ok(modified) = meta.modified() {
+                        match &newest {
+                            Some((t, _)) if *t >= modified => {}
+                            _ => {
+                                newest = Some((modified, entry.path()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    newest.map(|(_, p)| p)
+}
+
+#[tauri::command]
+async fn unified_download(
+    app_handle: tauri::AppHandle,
+    input: String,
+    input_type: InputType,
+    _processing_mode: String,
+    startTime: Option<f64>,
+    endTime: Option<f64>,
+) -> Result<DownloadResult, AppError> {
+    // Local files need no download, so there is nothing to notify about
+    let notify = matches!(input_type, InputType::YouTube | InputType::Spotify);
+    engine::DOWNLOAD_CANCELLED.store(false, Ordering::SeqCst);
+    let started = std::time::Instant::now();
+
+    let emit_progress = |progress: f64, message: &str, status: &str| {
+        let _ = app_handle.emit(
+            "download-progress",
+            DownloadProgress {
+                progress,
+                message: message.to_string(),
+                status: status.to_string(),
+            },
+        );
+    };
+    let result = engine::download(
+        &engine(&app_handle),
+        &app_handle.state::<LibraryState>().0,
+        input.clone(),
+        input_type,
+        startTime,
+        endTime,
+        &emit_progress,
+    )
+    .await;
+    // yt-dlp just reports being killed, so say what actually happened
+    let result = match result {
+        Err(e) if engine::DOWNLOAD_CANCELLED.load(Ordering::SeqCst) => {
+            let cancelled = AppError::cancelled("Download cancelled").with_details(e.message);
+            Err(match e.job_id {
+                Some(job_id) => cancelled.with_job(job_id),
+                None => cancelled,
+            })
+        }
+        other => other,
+    };
+
+    if notify {
+        let outcome = match &result {
+            Ok(_) => notifications::JobOutcome::Completed,
+            Err(e) if e.code == error::ErrorCode::Cancelled => notifications::JobOutcome::Cancelled,
+            Err(e) => notifications::JobOutcome::Failed(e.message.clone()),
+        };
+        let title = match result.as_ref().ok().and_then(|r| r.file_path.as_deref()) {
+            Some(path) => engine::track_title(&app_handle.state::<LibraryState>().0, Path::new(path)),
+            None => input.clone(),
+        };
+        notify_job(
+            &app_handle,
+            notifications::JobNotice {
+                kind: notifications::JobKind::Download,
+                outcome,
+                title,
+                elapsed: started.elapsed(),
+                stems: Vec::new(),
+            },
+        );
+    }
+    result
+}
+
+/// Paths file-operation commands may touch: the download base (including its
+/// Downloads and Separated folders), the model directory, files the library
+/// recorded as downloads or separation outputs, and files picked via `select_file`.
+fn path_policy(app_handle: &tauri::AppHandle) -> Result<PathPolicy, AppError> {
+    let download_base = get_download_base(app_handle)?;
+    // Settings saved before roots were checked could still name e.g. `/`
+    let roots: Vec<PathBuf> = [
+        output::downloads_dir(&download_base),
+        output::separated_dir(&download_base),
+        download_base,
+        get_model_directory(app_handle)?,
+    ]
+    .into_iter()
+    .filter(|root| !root.exists() || path_policy::check_managed_root(&root.to_string_lossy()).is_ok())
+    .collect();
+
+    let mut allowed_files: Vec<PathBuf> = {
+        let state = app_handle.state::<LibraryState>();
+        let lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+        lib.files()
+            .filter(|f| f.role != library::FileRole::Local)
+            .map(|f| PathBuf::from(&f.path))
+            .collect()
+    };
+    {
+        let state = app_handle.state::<PickedFiles>();
+        let picked = state.0.lock().map_err(|_| "Picked files lock poisoned".to_string())?;
+        allowed_files.extend(picked.iter().cloned());
+    }
+
+    Ok(PathPolicy::new(roots, allowed_files))
+}
+
+fn purge_expired_trash(app_handle: &tauri::AppHandle) {
+    let result = get_download_base(app_handle)
+        .and_then(|base| trash::purge_expired(&base, engine(app_handle).trash_retention_days()));
+    if let Err(e) = result {
+        log_warn!("Failed to purge expired trash: {}", e);
+    }
+}
+
+#[tauri::command]
+async fn get_audio_file_history(app_handle: tauri::AppHandle) -> Result<Vec<AudioFileInfoFrontend>, AppError> {
+    let download_base = get_download_base(&app_handle)?;
+    let scan = history_scan_options(&app_handle);
+    let state = app_handle.state::<LibraryState>();
+    let (_, mut items) = history::lock_and_collect(&state.0, &download_base, &scan)?;
+    enrich_with_probes(&app_handle, &mut items);
+    Ok(items)
+}
+
+/// Filter, sort and page the history. Text search covers file names, source
+/// titles, uploaders, tags and notes.
+#[tauri::command]
+async fn query_audio_file_history(app_handle: tauri::AppHandle, query: history::HistoryQuery) -> Result<history::HistoryPage, AppError> {
+    let download_base = get_download_base(&app_handle)?;
+    let scan = history_scan_options(&app_handle);
+    let state = app_handle.state::<LibraryState>();
+    let mut page = {
+        let (lib, mut items) = history::lock_and_collect(&state.0, &download_base, &scan)?;
+        // Cached durations are needed up front for sorting by duration
+        apply_cached_probes(&app_handle, &mut items);
+        history::query_history(&lib, items, &query)?
+    };
+    enrich_with_probes(&app_handle, &mut page.items);
+    Ok(page)
+}
+
+fn apply_cached_probes(app_handle: &tauri::AppHandle, items: &mut [AudioFileInfoFrontend]) {
+    let state = app_handle.state::<ProbeState>();
+    let Ok(cache) = state.0.lock() else { return };
+    for item in items.iter_mut() {
+        if let Some(properties) = cache.get(Path::new(&item.file_path)) {
+            item.apply_properties(properties);
+        }
+    }
+}
+
+/// Fill in cached audio properties and probe the rest in the background. Each
+/// probed item is sent to the frontend as a `history-item-probed` event.
+fn enrich_with_probes(app_handle: &tauri::AppHandle, items: &mut [AudioFileInfoFrontend]) {
+    let mut pending: Vec<(String, PathBuf)> = Vec::new();
+    {
+        let state = app_handle.state::<ProbeState>();
+        let Ok(mut cache) = state.0.lock() else { return };
+        for item in items.iter_mut() {
+            let path = PathBuf::from(&item.file_path);
+            match cache.get(&path) {
+                Some(properties) => item.apply_properties(properties),
+                None => {
+                    if cache.claim(&path) {
+                        pending.push((item.id.clone(), path));
+                    }
+                }
+            }
+        }
+    }
+    if pending.is_empty() {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let engine = engine(&app_handle);
+        let ffprobe_path = match engine.ffprobe_binary() {
+            Ok(p) => p,
+            Err(e) => {
+                log_warn!("Skipping history probing: {}", e);
+                let state = app_handle.state::<ProbeState>();
+                if let Ok(mut cache) = state.0.lock() {
+                    for (_, path) in &pending {
+                        cache.release(path);
+                    }
+                }
+                return;
+            }
+        };
+
+        log_info!("Probing {} history items", pending.len());
+        let state = app_handle.state::<ProbeState>();
+        for (index, (id, path)) in pending.iter().enumerate() {
+            let properties = match probe::probe_file(engine.runner.as_ref(), &ffprobe_path, path) {
+                Ok(properties) => properties,
+                Err(e) => {
+                    // Not cached, so the next history load tries again
+                    log_warn!("Failed to probe {:?}: {}", path, e);
+                    if let Ok(mut cache) = state.0.lock() {
+                        cache.release(path);
+                    }
+                    continue;
+                }
+            };
+
+            if let Ok(mut cache) = state.0.lock() {
+                cache.insert(path, properties.clone());
+                // Persist periodically so a long first scan isn't lost on exit
+                if index % 25 == 24 {
+                    if let Err(e) = cache.save() {
+                        log_warn!("{}", e);
+                    }
+                }
+            }
+
+            let _ = app_handle.emit(
+                "history-item-probed",
+                HistoryProbeUpdate {
+                    id: id.clone(),
+                    duration: properties.duration_seconds.map(probe::format_duration),
+                    properties,
+                },
+            );
+        }
+        if let Ok(mut cache) = state.0.lock() {
+            if let Err(e) = cache.save() {
+                log_warn!("{}", e);
+            }
+        };
+    });
+}
+
+/// Look up a single history item by its stable id.
+#[tauri::command]
+async fn get_history_item(app_handle: tauri::AppHandle, id: String) -> Result<AudioFileInfoFrontend, AppError> {
+    let download_base = get_download_base(&app_handle)?;
+    let scan = history_scan_options(&app_handle);
+    let state = app_handle.state::<LibraryState>();
+    let mut item = {
+        let mut lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+        history::find_history_item(&mut lib, &download_base, &scan, &id)?
+    };
+    enrich_with_probes(&app_handle, std::slice::from_mut(&mut item));
+    Ok(item)
+}
+
+/// Apply an annotation change to a history item and return the updated item.
+fn annotate_history_item<F>(app_handle: &tauri::AppHandle, id: &str, update: F) -> Result<AudioFileInfoFrontend, String>
+where
+    F: FnOnce(&mut library::FileAnnotations),
+{
+    let state = app_handle.state::<LibraryState>();
+    let mut item = {
+        let mut lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+        let file = lib.update_file(id, |f| update(&mut f.annotations))?;
+        history::history_item(&lib, &file).ok_or_else(|| "File no longer exists".to_string())?
+    };
+    apply_cached_probes(app_handle, std::slice::from_mut(&mut item));
+    Ok(item)
+}
+
+#[tauri::command]
+async fn add_history_tag(app_handle: tauri::AppHandle, id: String, tag: String) -> Result<AudioFileInfoFrontend, AppError> {
+    if tag.trim().is_empty() {
+        return Err(AppError::invalid_input("Tag cannot be empty"));
+    }
+    Ok(annotate_history_item(&app_handle, &id, |a| a.add_tag(&tag))?)
+}
+
+#[tauri::command]
+async fn remove_history_tag(app_handle: tauri::AppHandle, id: String, tag: String) -> Result<AudioFileInfoFrontend, AppError> {
+    Ok(annotate_history_item(&app_handle, &id, |a| a.remove_tag(&tag))?)
+}
+
+#[tauri::command]
+async fn set_history_favorite(app_handle: tauri::AppHandle, id: String, favorite: bool) -> Result<AudioFileInfoFrontend, AppError> {
+    Ok(annotate_history_item(&app_handle, &id, |a| a.favorite = favorite)?)
+}
+
+#[tauri::command]
+async fn set_history_note(app_handle: tauri::AppHandle, id: String, note: Option<String>) -> Result<AudioFileInfoFrontend, AppError> {
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    Ok(annotate_history_item(&app_handle, &id, |a| a.note = note)?)
+}
+
+/// Every stem produced from the given library file, across all separation jobs.
+#[tauri::command]
+async fn get_stems_for_track(app_handle: tauri::AppHandle, id: String) -> Result<Vec<AudioFileInfoFrontend>, AppError> {
+    let state = app_handle.state::<LibraryState>();
+    let mut stems: Vec<AudioFileInfoFrontend> = {
+        let lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+        if lib.file(&id).is_none() {
+            return Err(AppError::new(error::ErrorCode::NotFound, "History item not found"));
+        }
+        lib.jobs_for_input(&id)
+            .into_iter()
+            .flat_map(|job| lib.outputs_of_job(&job.id))
+            .filter_map(|f| history::history_item(&lib, f))
+            .collect()
+    };
+    enrich_with_probes(&app_handle, &mut stems);
+    Ok(stems)
+}
+
+/// Run a recorded separation job again with the same input and settings.
+#[tauri::command]
+async fn rerun_separation(app_handle: tauri::AppHandle, jobId: String) -> Result<SeparationResult, AppError> {
+    let (input_path, settings, selected_stems) = {
+        let state = app_handle.state::<LibraryState>();
+        let lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+        let job = lib.job(&jobId).ok_or_else(|| "Separation job not found".to_string())?;
+        let input = lib
+            .file(&job.input_id)
+            .ok_or_else(|| "Input file for this job is no longer in the library".to_string())?;
+        let settings: SeparationSettings = serde_json::from_value(job.settings.clone())
+            .map_err(|e| format!("Failed to read job settings: {}", e))?;
+        (input.path.clone(), settings, job.selected_stems.clone())
+    };
+    if !PathBuf::from(&input_path).exists() {
+        return Err(AppError::not_found(input_path));
+    }
+    perform_audio_separation(app_handle, input_path, settings, selected_stems).await
+}
+
+#[tauri::command]
+async fn copy_audio_file_to_clipboard(app_handle: tauri::AppHandle, filePath: String) -> Result<String, AppError> {
+    let path = path_policy(&app_handle)?.check(&filePath)?;
+    Ok(clipboard::copy_files(engine(&app_handle).runner.as_ref(), &[path])?)
+}
+
+/// Put several files on the clipboard in one go, e.g. a whole stem set or
+/// `SeparationResult.output_files`, so they can be pasted into a DAW together.
+#[tauri::command]
+async fn copy_files_to_clipboard(app_handle: tauri::AppHandle, filePaths: Vec<String>) -> Result<String, AppError> {
+    if filePaths.is_empty() {
+        return Err("No files to copy".into());
+    }
+    let policy = path_policy(&app_handle)?;
+    let paths = filePaths
+        .iter()
+        .map(|p| policy.check(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(clipboard::copy_files(engine(&app_handle).runner.as_ref(), &paths)?)
+}
+
+/// Move a file, or a history set folder with everything in it, to the app trash.
+/// Returns the trash entry id to pass to `restore_deleted`.
+#[tauri::command]
+async fn delete_file(app_handle: tauri::AppHandle, filePath: String) -> Result<String, AppError> {
+    let path = path_policy(&app_handle)?.check(&filePath)?;
+    let entry = trash_file(&app_handle, &path)?;
+    purge_expired_trash(&app_handle);
+    Ok(entry.id)
+}
+
+/// Move a file or a history set folder to the trash, taking the library records
+/// along so a restore brings back their links and tags. `path` is canonical, so
+/// records are matched on their canonical paths too.
+fn trash_file(app_handle: &tauri::AppHandle, path: &Path) -> Result<trash::TrashEntry, AppError> {
+    let download_base = get_download_base(app_handle)?;
+    check_trashable(&download_base, path)?;
+
+    // Held across the move, so a scan never sees the records without their files
+    let state = app_handle.state::<LibraryState>();
+    let mut lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+    let canonical = |f: &library::FileRecord| Path::new(&f.path).canonicalize().ok();
+    let contained: Vec<library::FileRecord> = if path.is_dir() {
+        lib.files().filter(|f| canonical(f).is_some_and(|p| p.starts_with(path))).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    let record = lib
+        .file_by_path(path)
+        .or_else(|| lib.files().find(|f| canonical(f).as_deref() == Some(path)))
+        .cloned();
+    let entry = trash::move_to_trash(&download_base, path, trash::TrashKind::File, record, contained)?;
+
+    for record in entry.library_record.iter().chain(&entry.contained_records) {
+        lib.remove(&record.id)?;
+    }
+    Ok(entry)
+}
+
+/// Folders can only be trashed when they are a history set, so a managed root (the
+/// download base, Downloads, Separated, the model directory) is never moved, and
+/// nothing inside or holding the trash is either.
+fn check_trashable(download_base: &Path, path: &Path) -> Result<(), AppError> {
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    let download_base = canonical(download_base);
+    let path = canonical(path);
+    let trash_dir = canonical(&trash::trash_dir(&download_base));
+    let path_str = path.to_string_lossy();
+    if path.starts_with(&trash_dir) || trash_dir.starts_with(&path) {
+        return Err(AppError::invalid_input("Items in the trash can't be deleted again").with_path(path_str));
+    }
+    if path.is_dir() && !history::is_set_folder(&download_base, &path) {
+        return Err(AppError::invalid_input("Only files and history set folders can be deleted").with_path(path_str));
+    }
+    Ok(())
+}
+
+/// Disk usage per category (downloads, separated, trash, models), per model and per month.
+#[tauri::command]
+async fn get_storage_report(app_handle: tauri::AppHandle) -> Result<storage::StorageReport, AppError> {
+    let download_base = get_download_base(&app_handle)?;
+    let scan = history_scan_options(&app_handle);
+    let model_dir = get_model_directory(&app_handle)?;
+    let state = app_handle.state::<LibraryState>();
+    let (_, items) = history::lock_and_collect(&state.0, &download_base, &scan)?;
+    Ok(storage::storage_report(&download_base, &model_dir, &items))
+}
+
+/// Apply a cleanup policy (the saved `cleanup_policy` setting unless one is passed).
+/// With `dryRun` the files that would be removed are listed and nothing is touched;
+/// otherwise they are moved to the trash, where they can be restored until they expire,
+/// except for those over the size limit, which the user has confirmed deleting for good.
+#[tauri::command]
+async fn run_cleanup(
+    app_handle: tauri::AppHandle,
+    dryRun: bool,
+    policy: Option<storage::CleanupPolicy>,
+) -> Result<storage::CleanupReport, AppError> {
+    let policy = policy.unwrap_or_else(|| saved_cleanup_policy(&app_handle));
+    apply_cleanup(&app_handle, &policy, dryRun, true)
+}
+
+fn saved_cleanup_policy(app_handle: &tauri::AppHandle) -> storage::CleanupPolicy {
+    read_saved_settings(app_handle)
+        .get("cleanup_policy")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Run the saved cleanup policy at startup, if the user turned on automatic cleanup
+/// and it has any rules enabled. Everything it removes goes to the trash; nothing is
+/// deleted without review. Each moved file is logged and the report is sent to the
+/// window as `cleanup-finished`.
+fn run_saved_cleanup(app_handle: &tauri::AppHandle) {
+    let policy = saved_cleanup_policy(app_handle);
+    if !policy.automatic || !policy.is_active() {
+        return;
+    }
+    match apply_cleanup(app_handle, &policy, false, false) {
+        Ok(report) => {
+            for candidate in &report.candidates {
+                log_info!("Automatic cleanup moved {} to the trash ({:?})", candidate.file_path, candidate.reason);
+            }
+            for failure in &report.failures {
+                log_warn!("Automatic cleanup couldn't move {}", failure);
+            }
+            let _ = app_handle.emit("cleanup-finished", &report);
+        }
+        Err(e) => log_warn!("Automatic cleanup failed: {}", e),
+    }
+}
+
+/// Items over the size limit are deleted permanently, so they are only touched with
+/// `delete_over_limit`, when the user has confirmed it; otherwise they are left alone.
+fn apply_cleanup(
+    app_handle: &tauri::AppHandle,
+    policy: &storage::CleanupPolicy,
+    dry_run: bool,
+    delete_over_limit: bool,
+) -> Result<storage::CleanupReport, AppError> {
+    let download_base = get_download_base(app_handle)?;
+    let scan = history_scan_options(app_handle);
+    let candidates = {
+        let state = app_handle.state::<LibraryState>();
+        let (lib, items) = history::lock_and_collect(&state.0, &download_base, &scan)?;
+        storage::plan_cleanup(&lib, &items, policy, library::now_secs())
+    };
+
+    let mut report = storage::CleanupReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut left_over_limit = 0;
+    for candidate in candidates {
+        let permanent = candidate.reason.is_permanent();
+        if permanent && !delete_over_limit {
+            left_over_limit += 1;
+            continue;
+        }
+        if !dry_run {
+            let path = Path::new(&candidate.file_path);
+            let result = if permanent {
+                delete_file_permanently(app_handle, path)
+            } else {
+                trash_file(app_handle, path).map(|_| ())
+            };
+            if let Err(e) = result {
+                report.failures.push(format!("{}: {}", candidate.file_path, e));
+                continue;
+            }
+        }
+        if permanent {
+            report.bytes_deleted += candidate.file_size;
+        } else {
+            report.bytes_trashed += candidate.file_size;
+        }
+        report.candidates.push(candidate);
+    }
+    log_info!(
+        "Cleanup{}: {} files, {} bytes moved to the trash, {} bytes deleted",
+        if dry_run { " (dry run)" } else { "" },
+        report.candidates.len(),
+        report.bytes_trashed,
+        report.bytes_deleted
+    );
+    if left_over_limit > 0 {
+        log_info!(
+            "Cleanup left {} files over the size limit; run it from Settings to delete them",
+            left_over_limit
+        );
+    }
+    Ok(report)
+}
+
+/// Delete a file or history set folder for good, with the library records of
+/// everything in it.
+fn delete_file_permanently(app_handle: &tauri::AppHandle, path: &Path) -> Result<(), AppError> {
+    let download_base = get_download_base(app_handle)?;
+    check_trashable(&download_base, path)?;
+
+    let state = app_handle.state::<LibraryState>();
+    let mut lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+    trash::remove_path(path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    let removed: Vec<String> = lib
+        .files()
+        .filter(|f| Path::new(&f.path).starts_with(path))
+        .map(|f| f.id.clone())
+        .collect();
+    for id in removed {
+        lib.remove(&id)?;
+    }
+    Ok(())
+}
+
+/// Export the history items matching `query` (paging is ignored) with their
+/// provenance as CSV or JSON, to a file picked in a save dialog.
+#[tauri::command]
+async fn export_library(
+    app_handle: tauri::AppHandle,
+    query: history::HistoryQuery,
+    format: export::ExportFormat,
+) -> Result<export::ExportReport, AppError> {
+    let path = FileDialog::new()
+        .set_title("Export Library")
+        .set_file_name(format!("resample2-library.{}", format.extension()))
+        .add_filter(format.extension().to_uppercase(), &[format.extension()])
+        .save_file()
+        .ok_or_else(|| AppError::cancelled("No file selected"))?;
+
+    let download_base = get_download_base(&app_handle)?;
+    let scan = history_scan_options(&app_handle);
+    let rows = {
+        let state = app_handle.state::<LibraryState>();
+        let (lib, mut items) = history::lock_and_collect(&state.0, &download_base, &scan)?;
+        apply_cached_probes(&app_handle, &mut items);
+        let all = history::HistoryQuery {
+            cursor: None,
+            limit: usize::MAX,
+            ..query
+        };
+        let page = history::query_history(&lib, items, &all)?;
+        page.items.iter().map(|item| export::export_row(&lib, item)).collect::<Vec<_>>()
+    };
+
+    export::write_export(&path, &rows, format)?;
+    log_info!("Exported {} library rows to {:?}", rows.len(), path);
+    Ok(export::ExportReport {
+        path: path.to_string_lossy().to_string(),
+        rows: rows.len(),
+    })
+}
+
+/// The newest log entries, oldest first. `level` is the lowest level to include.
+#[tauri::command]
+async fn get_recent_logs(
+    level: Option<String>,
+    jobId: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<logging::LogEntry>, AppError> {
+    let min_level = match level.filter(|l| !l.trim().is_empty()) {
+        Some(l) => Some(logging::Level::parse(&l).ok_or_else(|| AppError::invalid_input(format!("Unknown log level: {}", l)))?),
+        None => None,
+    };
+    let Some(dir) = logging::log_dir() else {
+        return Ok(Vec::new());
+    };
+    let filter = logging::LogFilter {
+        min_level,
+        job_id: jobId,
+        limit: limit.unwrap_or(500),
+    };
+    Ok(logging::recent(&dir, &filter))
+}
+
+/// The command lines, output, exit codes and timings of a download's or
+/// separation's tool runs.
+#[tauri::command]
+async fn get_job_log(app_handle: tauri::AppHandle, jobId: String) -> Result<job_log::JobLog, AppError> {
+    job_log::load(&engine(&app_handle).job_log_dir(), &jobId)
+}
+
+/// Zip the logs and a redacted settings.json for a bug report, to a file picked in
+/// a save dialog. Returns the zip's path.
+#[tauri::command]
+async fn export_diagnostics(app_handle: tauri::AppHandle) -> Result<String, AppError> {
+    let path = FileDialog::new()
+        .set_title("Export Diagnostics")
+        .set_file_name(format!("resample2-diagnostics-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S")))
+        .add_filter("ZIP", &["zip"])
+        .save_file()
+        .ok_or_else(|| AppError::cancelled("No file selected"))?;
+    let settings = engine(&app_handle).settings();
+    diagnostics::write_bundle(&path, logging::log_dir().as_deref(), &settings)?;
+    log_info!("Exported diagnostics to {:?}", path);
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Put a trashed file or model back where it was deleted from.
+#[tauri::command]
+async fn restore_deleted(app_handle: tauri::AppHandle, id: String) -> Result<trash::TrashEntry, AppError> {
+    let download_base = get_download_base(&app_handle)?;
+    let entry = trash::restore(&download_base, &id)?;
+    {
+        let state = app_handle.state::<LibraryState>();
+        let mut lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+        for record in entry.library_record.iter().chain(&entry.contained_records) {
+            lib.put(record.clone().into())?;
+        }
+    }
+    Ok(entry)
+}
+
+#[tauri::command]
+async fn list_trash(app_handle: tauri::AppHandle) -> Result<Vec<trash::TrashEntry>, AppError> {
+    let download_base = get_download_base(&app_handle)?;
+    Ok(trash::list(&download_base)?)
+}
+
+/// Permanently delete everything in the trash and report the space freed.
+#[tauri::command]
+async fn empty_trash(app_handle: tauri::AppHandle) -> Result<trash::EmptyTrashReport, AppError> {
+    let download_base = get_download_base(&app_handle)?;
+    let report = trash::empty(&download_base)?;
+    log_info!("Emptied trash: {} items, {} bytes freed", report.removed, report.bytes_freed);
+    Ok(report)
+}
+
+#[tauri::command]
+async fn open_in_explorer(app_handle: tauri::AppHandle, path: String) -> Result<(), AppError> {
+    let target = path_policy(&app_handle)?.check(&path)?;
+    Ok(file_manager::reveal(engine(&app_handle).runner.as_ref(), &[target])?)
+}
+
+/// Reveal several files at once, e.g. a whole stem set, selected in the file manager.
+#[tauri::command]
+async fn open_files_in_explorer(app_handle: tauri::AppHandle, paths: Vec<String>) -> Result<(), AppError> {
+    if paths.is_empty() {
+        return Err("No files to show".into());
+    }
+    let policy = path_policy(&app_handle)?;
+    let targets = paths
+        .iter()
+        .map(|p| policy.check(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(file_manager::reveal(engine(&app_handle).runner.as_ref(), &targets)?)
+}
+
+/// Stop the download in progress. The download loop owns the yt-dlp process and
+/// kills it (with its children) as soon as it sees the flag.
+#[tauri::command]
+async fn stop_download() -> Result<(), AppError> {
+    engine::DOWNLOAD_CANCELLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Move a model file to the app trash. Returns the trash entry id.
+#[tauri::command]
+async fn delete_model(app_handle: tauri::AppHandle, model_filename: String, modelDirectory: String) -> Result<String, AppError> {
+    path_policy::validate_model_filename(&model_filename)?;
+    let model_path = PathBuf::from(&modelDirectory).join(&model_filename);
+    
+    if !model_path.exists() {
+        return Err("Model file not found".into());
+    }
+    let model_path = path_policy(&app_handle)?.check(&model_path.to_string_lossy())?;
+
+    let download_base = get_download_base(&app_handle)?;
+    let entry = trash::move_to_trash(
+        &download_base,
+        &model_path,
+        trash::TrashKind::Model,
+        None,
+        Vec::new(),
+    )?;
+    purge_expired_trash(&app_handle);
+
+    Ok(entry.id)
+}
+
+#[tauri::command]
+async fn list_separation_models(app_handle: tauri::AppHandle) -> Result<Vec<ModelInfo>, AppError> {
+    // Alias for list_audio_separator_models for frontend compatibility
+    list_audio_separator_models(app_handle).await
+}
+
+#[tauri::command]
+async fn perform_audio_separation(
+    app_handle: tauri::AppHandle,
+    inputFile: String,
+    settings: SeparationSettings,
+    selectedStems: Vec<String>,
+) -> Result<SeparationResult, AppError> {
+    let started = std::time::Instant::now();
+    let result = engine::separate(
+        &engine(&app_handle),
+        &app_handle.state::<LibraryState>().0,
+        inputFile.clone(),
+        settings,
+        selectedStems,
+    );
+
+    let (outcome, stems) = match &result {
+        Ok(r) => (
+            notifications::JobOutcome::Completed,
+            r.output_files.iter().map(PathBuf::from).collect(),
+        ),
+        Err(e) => (notifications::JobOutcome::Failed(e.message.clone()), Vec::new()),
+    };
+    notify_job(
+        &app_handle,
+        notifications::JobNotice {
+            kind: notifications::JobKind::Separation,
+            outcome,
+            title: engine::track_title(&app_handle.state::<LibraryState>().0, Path::new(&inputFile)),
+            elapsed: started.elapsed(),
+            stems,
+        },
+    );
+    result
+}
+
+/// Post a desktop notification for a finished job, if settings allow it. Runs on
+/// its own thread since a notification with an action blocks until dismissed.
+fn notify_job(app_handle: &tauri::AppHandle, notice: notifications::JobNotice) {
+    let settings = notifications::NotificationSettings::from_settings_value(&read_saved_settings(app_handle));
+    let focused = app_handle
+        .get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false);
+    if !settings.allows(&notice, focused) {
+        return;
+    }
+
+    let handle = app_handle.clone();
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let runner = engine(app_handle).runner;
+    std::thread::spawn(move || {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        match notifications::show_with_notify_send(runner.as_ref(), &notice) {
+            Ok(copy_requested) => {
+                if copy_requested {
+                    match clipboard::copy_files(runner.as_ref(), &notice.stems) {
+                        Ok(message) => log_info!("{}", message),
+                        Err(e) => log_warn!("Failed to copy stems from notification: {}", e),
+                    }
+                }
+                return;
+            }
+            Err(e) => log_warn!("Falling back to notification plugin: {}", e),
+        }
+
+        use tauri_plugin_notification::NotificationExt;
+        if let Err(e) = handle
+            .notification()
+            .builder()
+            .title(notice.summary())
+            .body(notice.body())
+            .show()
+        {
+            log_warn!("Failed to show notification: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+async fn detect_gpu_capabilities(app_handle: tauri::AppHandle) -> Result<GPUInfo, AppError> {
+    #[cfg(target_os = "macos")]
+    {
+        // Nothing to probe; the backend follows from the architecture
+        let _ = app_handle;
+        if std::env::consts::ARCH == "aarch64" {
+            // Apple Silicon - check if Metal is available
+            return Ok(GPUInfo {
+                gpu_type: "mps".to_string(),
+                is_available: true,
+                description: "Apple Metal Performance Shaders (MPS) - Best for Apple Silicon Macs".to_string(),
+            });
+        } else {
+            // Intel Mac - Metal might be available but less reliable
+            return Ok(GPUInfo {
+                gpu_type: "metal".to_string(),
+                is_available: true,
+                description: "Apple Metal - Available on Intel Macs but may be slower".to_string(),
+            });
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let runner = engine(&app_handle).runner;
+        // Check for CUDA
+        if let Ok(output) = runner.output(&ProcessSpec::new("nvidia-smi")) {
+            if output.status.success() {
+                return Ok(GPUInfo {
+                    gpu_type: "cuda".to_string(),
+                    is_available: true,
+                    description: "NVIDIA CUDA GPU acceleration available".to_string(),
+                });
+            }
+        }
+        
+        // Check for DirectML (Windows ML)
+        return Ok(GPUInfo {
+            gpu_type: "directml".to_string(),
+            is_available: true,
+            description: "DirectML acceleration available on Windows".to_string(),
+        });
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let runner = engine(&app_handle).runner;
+        // Check for CUDA
+        if let Ok(output) = runner.output(&ProcessSpec::new("nvidia-smi")) {
+            if output.status.success() {
+                return Ok(GPUInfo {
+                    gpu_type: "cuda".to_string(),
+                    is_available: true,
+                    description: "NVIDIA CUDA GPU acceleration available".to_string(),
+                });
+            }
+        }
+        
+        // Check for ROCm (AMD)
+        if let Ok(output) = runner.output(&ProcessSpec::new("rocm-smi")) {
+            if output.status.success() {
+                return Ok(GPUInfo {
+                    gpu_type: "rocm".to_string(),
+                    is_available: true,
+                    description: "AMD ROCm GPU acceleration available".to_string(),
+                });
+            }
+        }
+        
+        return Ok(GPUInfo {
+            gpu_type: "cpu".to_string(),
+            is_available: false,
+            description: "CPU processing - no GPU acceleration detected".to_string(),
+        });
+    }
+
+    // Fallback for unknown platforms
+    Ok(GPUInfo {
+        gpu_type: "cpu".to_string(),
+        is_available: false,
+        description: "CPU processing - platform not supported".to_string(),
+    })
+}
+
+fn model_filenames(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .filter_map(|p| p.file_name().and_then(|n| n.to_str()))
+        .filter(|name| engine::is_model_filename(name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Bring the library up to date with a change in the managed folders and tell the frontend.
+fn handle_history_changes(app_handle: &tauri::AppHandle, download_base: &Path, changes: watcher::SnapshotDiff) -> Result<(), String> {
+    let scan = history_scan_options(app_handle);
+    let state = app_handle.state::<LibraryState>();
+    // Resolve ids before the scan relocates renamed files
+    let removed: Vec<String> = {
+        let lib = state.0.lock().map_err(|_| "Library lock poisoned".to_string())?;
+        changes
+            .removed
+            .iter()
+            .filter_map(|path| lib.file_by_path(path).map(|f| f.id.clone()))
+            .collect()
+    };
+    let (_, items) = history::lock_and_collect(&state.0, download_base, &scan)?;
+
+    let pick = |paths: &[PathBuf]| -> Vec<AudioFileInfoFrontend> {
+        items
+            .iter()
+            .filter(|item| paths.iter().any(|p| Path::new(&item.file_path) == p))
+            .cloned()
+            .collect()
+    };
+    let mut added = pick(&changes.added);
+    let mut modified = pick(&changes.modified);
+    enrich_with_probes(app_handle, &mut added);
+    enrich_with_probes(app_handle, &mut modified);
+    // Partial downloads, sidecar files and the like
+    if added.is_empty() && modified.is_empty() && removed.is_empty() {
+        return Ok(());
+    }
+
+    log_info!(
+        "History changed on disk: {} added, {} modified, {} removed",
+        added.len(),
+        modified.len(),
+        removed.len()
+    );
+    app_handle
+        .emit("history-changed", HistoryChangedEvent { added, modified, removed })
+        .map_err(|e| format!("Failed to emit history-changed: {}", e))
+}
+
+/// Watch the managed download folder and the model directory, pushing debounced
+/// changes to the frontend as `history-changed` / `models-changed`. The folders are
+/// only snapshotted after a change notification, every `WATCH_INTERVAL` until they
+/// settle; folders that can't be watched are polled at that interval instead.
+fn start_watcher(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut events = watcher::EventWatcher::new()
+            .map_err(|e| log_warn!("{}; polling the managed folders instead", e))
+            .ok();
+        let mut history_watch = watcher::DebouncedWatch::new();
+        let mut model_watch = watcher::DebouncedWatch::new();
+        let mut watched: Option<(PathBuf, PathBuf)> = None;
+        let (mut history_dirty, mut model_dirty) = (true, true);
+
+        loop {
+            let (Ok(download_base), Ok(model_dir)) = (get_download_base(&app_handle), get_model_directory(&app_handle)) else {
+                std::thread::sleep(WATCH_INTERVAL);
+                continue;
+            };
+
+            // Folders moved in settings: start over from a fresh baseline
+            let current = (download_base.clone(), model_dir.clone());
+            if watched.as_ref() != Some(&current) {
+                history_watch.reset();
+                model_watch.reset();
+                history_dirty = true;
+                model_dirty = true;
+                watched = Some(current);
+            }
+            if let Some(events) = events.as_mut() {
+                events.watch(&[(download_base.clone(), true), (model_dir.clone(), false)]);
+            }
+
+            let polled = |dir: &Path| events.as_ref().is_none_or(|events| !events.is_watched(dir));
+            let (history_polled, model_polled) = (polled(&download_base), polled(&model_dir));
+            let idle = !history_polled && !model_polled && !history_dirty && !model_dirty;
+            let changed = match events.as_ref() {
+                Some(events) if idle => events.wait(WATCH_IDLE_INTERVAL),
+                _ => {
+                    std::thread::sleep(WATCH_INTERVAL);
+                    events.as_ref().map(|events| events.wait(std::time::Duration::ZERO)).unwrap_or_default()
+                }
+            };
+            history_dirty |= history_polled || changed.iter().any(|path| path.starts_with(&download_base));
+            model_dirty |= model_polled || changed.iter().any(|path| path.parent() == Some(model_dir.as_path()));
+
+            if history_dirty {
+                let history_dirs = [output::downloads_dir(&download_base), output::separated_dir(&download_base)];
+                let depth = history_scan_options(&app_handle).max_depth + 1;
+                if let Some(changes) = history_watch.poll(watcher::snapshot(&history_dirs, depth)) {
+                    if let Err(e) = handle_history_changes(&app_handle, &download_base, changes) {
+                        log_warn!("Failed to update history from watcher: {}", e);
+                    }
+                }
+                history_dirty = !history_watch.is_settled();
+            }
+
+            if model_dirty {
+                if let Some(changes) = model_watch.poll(watcher::snapshot(&[model_dir], 1)) {
+                    let event = ModelsChangedEvent {
+                        added: model_filenames(&changes.added),
+                        removed: model_filenames(&changes.removed),
+                    };
+                    if !event.added.is_empty() || !event.removed.is_empty() {
+                        log_info!("Models changed on disk: {} added, {} removed", event.added.len(), event.removed.len());
+                        let _ = app_handle.emit("models-changed", event);
+                    }
+                }
+                model_dirty = !model_watch.is_settled();
+            }
+        }
+    });
+}
+
+#[tauri::command]
+fn list_hot_folders(app_handle: tauri::AppHandle) -> Result<Vec<hot_folder::HotFolder>, AppError> {
+    let state = app_handle.state::<HotFolderState>();
+    let store = state.0.lock().map_err(|_| "Hot folder lock poisoned".to_string())?;
+    Ok(store.list().to_vec())
+}
+
+/// Ask for a folder to watch in the native folder dialog. Its files are separated
+/// with `settings`, into a mirror of the folder under `Separated/<folder name>`.
+/// The folder is never taken from the webview, since the watcher moves its files.
+#[tauri::command]
+async fn add_hot_folder(
+    app_handle: tauri::AppHandle,
+    settings: SeparationSettings,
+    stableSeconds: Option<u64>,
+) -> Result<hot_folder::HotFolder, AppError> {
+    let watched = FileDialog::new()
+        .set_title("Select Hot Folder")
+        .pick_folder()
+        .ok_or_else(|| AppError::cancelled("No folder selected"))?;
+    let name = watched
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Hot Folder".to_string());
+    let output_dir = output::separated_dir(&get_download_base(&app_handle)?).join(name);
+    let folder = hot_folder::HotFolder {
+        id: library::new_id(),
+        path: watched.to_string_lossy().to_string(),
+        output_dir: output_dir.to_string_lossy().to_string(),
+        profile: serde_json::to_value(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?,
+        stable_seconds: stableSeconds.unwrap_or(hot_folder::DEFAULT_STABLE_SECONDS),
+        enabled: true,
+        created_at: library::now_secs(),
+    };
+
+    let state = app_handle.state::<HotFolderState>();
+    let mut store = state.0.lock().map_err(|_| "Hot folder lock poisoned".to_string())?;
+    store.add(folder.clone())?;
+    log_info!("Watching hot folder: {} -> {}", folder.path, folder.output_dir);
+    Ok(folder)
+}
+
+#[tauri::command]
+fn remove_hot_folder(app_handle: tauri::AppHandle, id: String) -> Result<(), AppError> {
+    let state = app_handle.state::<HotFolderState>();
+    let mut store = state.0.lock().map_err(|_| "Hot folder lock poisoned".to_string())?;
+    let removed = store.remove(&id)?;
+    log_info!("Stopped watching hot folder: {}", removed.path);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_hot_folder_enabled(app_handle: tauri::AppHandle, id: String, enabled: bool) -> Result<hot_folder::HotFolder, AppError> {
+    let state = app_handle.state::<HotFolderState>();
+    let mut store = state.0.lock().map_err(|_| "Hot folder lock poisoned".to_string())?;
+    Ok(store.set_enabled(&id, enabled)?)
+}
+
+/// Separate one hot folder file and move it to `done` or `failed`.
+async fn process_hot_folder_file(app_handle: tauri::AppHandle, folder: hot_folder::HotFolder, file: PathBuf) {
+    let emit = |status: &str, output_files: Vec<String>, error: Option<String>| {
+        let _ = app_handle.emit(
+            "hot-folder-job",
+            HotFolderJobEvent {
+                folder_id: folder.id.clone(),
+                file: file.to_string_lossy().to_string(),
+                status: status.to_string(),
+                output_files,
+                error,
+            },
+        );
+    };
+    log_info!("Hot folder file ready: {:?}", file);
+    emit("processing", Vec::new(), None);
+
+    let result = match serde_json::from_value::<SeparationSettings>(folder.profile.clone()) {
+        Ok(mut settings) => {
+            settings.output_dir = folder.output_dir_for(&file).to_string_lossy().to_string();
+            perform_audio_separation(app_handle.clone(), file.to_string_lossy().to_string(), settings, Vec::new()).await
+        }
+        Err(e) => Err(AppError::invalid_input(format!("Invalid hot folder settings: {}", e))),
+    };
+
+    let succeeded = result.is_ok();
+    match hot_folder::move_processed(Path::new(&folder.path), &file, succeeded) {
+        Ok(moved) => {
+            // Keep the library pointing at the source so its stems stay linked
+            let state = app_handle.state::<LibraryState>();
+            if let Ok(mut lib) = state.0.lock() {
+                if let Some(id) = lib.file_by_path(&file).map(|f| f.id.clone()) {
+                    if let Err(e) = lib.relocate(&id, &moved) {
+                        log_warn!("Failed to update library after move: {}", e);
+                    }
+                }
+            };
+        }
+        Err(e) => log_warn!("Failed to move processed hot folder file: {}", e),
+    }
+
+    match result {
+        Ok(r) => emit("done", r.output_files, None),
+        Err(e) => {
+            log_error!("Hot folder separation failed for {:?}: {}", file, e);
+            emit("failed", Vec::new(), Some(e.message));
+        }
+    }
+}
+
+/// Poll the hot folders and separate files once they stop changing. Files are
+/// processed one at a time, in the order they became ready, each as a task on the
+/// async runtime so the polling thread never waits on a separation.
+fn start_hot_folder_worker(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let mut trackers: std::collections::HashMap<String, hot_folder::StabilityTracker> =
+            std::collections::HashMap::new();
+        let mut queue: std::collections::VecDeque<(hot_folder::HotFolder, PathBuf)> = std::collections::VecDeque::new();
+        // The file being separated, until its task finishes
+        let in_flight: std::sync::Arc<Mutex<Option<PathBuf>>> = Default::default();
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let folders = match app_handle.state::<HotFolderState>().0.lock() {
+                Ok(store) => store.list().to_vec(),
+                Err(_) => continue,
+            };
+            // Removed or paused folders start from scratch when watched again
+            let watching = |id: &str| folders.iter().any(|f| f.id == id && f.enabled);
+            trackers.retain(|id, _| watching(id));
+            queue.retain(|(folder, _)| watching(&folder.id));
+
+            let scan = history_scan_options(&app_handle);
+            for folder in folders.iter().filter(|f| f.enabled) {
+                let pending = hot_folder::pending_files(Path::new(&folder.path), |p| scan.accepts(p));
+                let ready = trackers.entry(folder.id.clone()).or_default().ready(
+                    &pending,
+                    std::time::Instant::now(),
+                    std::time::Duration::from_secs(folder.stable_seconds),
+                );
+                for file in ready {
+                    // Handed out again after a pause while it is still queued or being separated
+                    let busy = in_flight.lock().is_ok_and(|current| current.as_deref() == Some(file.as_path()));
+                    if !busy && !queue.iter().any(|(_, queued)| queued == &file) {
+                        queue.push_back((folder.clone(), file));
+                    }
+                }
+            }
+
+            let Ok(mut current) = in_flight.lock() else { continue };
+            if current.is_some() {
+                continue;
+            }
+            if let Some((folder, file)) = queue.pop_front() {
+                *current = Some(file.clone());
+                let (app_handle, in_flight) = (app_handle.clone(), in_flight.clone());
+                tauri::async_runtime::spawn(async move {
+                    process_hot_folder_file(app_handle, folder, file).await;
+                    if let Ok(mut current) = in_flight.lock() {
+                        *current = None;
+                    }
+                });
+            }
+        }
+    });
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            let log_dir = app.path().app_log_dir()?;
+            if let Err(e) = logging::init(&log_dir) {
+                log_warn!("Logging to files is unavailable: {}", e);
+            }
+            let log_handle = app.handle().clone();
+            logging::set_listener(move |entry| {
+                let _ = log_handle.emit("log-entry", entry);
+            });
+
+            // Binaries are resolved on-demand; only the library needs loading
+            let app_dir = app.path().app_data_dir()?;
+            let library = Library::open(app_dir.join("library.jsonl"))?;
+            app.manage(LibraryState(Mutex::new(library)));
+            app.manage(ProbeState(Mutex::new(ProbeCache::open(app_dir.join("probe_cache.json")))));
+            app.manage(PickedFiles(Mutex::new(std::collections::HashSet::new())));
+            app.manage(HotFolderState(Mutex::new(hot_folder::HotFolderStore::open(
+                app_dir.join("hot_folders.json"),
+            ))));
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                purge_expired_trash(&handle);
+                run_saved_cleanup(&handle);
+            });
+            start_watcher(app.handle().clone());
+            start_hot_folder_worker(app.handle().clone());
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            load_settings,
+            save_settings,
+            select_folder,
+            select_file,
+            detect_input_type,
+            fetch_video_info,
+            get_local_file_info,
+            unified_download,
+            get_audio_file_history,
+            query_audio_file_history,
+            add_history_tag,
+            remove_history_tag,
+            set_history_favorite,
+            set_history_note,
+            get_history_item,
+            get_stems_for_track,
+            rerun_separation,
+            get_recent_logs,
+            get_job_log,
+            export_diagnostics,
+            open_in_explorer,
+            open_files_in_explorer,
+            stop_download,
+            copy_audio_file_to_clipboard,
+            copy_files_to_clipboard,
+            delete_file,
+            restore_deleted,
+            list_trash,
+            empty_trash,
+            get_storage_report,
+            run_cleanup,
+            export_library,
+            list_hot_folders,
+            add_hot_folder,
+            remove_hot_folder,
+            set_hot_folder_enabled,
+            list_audio_separator_models,
+            list_downloaded_models,
+            list_separation_models,
+            download_audio_separator_model,
+            delete_model,
+            perform_audio_separation,
+            detect_gpu_capabilities
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+
+
+
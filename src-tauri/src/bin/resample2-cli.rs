@@ -0,0 +1,256 @@
+// Headless front end to the Resample2 core, for build boxes and SSH sessions.
+//
+// Uses the same settings.json and library as the desktop app. Every subcommand
+// prints its result as JSON on stdout; progress and tool logs go to stderr so the
+// output can be piped straight into `jq`.
+
+use clap::{Parser, Subcommand};
+use Resample2_lib::engine::{self, Engine, InputType};
+use Resample2_lib::error::AppError;
+use Resample2_lib::{history, job_log, library, trash};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Parser)]
+#[command(name = "resample2-cli", version, about = "Download and separate audio without the Resample2 window")]
+struct Cli {
+    /// Folder holding settings.json and the library (defaults to the desktop app's)
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Download a YouTube or Spotify URL into the Downloads folder
+    Download {
+        url: String,
+        /// Start of the section to keep, in seconds
+        #[arg(long)]
+        start: Option<f64>,
+        /// End of the section to keep, in seconds
+        #[arg(long)]
+        end: Option<f64>,
+    },
+    /// Show metadata for a URL or the audio properties of a local file
+    Info { input: String },
+    /// Separate a file into stems using the saved separation settings
+    Separate {
+        file: PathBuf,
+        /// Model filename, overriding the saved one
+        #[arg(long)]
+        model: Option<String>,
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        /// Output format, e.g. wav or flac
+        #[arg(long)]
+        format: Option<String>,
+        /// Only write this stem
+        #[arg(long)]
+        stem: Option<String>,
+    },
+    /// List, download or delete separation models
+    Models {
+        #[command(subcommand)]
+        command: ModelCommands,
+    },
+    /// List downloads and stems, newest first
+    History {
+        /// Match against name, source title, uploader, tags and note
+        #[arg(long)]
+        search: Option<String>,
+        /// "downloads" or "separated"
+        #[arg(long)]
+        kind: Option<String>,
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+    },
+    /// Show the command lines, output and exit codes of a download or separation
+    JobLog { job_id: String },
+}
+
+#[derive(Subcommand)]
+enum ModelCommands {
+    /// List models audio-separator knows about
+    List {
+        /// Only models present in the model directory
+        #[arg(long)]
+        downloaded: bool,
+    },
+    Download { filename: String },
+    /// Move a model file to the app trash
+    Delete { filename: String },
+}
+
+/// The folder containing the bundled `resources/` directory: `RESAMPLE2_RESOURCE_DIR`,
+/// then the install layouts of each platform, then the source tree for dev builds.
+fn resource_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("RESAMPLE2_RESOURCE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    let candidates = [
+        exe_dir.clone(),
+        exe_dir.join("../Resources"),
+        exe_dir.join("../lib/Resample2"),
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")),
+    ];
+    candidates.into_iter().find(|dir| dir.join("resources").is_dir())
+}
+
+fn open_library(engine: &Engine) -> Result<Mutex<library::Library>, AppError> {
+    std::fs::create_dir_all(&engine.app_data_dir)
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    Ok(library::Library::open(engine.library_path()).map(Mutex::new)?)
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<Value, AppError> {
+    Ok(serde_json::to_value(value).map_err(|e| format!("Failed to serialize result: {}", e))?)
+}
+
+fn print_progress(progress: f64, message: &str, status: &str) {
+    eprintln!("[{}] {:>5.1}% {}", status.to_uppercase(), progress, message);
+}
+
+fn download(engine: &Engine, url: String, start: Option<f64>, end: Option<f64>) -> Result<Value, AppError> {
+    let input_type = match engine::detect_input_type(url.clone())? {
+        InputType::YouTube => InputType::YouTube,
+        InputType::Spotify => InputType::Spotify,
+        _ => return Err(AppError::invalid_input(format!("Not a YouTube or Spotify URL: {}", url))),
+    };
+    let library = open_library(engine)?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start runtime: {}", e))?;
+    let result = runtime.block_on(engine::download(engine, &library, url, input_type, start, end, &print_progress))?;
+    to_json(result)
+}
+
+fn info(engine: &Engine, input: String) -> Result<Value, AppError> {
+    if Path::new(&input).exists() {
+        let ffprobe = engine.ffprobe_binary()?;
+        let properties = Resample2_lib::probe::probe_file(engine.runner.as_ref(), &ffprobe, Path::new(&input))?;
+        let mut value = to_json(engine::local_file_info(engine, input)?)?;
+        value["properties"] = to_json(properties)?;
+        return Ok(value);
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start runtime: {}", e))?;
+    to_json(runtime.block_on(engine::fetch_video_info(engine, input))?)
+}
+
+fn separate(
+    engine: &Engine,
+    file: PathBuf,
+    model: Option<String>,
+    output_dir: Option<PathBuf>,
+    format: Option<String>,
+    stem: Option<String>,
+) -> Result<Value, AppError> {
+    if !file.is_file() {
+        return Err(AppError::not_found(file.to_string_lossy()));
+    }
+    let mut settings = engine.separation_settings()?;
+    if let Some(model) = model {
+        settings.model_filename = model;
+    }
+    if let Some(dir) = output_dir {
+        settings.output_dir = dir.to_string_lossy().to_string();
+    }
+    if let Some(format) = format {
+        settings.output_format = format.to_uppercase();
+    }
+    if settings.model_file_dir.is_empty() {
+        settings.model_file_dir = engine.model_directory()?.to_string_lossy().to_string();
+    }
+    let selected_stems = stem.iter().cloned().collect();
+    settings.single_stem = stem;
+
+    let library = open_library(engine)?;
+    let input = std::fs::canonicalize(&file)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .to_string();
+    to_json(engine::separate(engine, &library, input, settings, selected_stems)?)
+}
+
+fn models(engine: &Engine, command: ModelCommands) -> Result<Value, AppError> {
+    let model_dir = engine.model_directory()?;
+    match command {
+        ModelCommands::List { downloaded: true } => to_json(engine::list_downloaded_models(&model_dir)?),
+        ModelCommands::List { downloaded: false } => to_json(engine::list_models(engine)?),
+        ModelCommands::Download { filename } => {
+            std::fs::create_dir_all(&model_dir).map_err(|e| format!("Failed to create model directory: {}", e))?;
+            engine::download_model(engine, &filename, &model_dir)?;
+            Ok(json!({ "filename": filename, "path": model_dir.join(&filename) }))
+        }
+        ModelCommands::Delete { filename } => {
+            if !engine::is_model_filename(&filename) || filename.contains(['/', '\\']) {
+                return Err(AppError::invalid_model_filename(filename));
+            }
+            let model_path = model_dir.join(&filename);
+            if !model_path.exists() {
+                return Err(AppError::not_found(model_path.to_string_lossy()));
+            }
+            let entry = trash::move_to_trash(
+                &engine.download_base()?,
+                &model_path,
+                trash::TrashKind::Model,
+                None,
+                Vec::new(),
+            )?;
+            to_json(entry)
+        }
+    }
+}
+
+fn history(engine: &Engine, search: Option<String>, kind: Option<String>, limit: usize) -> Result<Value, AppError> {
+    let library = open_library(engine)?;
+    let mut lib = library.lock().map_err(|_| "Library lock poisoned".to_string())?;
+    let items = history::collect_history(&mut lib, &engine.download_base()?, &engine.history_scan_options())?;
+    let query = history::HistoryQuery {
+        text: search,
+        directory_type: kind,
+        limit,
+        ..Default::default()
+    };
+    to_json(history::query_history(&lib, items, &query)?)
+}
+
+fn run(cli: Cli) -> Result<Value, AppError> {
+    let data_dir = match cli.data_dir {
+        Some(dir) => dir,
+        None => Engine::default_app_data_dir()?,
+    };
+    let engine = Engine::new(data_dir, resource_dir());
+    match cli.command {
+        Commands::Download { url, start, end } => download(&engine, url, start, end),
+        Commands::Info { input } => info(&engine, input),
+        Commands::Separate { file, model, output_dir, format, stem } => {
+            separate(&engine, file, model, output_dir, format, stem)
+        }
+        Commands::Models { command } => models(&engine, command),
+        Commands::History { search, kind, limit } => history(&engine, search, kind, limit),
+        Commands::JobLog { job_id } => to_json(job_log::load(&engine.job_log_dir(), &job_id)?),
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let (value, code) = match run(cli) {
+        Ok(value) => (value, 0),
+        Err(e) => (json!({ "error": e }), 1),
+    };
+    let text = serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string());
+    let mut out = std::io::stdout().lock();
+    let _ = writeln!(out, "{}", text);
+    let _ = out.flush();
+    std::process::exit(code);
+}
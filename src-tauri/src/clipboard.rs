@@ -0,0 +1,462 @@
+// Putting files on the system clipboard for pasting into a DAW or file manager.
+//
+// macOS and Windows have one native way to do this. On Linux every format is
+// offered at once, so each application takes the one it reads: `text/uri-list`
+// (DAWs, KDE and GTK 4 file managers), `x-special/gnome-copied-files` (GTK 3 file
+// managers such as Nemo and Caja) and the plain paths as text. The app owns the
+// selection itself and serves it from a background thread: over the Wayland
+// data-control protocol where the compositor has it, otherwise through X11 (or
+// XWayland). When neither works, `wl-copy`/`xclip` put the URI list alone on the
+// clipboard, or `xsel` the plain paths; the result then names the one format set.
+
+use crate::process::ProcessRunner;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use crate::process::ProcessSpec;
+#[cfg(not(target_os = "windows"))]
+use std::path::Path;
+use std::path::PathBuf;
+
+/// `file://` URI with everything but unreserved characters and `/` percent-encoded.
+#[cfg(not(target_os = "windows"))]
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+fn describe(paths: &[PathBuf]) -> String {
+    if paths.len() == 1 { "file".to_string() } else { format!("{} files", paths.len()) }
+}
+
+/// Put `paths` on the clipboard. Returns a message naming the method that worked.
+#[cfg(target_os = "macos")]
+pub fn copy_files(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> Result<String, String> {
+    // Method 1: AppKit pasteboard via JavaScript for Automation, which takes a list of file URLs
+    let urls = paths
+        .iter()
+        .map(|p| {
+            let quoted = serde_json::to_string(&p.to_string_lossy()).unwrap_or_default();
+            format!("urls.addObject($.NSURL.fileURLWithPath({}));", quoted)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let script = format!(
+        "ObjC.import('AppKit');\nvar pb = $.NSPasteboard.generalPasteboard;\npb.clearContents;\nvar urls = $.NSMutableArray.array;\n{}\npb.writeObjects(urls);",
+        urls
+    );
+    let output = runner
+        .output(&ProcessSpec::new("osascript").args(["-l", "JavaScript", "-e", &script]))
+        .map_err(|e| format!("Failed to copy file to clipboard: {}", e))?;
+    if output.status.success() {
+        return Ok(format!("Copied {} to clipboard", describe(paths)));
+    }
+
+    // Method 2: Fallback to copying file URLs via pbcopy
+    let text = paths.iter().map(|p| file_uri(p)).collect::<Vec<_>>().join("\n");
+    let fallback = runner
+        .output(&ProcessSpec::new("pbcopy").stdin(text))
+        .map_err(|e| format!("Failed to execute pbcopy: {}", e))?;
+    if fallback.status.success() {
+        Ok(format!("Copied {} as URL text to clipboard (pbcopy)", describe(paths)))
+    } else {
+        Err("Failed to copy file to clipboard".into())
+    }
+}
+
+/// Put `paths` on the clipboard. Returns a message naming the method that worked.
+#[cfg(target_os = "windows")]
+pub fn copy_files(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> Result<String, String> {
+    let quoted = paths
+        .iter()
+        // Canonical paths carry a \\?\ prefix PowerShell doesn't need
+        .map(|p| format!("'{}'", p.to_string_lossy().trim_start_matches(r"\\?\").replace("'", "''")))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    // Method 1: PowerShell Set-Clipboard -Path with a list of files
+    let script = format!("Set-Clipboard -Path {}", quoted);
+    let output = runner
+        .output(&ProcessSpec::new("powershell").args(["-Command", &script]))
+        .map_err(|e| format!("Failed to execute PowerShell: {}", e))?;
+    if output.status.success() {
+        return Ok(format!("Copied {} to clipboard", describe(paths)));
+    }
+
+    // Method 2: Alternative pipeline fallback
+    let alt = format!("Get-Item {} | Set-Clipboard", quoted);
+    let alt_output = runner
+        .output(&ProcessSpec::new("powershell").args(["-Command", &alt]))
+        .map_err(|e| format!("Failed to execute PowerShell fallback: {}", e))?;
+    if alt_output.status.success() {
+        Ok(format!("Copied {} to clipboard", describe(paths)))
+    } else {
+        Err("Failed to copy file to clipboard".into())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod linux {
+    use super::{describe, file_uri};
+    use crate::process::{ProcessRunner, ProcessSpec};
+    use std::path::PathBuf;
+
+    const URI_LIST: &str = "text/uri-list";
+    const GNOME_COPIED_FILES: &str = "x-special/gnome-copied-files";
+    const PLAIN_TEXT: &str = "text/plain;charset=utf-8";
+
+    /// Every format `paths` are offered in, as (MIME type, content).
+    fn file_formats(paths: &[PathBuf]) -> Vec<(&'static str, String)> {
+        let uris: Vec<String> = paths.iter().map(|p| file_uri(p)).collect();
+        vec![
+            // text/uri-list lines are CRLF separated, per RFC 2483
+            (URI_LIST, uris.join("\r\n")),
+            (GNOME_COPIED_FILES, format!("copy\n{}", uris.join("\n"))),
+            (PLAIN_TEXT, plain_paths(paths)),
+        ]
+    }
+
+    fn plain_paths(paths: &[PathBuf]) -> String {
+        paths.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>().join("\n")
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Session {
+        Wayland,
+        X11,
+        Unknown,
+    }
+
+    pub fn detect_session() -> Session {
+        let has = |var: &str| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+        let session_type = std::env::var("XDG_SESSION_TYPE").unwrap_or_default().to_lowercase();
+        if has("WAYLAND_DISPLAY") || session_type == "wayland" {
+            Session::Wayland
+        } else if has("DISPLAY") || session_type == "x11" {
+            Session::X11
+        } else {
+            Session::Unknown
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Tool {
+        WlCopy,
+        Xclip,
+        Xsel,
+    }
+
+    impl Tool {
+        fn binary(self) -> &'static str {
+            match self {
+                Tool::WlCopy => "wl-copy",
+                Tool::Xclip => "xclip",
+                Tool::Xsel => "xsel",
+            }
+        }
+
+        /// xsel can only set plain text
+        fn supports_mime(self) -> bool {
+            self != Tool::Xsel
+        }
+
+        fn command(self, mime: Option<&str>) -> ProcessSpec {
+            let spec = ProcessSpec::new(self.binary());
+            match (self, mime) {
+                (Tool::WlCopy, Some(mime)) => spec.args(["--type", mime]),
+                (Tool::WlCopy, None) => spec,
+                (Tool::Xclip, Some(mime)) => spec.args(["-selection", "clipboard", "-t", mime]),
+                (Tool::Xclip, None) => spec.args(["-selection", "clipboard"]),
+                (Tool::Xsel, _) => spec.args(["--clipboard", "--input"]),
+            }
+        }
+    }
+
+    /// Tools that can reach the clipboard of this session, best first. X11 tools
+    /// also work on Wayland through XWayland when `DISPLAY` is set.
+    fn candidate_tools(session: Session) -> Vec<Tool> {
+        let xwayland = std::env::var_os("DISPLAY").is_some();
+        match session {
+            Session::Wayland if xwayland => vec![Tool::WlCopy, Tool::Xclip, Tool::Xsel],
+            Session::Wayland => vec![Tool::WlCopy],
+            Session::X11 | Session::Unknown => vec![Tool::Xclip, Tool::Xsel],
+        }
+    }
+
+    fn run(runner: &dyn ProcessRunner, tool: Tool, mime: Option<&str>, content: &str) -> Result<(), String> {
+        // The tools keep serving the selection from a background fork, so stdout and
+        // stderr must not be pipes we wait on
+        let spec = tool.command(mime).stdin(content).discard_output();
+        let output = runner
+            .output(&spec)
+            .map_err(|e| format!("Failed to start {}: {}", tool.binary(), e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!("{} failed ({})", tool.binary(), output.status))
+        }
+    }
+
+    pub fn copy_files(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> Result<String, String> {
+        let session = detect_session();
+        let formats = file_formats(paths);
+        let offered = || formats.iter().map(|(mime, _)| *mime).collect::<Vec<_>>().join(", ");
+        let mut errors = Vec::new();
+
+        if session == Session::Wayland {
+            match wayland::offer(&formats) {
+                Ok(()) => {
+                    log_info!("Copied {} to clipboard via Wayland data-control as {}", paths.len(), offered());
+                    return Ok(format!("Copied {} to clipboard ({})", describe(paths), offered()));
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        if std::env::var_os("DISPLAY").is_some_and(|v| !v.is_empty()) {
+            match x11::offer(&formats) {
+                Ok(()) => {
+                    log_info!("Copied {} to clipboard via X11 as {}", paths.len(), offered());
+                    return Ok(format!("Copied {} to clipboard ({})", describe(paths), offered()));
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        log_warn!("Could not own the clipboard ({}); trying the clipboard tools", errors.join("; "));
+        copy_with_tools(runner, session, paths, errors)
+    }
+
+    /// One format through whichever command-line tool is installed.
+    fn copy_with_tools(
+        runner: &dyn ProcessRunner,
+        session: Session,
+        paths: &[PathBuf],
+        mut errors: Vec<String>,
+    ) -> Result<String, String> {
+        let (tools, missing): (Vec<Tool>, Vec<Tool>) =
+            candidate_tools(session).into_iter().partition(|tool| which::which(tool.binary()).is_ok());
+        if tools.is_empty() {
+            let install = match session {
+                Session::Wayland => "install wl-clipboard (wl-copy)",
+                _ => "install xclip or xsel",
+            };
+            return Err(format!(
+                "No clipboard tool found ({} not installed); {}",
+                missing.iter().map(|t| t.binary()).collect::<Vec<_>>().join(", "),
+                install
+            ));
+        }
+
+        let uri_list = paths.iter().map(|p| file_uri(p)).collect::<Vec<_>>().join("\r\n");
+        for tool in tools.iter().filter(|t| t.supports_mime()) {
+            match run(runner, *tool, Some(URI_LIST), &uri_list) {
+                Ok(()) => {
+                    log_info!("Copied {} to clipboard via {} as {}", paths.len(), tool.binary(), URI_LIST);
+                    return Ok(format!(
+                        "Copied {} to clipboard ({}, {} only)",
+                        describe(paths),
+                        tool.binary(),
+                        URI_LIST
+                    ));
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        // Last resort: plain paths, one per line
+        let text = plain_paths(paths);
+        for tool in &tools {
+            match run(runner, *tool, None, &text) {
+                Ok(()) => {
+                    log_info!("Copied {} paths to clipboard as text via {}", paths.len(), tool.binary());
+                    return Ok(format!(
+                        "Copied file path{} to clipboard as text ({})",
+                        if paths.len() == 1 { "" } else { "s" },
+                        tool.binary()
+                    ));
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        Err(format!("Failed to copy file to clipboard: {}", errors.join("; ")))
+    }
+
+    /// Selection ownership over the Wayland data-control protocol (wlroots, KDE).
+    /// GNOME doesn't implement it; there the X11 path goes through XWayland.
+    mod wayland {
+        use wl_clipboard_rs::copy::{MimeSource, MimeType, Options, Source};
+
+        pub fn offer(formats: &[(&'static str, String)]) -> Result<(), String> {
+            let sources = formats
+                .iter()
+                .map(|(mime, content)| MimeSource {
+                    source: Source::Bytes(content.as_bytes().into()),
+                    mime_type: MimeType::Specific(mime.to_string()),
+                })
+                .collect();
+            let mut options = Options::new();
+            // Served from our own thread rather than a forked process
+            options.foreground(true);
+            let prepared = options
+                .prepare_copy_multi(sources)
+                .map_err(|e| format!("Wayland clipboard unavailable: {}", e))?;
+            std::thread::spawn(move || {
+                // Returns once another client takes the clipboard
+                if let Err(e) = prepared.serve() {
+                    log_warn!("Stopped serving the Wayland clipboard: {}", e);
+                }
+            });
+            Ok(())
+        }
+    }
+
+    /// Selection ownership through an unmapped X11 window that answers
+    /// `SelectionRequest`s until another client takes the clipboard.
+    mod x11 {
+        use x11rb::connection::Connection;
+        use x11rb::errors::ReplyOrIdError;
+        use x11rb::protocol::xproto::{
+            Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, SelectionNotifyEvent,
+            SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+        };
+        use x11rb::protocol::Event;
+        use x11rb::rust_connection::RustConnection;
+        use x11rb::wrapper::ConnectionExt as _;
+        use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE};
+
+        struct Owner {
+            conn: RustConnection,
+            window: Window,
+            targets: Atom,
+            /// Target atom -> content. Lists of paths stay far below the maximum
+            /// request size, so INCR transfers are not needed.
+            contents: Vec<(Atom, Vec<u8>)>,
+        }
+
+        fn intern(conn: &RustConnection, name: &str) -> Result<Atom, ReplyOrIdError> {
+            Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+        }
+
+        pub fn offer(formats: &[(&'static str, String)]) -> Result<(), String> {
+            take_clipboard(formats).map_err(|e| format!("X11 clipboard unavailable: {}", e))
+        }
+
+        fn take_clipboard(formats: &[(&'static str, String)]) -> Result<(), Box<dyn std::error::Error>> {
+            let (conn, screen_num) = x11rb::connect(None)?;
+            let screen = &conn.setup().roots[screen_num];
+            let window = conn.generate_id()?;
+            conn.create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                screen.root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                screen.root_visual,
+                &CreateWindowAux::new(),
+            )?;
+            let clipboard = intern(&conn, "CLIPBOARD")?;
+            let targets = intern(&conn, "TARGETS")?;
+            let mut contents = Vec::new();
+            for (mime, content) in formats {
+                contents.push((intern(&conn, mime)?, content.as_bytes().to_vec()));
+                // Older X11 clients ask for text by these names
+                if *mime == super::PLAIN_TEXT {
+                    contents.push((intern(&conn, "UTF8_STRING")?, content.as_bytes().to_vec()));
+                    contents.push((intern(&conn, "text/plain")?, content.as_bytes().to_vec()));
+                }
+            }
+
+            conn.set_selection_owner(window, clipboard, CURRENT_TIME)?;
+            if conn.get_selection_owner(clipboard)?.reply()?.owner != window {
+                return Err("another client kept the clipboard".into());
+            }
+            conn.flush()?;
+
+            let owner = Owner { conn, window, targets, contents };
+            std::thread::spawn(move || owner.serve());
+            Ok(())
+        }
+
+        impl Owner {
+            fn serve(self) {
+                loop {
+                    let result = match self.conn.wait_for_event() {
+                        Ok(Event::SelectionRequest(request)) => self.answer(&request),
+                        Ok(Event::SelectionClear(_)) => break,
+                        Ok(_) => Ok(()),
+                        Err(e) => {
+                            log_warn!("Lost the X11 connection serving the clipboard: {}", e);
+                            return;
+                        }
+                    };
+                    if let Err(e) = result {
+                        log_warn!("Failed to answer a clipboard request: {}", e);
+                    }
+                }
+                let _ = self.conn.destroy_window(self.window);
+                let _ = self.conn.flush();
+            }
+
+            fn answer(&self, request: &SelectionRequestEvent) -> Result<(), ReplyOrIdError> {
+                // Obsolete clients leave the property unset and expect the target's name
+                let property = if request.property == NONE { request.target } else { request.property };
+                let mut answered = property;
+                if request.target == self.targets {
+                    let mut atoms = vec![self.targets];
+                    atoms.extend(self.contents.iter().map(|(atom, _)| *atom));
+                    self.conn
+                        .change_property32(PropMode::REPLACE, request.requestor, property, AtomEnum::ATOM, &atoms)?;
+                } else if let Some((_, content)) = self.contents.iter().find(|(atom, _)| *atom == request.target) {
+                    self.conn
+                        .change_property8(PropMode::REPLACE, request.requestor, property, request.target, content)?;
+                } else {
+                    answered = NONE;
+                }
+                let notify = SelectionNotifyEvent {
+                    response_type: SELECTION_NOTIFY_EVENT,
+                    sequence: 0,
+                    time: request.time,
+                    requestor: request.requestor,
+                    selection: request.selection,
+                    target: request.target,
+                    property: answered,
+                };
+                self.conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+                self.conn.flush()?;
+                Ok(())
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn files_are_offered_as_uri_list_gnome_files_and_text() {
+            let paths = vec![PathBuf::from("/music/a b.wav"), PathBuf::from("/music/c.flac")];
+            let formats = file_formats(&paths);
+            assert_eq!(
+                formats,
+                vec![
+                    (URI_LIST, "file:///music/a%20b.wav\r\nfile:///music/c.flac".to_string()),
+                    (GNOME_COPIED_FILES, "copy\nfile:///music/a%20b.wav\nfile:///music/c.flac".to_string()),
+                    (PLAIN_TEXT, "/music/a b.wav\n/music/c.flac".to_string()),
+                ]
+            );
+        }
+    }
+}
+
+/// Put `paths` on the clipboard. Returns a message naming the method that worked.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn copy_files(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> Result<String, String> {
+    linux::copy_files(runner, paths)
+}
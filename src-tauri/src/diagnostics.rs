@@ -0,0 +1,92 @@
+// Diagnostics bundle for bug reports.
+//
+// A zip holding the log files, settings.json with secrets and the home folder
+// scrubbed out, and a short description of the system. Meant to be attached to
+// an issue as-is, so nothing personal should survive redaction.
+
+use crate::logging;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Settings keys whose values are replaced outright
+const SECRET_KEYS: [&str; 6] = ["token", "key", "secret", "password", "cookie", "auth"];
+const REDACTED: &str = "[redacted]";
+
+fn redact_string(value: &str, home: Option<&str>) -> String {
+    match home {
+        Some(home) if !home.is_empty() => value.replace(home, "~"),
+        _ => value.to_string(),
+    }
+}
+
+fn redact_value(value: &Value, home: Option<&str>) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let lower = key.to_lowercase();
+                    let redacted = if SECRET_KEYS.iter().any(|secret| lower.contains(secret)) && !value.is_null() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact_value(value, home)
+                    };
+                    (key.clone(), redacted)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|item| redact_value(item, home)).collect()),
+        Value::String(s) => Value::String(redact_string(s, home)),
+        other => other.clone(),
+    }
+}
+
+/// `settings` with secret-looking values replaced and the home folder shown as `~`.
+pub fn redact_settings(settings: &Value) -> Value {
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string());
+    redact_value(settings, home.as_deref())
+}
+
+fn system_info() -> Value {
+    json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "created_at": chrono::Local::now().to_rfc3339(),
+    })
+}
+
+/// Write the bundle to `dest`: `logs/*.log` from `log_dir`, `settings.json`
+/// (redacted) and `system.json`.
+pub fn write_bundle(dest: &Path, log_dir: Option<&Path>, settings: &Value) -> Result<(), String> {
+    let file = File::create(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string());
+
+    let mut add = |name: &str, content: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options)
+            .and_then(|_| zip.write_all(content).map_err(Into::into))
+            .map_err(|e| format!("Failed to write {} to the bundle: {}", name, e))
+    };
+
+    let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+    add("settings.json", pretty(&redact_settings(settings)).as_bytes())?;
+    add("system.json", pretty(&system_info()).as_bytes())?;
+    if let Some(dir) = log_dir {
+        for path in logging::log_files(dir) {
+            let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else { continue };
+            let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            // Log messages carry file paths too, JSON-escaped on Windows
+            let escaped_home = home.as_ref().map(|h| json!(h).to_string().trim_matches('"').to_string());
+            let content = redact_string(&redact_string(&content, escaped_home.as_deref()), home.as_deref());
+            add(&format!("logs/{}", name), content.as_bytes())?;
+        }
+    }
+
+    zip.finish().map_err(|e| format!("Failed to finish the bundle: {}", e))?;
+    Ok(())
+}
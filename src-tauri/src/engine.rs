@@ -0,0 +1,2191 @@
+// Tauri-free core shared by the desktop app and `resample2-cli`.
+//
+// Everything that needs to run without a window lives here: locating settings
+// and bundled tools, probing inputs, downloading with yt-dlp, managing models
+// and running audio-separator. Callers pass an `Engine` for paths and report
+// progress through callbacks; the Tauri commands in `app.rs` are thin wrappers.
+
+use crate::error::{classify_tool_output, AppError, ErrorCode, Tool};
+use crate::job_log::{self, JobKind, JobLog, RunRecord};
+use crate::history;
+use crate::library::{self, Library};
+use crate::output;
+use crate::path_policy;
+use crate::process::{LineEvent, ProcessOutput, ProcessRunner, ProcessSpec, Stream, SystemRunner};
+use crate::trash;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Bundle identifier; the app data dir is named after it.
+pub const APP_IDENTIFIER: &str = "com.Resample2.app";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoInfo {
+    pub title: String,
+    pub duration: Option<f64>,
+    pub thumbnail: Option<String>,
+    pub uploader: Option<String>,
+    pub view_count: Option<u64>,
+    pub video_url: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadResult {
+    pub success: bool,
+    pub message: String,
+    pub file_path: Option<String>,
+    /// Library source id, also attached to the download's log entries
+    #[serde(default)]
+    pub job_id: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum InputType {
+    #[serde(rename = "YouTube")]
+    YouTube,
+    #[serde(rename = "Spotify")]
+    Spotify,
+    #[serde(rename = "LocalFile")]
+    LocalFile,
+    #[serde(rename = "Unknown")]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub filename: String,
+    pub arch: String,
+    pub output_stems: String,
+    pub friendly_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadedModel {
+    pub filename: String,
+    pub friendly_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeparationSettings {
+    pub model_filename: String,
+    pub output_format: String,
+    pub output_dir: String,
+    pub model_file_dir: String,
+    pub normalization: f64,
+    pub amplification: f64,
+    pub single_stem: Option<String>,
+    pub sample_rate: u32,
+    pub use_autocast: bool,
+    pub use_gpu: bool,
+    pub gpu_type: String,
+    pub mdx_segment_size: u32,
+    pub mdx_overlap: f64,
+    pub mdx_batch_size: u32,
+    pub mdx_enable_denoise: bool,
+    pub vr_batch_size: u32,
+    pub vr_window_size: u32,
+    pub vr_aggression: u32,
+    pub vr_enable_tta: bool,
+    pub vr_high_end_process: bool,
+    pub vr_enable_post_process: bool,
+    pub vr_post_process_threshold: f64,
+    pub demucs_segment_size: String,
+    pub demucs_shifts: u32,
+    pub demucs_overlap: f64,
+    pub demucs_segments_enabled: bool,
+    pub mdxc_segment_size: u32,
+    pub mdxc_override_model_segment_size: bool,
+    pub mdxc_overlap: u32,
+    pub mdxc_batch_size: u32,
+    pub mdxc_pitch_shift: i32,
+    /// Used when `output_dir` is empty
+    #[serde(default)]
+    pub output_location: output::OutputLocation,
+    /// e.g. `{title}/{stem}`; empty keeps audio-separator's own names
+    #[serde(default)]
+    pub output_name_template: String,
+    /// After running out of memory, try again with smaller batches and then on the CPU
+    #[serde(default = "default_retry_on_out_of_memory")]
+    pub retry_on_out_of_memory: bool,
+}
+
+fn default_retry_on_out_of_memory() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeparationResult {
+    pub success: bool,
+    pub message: String,
+    pub output_files: Vec<String>,
+    /// Library job id, also attached to the job's log entries
+    #[serde(default)]
+    pub job_id: Option<String>,
+}
+
+/// yt-dlp related options from settings.json. Missing keys fall back to the
+/// same values `load_settings` writes as defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadSettings {
+    pub audio_format: String,
+    pub audio_quality: String,
+    pub write_subtitles: bool,
+    pub write_thumbnail: bool,
+    pub write_description: bool,
+    pub write_info: bool,
+    pub write_comments: bool,
+    pub write_automatic_subtitles: bool,
+    pub write_manual_subtitles: bool,
+    pub retries: u32,
+    pub fragment_retries: u32,
+    pub file_access_retries: u32,
+    pub concurrent_fragments: u32,
+}
+
+impl Default for DownloadSettings {
+    fn default() -> Self {
+        Self {
+            audio_format: "mp3".to_string(),
+            audio_quality: "0".to_string(),
+            write_subtitles: false,
+            write_thumbnail: false,
+            write_description: false,
+            write_info: false,
+            write_comments: false,
+            write_automatic_subtitles: false,
+            write_manual_subtitles: false,
+            retries: 10,
+            fragment_retries: 10,
+            file_access_retries: 3,
+            concurrent_fragments: 1,
+        }
+    }
+}
+
+/// `val[key]` as a `T`, or `None` if it is missing or has the wrong type.
+fn setting<T: serde::de::DeserializeOwned>(val: &Value, key: &str) -> Option<T> {
+    val.get(key).and_then(|v| T::deserialize(v).ok())
+}
+
+impl DownloadSettings {
+    /// Each field is read on its own, so one bad value only resets that field.
+    pub fn from_settings_value(val: &Value) -> Self {
+        let defaults = Self::default();
+        Self {
+            audio_format: setting(val, "audio_format").unwrap_or(defaults.audio_format),
+            audio_quality: setting(val, "audio_quality").unwrap_or(defaults.audio_quality),
+            write_subtitles: setting(val, "write_subtitles").unwrap_or(defaults.write_subtitles),
+            write_thumbnail: setting(val, "write_thumbnail").unwrap_or(defaults.write_thumbnail),
+            write_description: setting(val, "write_description").unwrap_or(defaults.write_description),
+            write_info: setting(val, "write_info").unwrap_or(defaults.write_info),
+            write_comments: setting(val, "write_comments").unwrap_or(defaults.write_comments),
+            write_automatic_subtitles: setting(val, "write_automatic_subtitles")
+                .unwrap_or(defaults.write_automatic_subtitles),
+            write_manual_subtitles: setting(val, "write_manual_subtitles").unwrap_or(defaults.write_manual_subtitles),
+            retries: setting(val, "retries").unwrap_or(defaults.retries),
+            fragment_retries: setting(val, "fragment_retries").unwrap_or(defaults.fragment_retries),
+            file_access_retries: setting(val, "file_access_retries").unwrap_or(defaults.file_access_retries),
+            concurrent_fragments: setting(val, "concurrent_fragments").unwrap_or(defaults.concurrent_fragments),
+        }
+    }
+}
+
+/// Where settings, the library and bundled tools live, and how the tools are run.
+#[derive(Clone)]
+pub struct Engine {
+    /// Holds settings.json, library.jsonl and the other app state files
+    pub app_data_dir: PathBuf,
+    /// Folder containing the bundled `resources/` directory, if known
+    pub resource_dir: Option<PathBuf>,
+    pub runner: Arc<dyn ProcessRunner>,
+}
+
+impl Engine {
+    pub fn new(app_data_dir: PathBuf, resource_dir: Option<PathBuf>) -> Self {
+        Engine {
+            app_data_dir,
+            resource_dir,
+            runner: Arc::new(SystemRunner),
+        }
+    }
+
+    /// Run tools through `runner`, e.g. a `FakeRunner` replaying recorded output.
+    pub fn with_runner(mut self, runner: Arc<dyn ProcessRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    /// The data dir the desktop app uses, so both share one settings file and library.
+    pub fn default_app_data_dir() -> Result<PathBuf, String> {
+        dirs::data_dir()
+            .map(|dir| dir.join(APP_IDENTIFIER))
+            .ok_or_else(|| "Failed to get data directory".to_string())
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.app_data_dir.join("settings.json")
+    }
+
+    pub fn library_path(&self) -> PathBuf {
+        self.app_data_dir.join("library.jsonl")
+    }
+
+    pub fn job_log_dir(&self) -> PathBuf {
+        self.app_data_dir.join("job_logs")
+    }
+
+    /// The saved settings.json as JSON, or `Value::Null` if there is none yet.
+    pub fn settings(&self) -> Value {
+        fs::read_to_string(self.settings_path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .unwrap_or(Value::Null)
+    }
+
+    /// Validate and write settings.json. A changed `download_path` or
+    /// `model_directory` must pass `path_policy::check_managed_root`, since file
+    /// operations are allowed anywhere under them.
+    pub fn save_settings(&self, settings: &str) -> Result<(), AppError> {
+        let value: Value = serde_json::from_str(settings).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let folders = [("download_path", self.download_base()?), ("model_directory", self.model_directory()?)];
+        for (key, current) in folders {
+            // An empty model directory means the default one
+            let Some(path) = value.get(key).and_then(|v| v.as_str()).filter(|p| !p.is_empty()) else {
+                continue;
+            };
+            if Path::new(path) != current {
+                path_policy::check_managed_root(path)?;
+            }
+        }
+
+        fs::create_dir_all(&self.app_data_dir).map_err(|e| format!("Failed to create app directory: {}", e))?;
+        fs::write(self.settings_path(), settings).map_err(|e| format!("Failed to write settings file: {}", e))?;
+        Ok(())
+    }
+
+    /// Resolve the download base folder from saved settings, falling back to the default.
+    pub fn download_base(&self) -> Result<PathBuf, String> {
+        let mut download_base = get_default_download_path()?;
+        if let Some(s) = self.settings().get("download_path").and_then(|v| v.as_str()) {
+            download_base = s.to_string();
+        }
+        Ok(PathBuf::from(download_base))
+    }
+
+    pub fn model_directory(&self) -> Result<PathBuf, String> {
+        let mut model_dir = get_default_model_directory()?;
+        if let Some(s) = self
+            .settings()
+            .get("model_directory")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+        {
+            model_dir = s.to_string();
+        }
+        Ok(PathBuf::from(model_dir))
+    }
+
+    pub fn history_scan_options(&self) -> history::HistoryScanOptions {
+        history::HistoryScanOptions::from_settings_value(&self.settings())
+    }
+
+    pub fn trash_retention_days(&self) -> u64 {
+        self.settings()
+            .get("trash_retention_days")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(trash::DEFAULT_RETENTION_DAYS)
+    }
+
+    /// The saved `separation_settings`, if they parse.
+    pub fn separation_settings(&self) -> Result<SeparationSettings, String> {
+        let value = self
+            .settings()
+            .get("separation_settings")
+            .cloned()
+            .unwrap_or_else(|| default_settings()["separation_settings"].clone());
+        serde_json::from_value(value).map_err(|e| format!("Failed to read separation settings: {}", e))
+    }
+
+    fn resource(&self, relative: &str) -> Option<PathBuf> {
+        self.resource_dir
+            .as_ref()
+            .map(|dir| dir.join(relative))
+            .filter(|path| path.exists())
+    }
+
+    // Optimized binary resolution functions
+    fn resolve_ytdlp_binary(&self) -> Option<PathBuf> {
+        // Try PATH first for maximum performance
+        if which::which("yt-dlp").is_ok() {
+            log_info!("yt-dlp found in PATH - using system version");
+            return Some(PathBuf::from("yt-dlp"));
+        }
+
+        // Fallback to bundled standalone script
+        let script_name = if cfg!(target_os = "windows") {
+            "yt-dlp-standalone.bat"
+        } else {
+            "yt-dlp-standalone.sh"
+        };
+
+        if let Some(resolved_path) = self.resource(&format!("resources/{}", script_name)) {
+            log_info!("Using bundled yt-dlp script: {:?}", resolved_path);
+            return Some(resolved_path);
+        }
+
+        log_error!("yt-dlp binary not found in PATH or bundled resources");
+        None
+    }
+
+    fn resolve_ff_binary(&self, bin_name: &str) -> Option<PathBuf> {
+        // Try PATH first for maximum performance
+        if which::which(bin_name).is_ok() {
+            log_info!("{} found in PATH - using system version", bin_name);
+            return Some(PathBuf::from(bin_name));
+        }
+
+        // Fallback to bundled binary
+        let resource_path = if cfg!(target_os = "windows") {
+            format!("resources/ffmpeg/{}.exe", bin_name)
+        } else {
+            format!("resources/ffmpeg/{}", bin_name)
+        };
+
+        if let Some(resolved_path) = self.resource(&resource_path) {
+            log_info!("Using bundled {}: {:?}", bin_name, resolved_path);
+            return Some(resolved_path);
+        }
+
+        log_error!("{} binary not found in PATH or bundled resources", bin_name);
+        None
+    }
+
+    fn resolve_audio_separator_binary(&self) -> Option<PathBuf> {
+        // Try PATH first for maximum performance
+        if which::which("audio-separator").is_ok() {
+            log_info!("audio-separator found in PATH - using system version");
+            return Some(PathBuf::from("audio-separator"));
+        }
+
+        // Fallback to bundled standalone script
+        let script_name = if cfg!(target_os = "windows") {
+            "audio-separator-standalone.bat"
+        } else {
+            "audio-separator-standalone.sh"
+        };
+
+        if let Some(resolved_path) = self.resource(&format!("resources/{}", script_name)) {
+            log_info!("Using bundled audio-separator script: {:?}", resolved_path);
+            return Some(resolved_path);
+        }
+
+        log_error!("audio-separator binary not found in PATH or bundled resources");
+        None
+    }
+
+    pub fn ytdlp_binary(&self) -> Result<PathBuf, AppError> {
+        self.resolve_ytdlp_binary()
+            .ok_or_else(|| AppError::tool_missing(Tool::YtDlp))
+    }
+
+    pub fn ffmpeg_binary(&self) -> Result<PathBuf, AppError> {
+        self.resolve_ff_binary("ffmpeg")
+            .ok_or_else(|| AppError::tool_missing(Tool::Ffmpeg))
+    }
+
+    pub fn ffprobe_binary(&self) -> Result<PathBuf, AppError> {
+        self.resolve_ff_binary("ffprobe")
+            .ok_or_else(|| AppError::tool_missing(Tool::Ffprobe))
+    }
+
+    pub fn audio_separator_binary(&self) -> Result<PathBuf, AppError> {
+        self.resolve_audio_separator_binary()
+            .ok_or_else(|| AppError::tool_missing(Tool::AudioSeparator))
+    }
+}
+
+fn get_documents_dir() -> Result<PathBuf, String> {
+    let docs_dir = dirs::home_dir()
+        .ok_or_else(|| "Failed to get home directory".to_string())?
+        .join("Documents");
+
+    // Try to canonicalize, but if it fails, just use the path as is
+    Ok(docs_dir.canonicalize().unwrap_or(docs_dir))
+}
+
+fn get_default_download_path() -> Result<String, String> {
+    let docs_dir = get_documents_dir()?;
+    let resample2_dir = docs_dir.join("Resample2");
+    Ok(resample2_dir.to_string_lossy().to_string())
+}
+
+fn get_default_model_directory() -> Result<String, String> {
+    let docs_dir = get_documents_dir()?;
+    let models_dir = docs_dir.join("Resample2").join("Models");
+    Ok(models_dir.to_string_lossy().to_string())
+}
+
+/// The settings.json written for a fresh install.
+pub fn default_settings_json() -> String {
+    let download_path =
+        get_default_download_path().unwrap_or_else(|_| "Documents/Resample2".to_string());
+    let model_directory = get_default_model_directory()
+        .unwrap_or_else(|_| "Documents/Resample2/Models".to_string());
+
+    format!(
+        r#"{{
+        "theme": "system",
+        "download_path": {},
+        "audio_format": "mp3",
+        "audio_quality": "0",
+        "write_subtitles": false,
+        "write_thumbnail": false,
+        "write_description": false,
+        "write_info": false,
+        "write_comments": false,
+        "write_automatic_subtitles": false,
+        "write_manual_subtitles": false,
+        "retries": 10,
+        "fragment_retries": 10,
+        "file_access_retries": 3,
+        "concurrent_fragments": 1,
+        "separation_settings": {{
+            "model_filename": "model_bs_roformer_ep_317_sdr_12.9755.ckpt",
+            "output_format": "WAV",
+            "output_dir": "",
+            "model_file_dir": "",
+            "normalization": 0.9,
+            "amplification": 0.0,
+            "sample_rate": 44100,
+            "use_autocast": false,
+            "use_gpu": true,
+            "gpu_type": "auto",
+            "mdx_segment_size": 256,
+            "mdx_overlap": 0.25,
+            "mdx_batch_size": 1,
+            "mdx_enable_denoise": false,
+            "vr_batch_size": 1,
+            "vr_window_size": 512,
+            "vr_aggression": 5,
+            "vr_enable_tta": false,
+            "vr_high_end_process": false,
+            "vr_enable_post_process": false,
+            "vr_post_process_threshold": 0.2,
+            "demucs_segment_size": "Default",
+            "demucs_shifts": 2,
+            "demucs_overlap": 0.25,
+            "demucs_segments_enabled": true,
+            "mdxc_segment_size": 256,
+            "mdxc_override_model_segment_size": false,
+            "mdxc_overlap": 8,
+            "mdxc_batch_size": 1,
+            "mdxc_pitch_shift": 0,
+            "output_location": "managed",
+            "output_name_template": "",
+            "retry_on_out_of_memory": true
+        }},
+        "model_directory": {},
+        "enable_stem_extraction": false,
+        "trash_retention_days": 30,
+        "history_scan_depth": 2,
+        "history_extensions": ["mp3", "wav", "flac", "m4a", "aac", "ogg", "opus", "aiff", "aif", "wma"],
+        "cleanup_policy": {{
+            "keep_days": null,
+            "max_total_gb": null,
+            "keep_favorites": true,
+            "delete_downloads_with_stems": false,
+            "automatic": false
+        }},
+        "notifications": {{
+            "enabled": true,
+            "only_when_unfocused": false,
+            "downloads": true,
+            "separations": true,
+            "failures": true
+        }}
+    }}"#,
+        json_string(&download_path), json_string(&model_directory)
+    )
+}
+
+pub fn default_settings() -> Value {
+    serde_json::from_str(&default_settings_json()).unwrap_or(Value::Null)
+}
+
+/// A JSON string literal, so Windows paths keep their backslashes escaped.
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn seconds_to_hhmmss(total_seconds: f64) -> String {
+    let secs = total_seconds.max(0.0).floor() as u64;
+    let h = secs / 3600;
+    let m = (secs % 3600) / 60;
+    let s = secs % 60;
+    format!("{:02}:{:02}:{:02}", h, m, s)
+}
+
+/// Set by the app's stop button so the failure that follows is reported as a
+/// cancellation, and no further attempts are made
+pub static DOWNLOAD_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// How often a quiet yt-dlp download checks whether it was cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Pauses before each new attempt at a yt-dlp call that failed with HTTP 429.
+#[cfg(not(test))]
+const RATE_LIMIT_BACKOFF: [Duration; 3] = [Duration::from_secs(5), Duration::from_secs(20), Duration::from_secs(60)];
+#[cfg(test)]
+const RATE_LIMIT_BACKOFF: [Duration; 3] = [Duration::from_millis(1); 3];
+
+/// Sleep for `delay`, waking every `CANCEL_POLL_INTERVAL` to check `cancelled`.
+/// Returns false if the wait was cut short.
+async fn sleep_unless_cancelled(delay: Duration, cancelled: &AtomicBool) -> bool {
+    let deadline = Instant::now() + delay;
+    loop {
+        if cancelled.load(Ordering::SeqCst) {
+            return false;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        tokio::time::sleep(remaining.min(CANCEL_POLL_INTERVAL)).await;
+    }
+}
+
+/// Build the yt-dlp argv (without the binary) for a download into `downloads_dir`.
+pub fn build_download_args(
+    settings: &DownloadSettings,
+    downloads_dir: &Path,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    input: &str,
+) -> Vec<String> {
+    let mut args = Vec::new();
+
+    // Audio extraction
+    args.push("--extract-audio".to_string());
+    args.push("--audio-format".to_string());
+    args.push(settings.audio_format.clone());
+    args.push("--audio-quality".to_string());
+    args.push(if settings.audio_quality.trim().is_empty() {
+        "0".to_string() // Best quality
+    } else {
+        settings.audio_quality.trim().to_string()
+    });
+
+    // Output location
+    args.push("-o".to_string());
+    args.push("%(title)s.%(ext)s".to_string());
+    args.push("-P".to_string());
+    args.push(downloads_dir.to_string_lossy().to_string());
+    args.push("--no-playlist".to_string());
+    args.push("--no-warnings".to_string());
+    // Report the final file and its metadata once post-processing is done
+    args.push("--print".to_string());
+    args.push("after_move:%(.{title,uploader,webpage_url,filepath})j".to_string());
+    // --print implies --quiet; keep progress (written to stderr), one line per update
+    args.push("--progress".to_string());
+    args.push("--newline".to_string());
+
+    // Side files written next to the audio
+    if settings.write_thumbnail {
+        args.push("--write-thumbnail".to_string());
+    }
+    if settings.write_info {
+        args.push("--write-info-json".to_string());
+    }
+    if settings.write_description {
+        args.push("--write-description".to_string());
+    }
+    if settings.write_comments {
+        args.push("--write-comments".to_string());
+    }
+    // `write_subtitles` asks for every subtitle track, the other two for one kind.
+    if settings.write_subtitles || settings.write_manual_subtitles {
+        args.push("--write-subs".to_string());
+    }
+    if settings.write_subtitles || settings.write_automatic_subtitles {
+        args.push("--write-auto-subs".to_string());
+    }
+
+    // Network robustness
+    args.push("--retries".to_string());
+    args.push(settings.retries.to_string());
+    args.push("--fragment-retries".to_string());
+    args.push(settings.fragment_retries.to_string());
+    args.push("--file-access-retries".to_string());
+    args.push(settings.file_access_retries.to_string());
+    if settings.concurrent_fragments > 1 {
+        args.push("--concurrent-fragments".to_string());
+        args.push(settings.concurrent_fragments.to_string());
+    }
+
+    // Time range options
+    if let (Some(s), Some(e)) = (start_time, end_time) {
+        if e > s {
+            // yt-dlp section trimming only (avoid extra ffmpeg post-trim to prevent duplicates)
+            args.push("--download-sections".into());
+            args.push(format!("*{}-{}", seconds_to_hhmmss(s), seconds_to_hhmmss(e)));
+        }
+    } else if let Some(s) = start_time {
+        if s >= 0.0 {
+            args.push("--postprocessor-args".into());
+            args.push(format!("ffmpeg:-ss {}", s));
+        }
+    } else if let Some(e) = end_time {
+        if e > 0.0 {
+            args.push("--postprocessor-args".into());
+            args.push(format!("ffmpeg:-t {}", e));
+        }
+    }
+
+    // finally the input URL
+    args.push(input.to_string());
+    args
+}
+
+/// The percentage from a yt-dlp `[download]  42.3% of ...` progress line.
+pub fn parse_download_percent(line: &str) -> Option<f64> {
+    if !line.contains("[download]") {
+        return None;
+    }
+    line.split_whitespace()
+        .find(|s| s.ends_with('%'))
+        .and_then(|s| s.trim_end_matches('%').parse::<f64>().ok())
+}
+
+/// Find the JSON line printed by `--print after_move:...` in yt-dlp's stdout.
+pub fn parse_download_report(stdout: &str) -> Option<Value> {
+    stdout.lines().rev().find_map(|line| {
+        serde_json::from_str::<Value>(line.trim())
+            .ok()
+            .filter(|v| v.get("filepath").is_some())
+    })
+}
+
+
+pub fn detect_input_type(input: String) -> Result<InputType, AppError> {
+    let input_trimmed = input.trim();
+    let input_lower = input_trimmed.to_lowercase();
+    
+    // Check for YouTube URLs
+    if input_lower.contains("youtube.com") || 
+       input_lower.contains("youtu.be") || 
+       input_lower.contains("music.youtube.com") {
+        return Ok(InputType::YouTube);
+    }
+    
+    // Check for Spotify URLs
+    if input_lower.contains("spotify.com") || 
+       input_lower.contains("open.spotify.com") {
+        return Ok(InputType::Spotify);
+    }
+    
+    // Check for local file paths
+    // First, check if it's a URL (starts with http:// or https://)
+    if input_lower.starts_with("http://") || input_lower.starts_with("https://") {
+        // If it's a URL but not YouTube or Spotify, it's Unknown
+        return Ok(InputType::Unknown);
+    }
+    
+    // Check if it's a file path (contains path separators or is an absolute path)
+    if input_trimmed.contains('/') || input_trimmed.contains('\\') || 
+       (input_trimmed.len() > 2 && input_trimmed.chars().nth(1) == Some(':')) || // Windows drive letter
+       input_trimmed.starts_with('.') || // Relative path starting with .
+       PathBuf::from(input_trimmed).exists() { // File actually exists
+        return Ok(InputType::LocalFile);
+    }
+    
+    // Default to Unknown
+    Ok(InputType::Unknown)
+}
+
+pub async fn fetch_video_info(engine: &Engine, url: String) -> Result<VideoInfo, AppError> {
+    if url.trim().is_empty() {
+        return Err(AppError::invalid_input("URL cannot be empty"));
+    }
+
+    // Get yt-dlp binary path
+    let yt_dlp_path = engine.ytdlp_binary()?;
+
+    // Check if it's a Spotify URL
+    if url.contains("spotify.com") || url.contains("open.spotify.com") {
+        return handle_spotify_url(engine.runner.as_ref(), &url, &yt_dlp_path).await;
+    }
+    
+    // Use the cached binary path for fast execution
+    let spec = ProcessSpec::new(yt_dlp_path).args(["--dump-json", "--no-playlist", "--no-warnings", &url]);
+    let mut backoff = RATE_LIMIT_BACKOFF.iter();
+    let output = loop {
+        let output = engine.runner.output(&spec).map_err(|e| AppError::tool_spawn(Tool::YtDlp, e))?;
+        if output.status.success() {
+            break output;
+        }
+        let error = AppError::tool_failed(Tool::YtDlp, &output.stderr);
+        match backoff.next() {
+            Some(delay) if error.code.is_retryable() => {
+                log_warn!("yt-dlp was rate limited, retrying in {}s", delay.as_secs());
+                if !sleep_unless_cancelled(*delay, &DOWNLOAD_CANCELLED).await {
+                    return Err(AppError::cancelled("Lookup cancelled"));
+                }
+            }
+            _ => return Err(error),
+        }
+    };
+
+    // Parse the JSON output
+    let json_value: Value = serde_json::from_str(&output.stdout)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    // Extract video information
+    let title = json_value["title"]
+        .as_str()
+        .unwrap_or("Unknown Title")
+        .to_string();
+
+    let duration = json_value["duration"]
+        .as_f64()
+        .or_else(|| json_value["duration"].as_u64().map(|d| d as f64));
+
+    let thumbnail = json_value["thumbnail"]
+        .as_str()
+        .map(|s| s.to_string());
+
+    let uploader = json_value["uploader"]
+        .as_str()
+        .map(|s| s.to_string());
+
+    let view_count = json_value["view_count"]
+        .as_u64();
+
+    let video_url = json_value["url"]
+        .as_str()
+        .map(|s| s.to_string());
+
+    Ok(VideoInfo {
+        title,
+        duration,
+        thumbnail,
+        uploader,
+        view_count,
+        video_url,
+        error: None,
+    })
+}
+
+async fn handle_spotify_url(runner: &dyn ProcessRunner, spotify_url: &str, yt_dlp_path: &Path) -> Result<VideoInfo, AppError> {
+    // Try to use Spotify oEmbed to get a clean title and artist
+    #[derive(Deserialize)]
+    struct SpotifyOembed {
+        title: Option<String>,
+        author_name: Option<String>,
+    }
+
+    let mut queries: Vec<String> = Vec::new();
+
+    if let Ok(resp) = reqwest::get(format!("https://open.spotify.com/oembed?url={}", spotify_url)).await {
+        if let Ok(oembed) = resp.json::<SpotifyOembed>().await {
+            if let (Some(title), Some(author)) = (oembed.title, oembed.author_name) {
+                queries.push(format!("{} {}", title, author));
+                queries.push(format!("{} {} official audio", title, author));
+                queries.push(format!("{} {} lyrics", title, author));
+                queries.push(format!("{} {} topic", title, author));
+            }
+        }
+    }
+
+    // Fallback: search by extracted Spotify track id patterns if available
+    if let Ok(track_id) = extract_spotify_track_id(spotify_url) {
+        queries.push(track_id.to_string());
+        queries.push(format!("spotify {}", track_id));
+        queries.push(format!("spotify track {}", track_id));
+    }
+
+    if queries.is_empty() {
+        queries.push("spotify track".to_string());
+    }
+
+    for search_query in queries.clone() {
+        let youtube_search_output = runner.output(&ProcessSpec::new(yt_dlp_path).args([
+            format!("ytsearch1:{}", search_query),
+            "--dump-json".to_string(),
+            "--no-playlist".to_string(),
+            "--no-warnings".to_string(),
+        ]));
+
+        if let Ok(output) = youtube_search_output {
+            if output.status.success() {
+                if let Ok(youtube_json) = serde_json::from_str::<Value>(&output.stdout) {
+                    let youtube_title = youtube_json["title"]
+                        .as_str()
+                        .unwrap_or("Spotify Track")
+                        .to_string();
+
+                    let duration = youtube_json["duration"]
+                        .as_f64()
+                        .or_else(|| youtube_json["duration"].as_u64().map(|d| d as f64));
+
+                    let thumbnail = youtube_json["thumbnail"]
+                        .as_str()
+                        .map(|s| s.to_string());
+
+                    let uploader = youtube_json["uploader"]
+                        .as_str()
+                        .map(|s| s.to_string())
+                        .or_else(|| Some("Unknown Artist".to_string()));
+
+                    let view_count = youtube_json["view_count"].as_u64();
+
+                    let video_url = youtube_json["webpage_url"]
+                        .as_str()
+                        .or_else(|| youtube_json["url"].as_str())
+                        .map(|s| s.to_string());
+
+                    return Ok(VideoInfo {
+                        title: youtube_title,
+                        duration,
+                        thumbnail,
+                        uploader,
+                        view_count,
+                        video_url,
+                        error: None,
+                    });
+                }
+            }
+        }
+    }
+
+    // If all searches fail, return a fallback that still allows yt-dlp to search directly
+    Ok(VideoInfo {
+        title: "Spotify Track".to_string(),
+        duration: None,
+        thumbnail: None,
+        uploader: Some("Unknown Artist".to_string()),
+        view_count: None,
+        video_url: queries
+            .first()
+            .map(|q| format!("ytsearch1:{}", q))
+            .or(None),
+        error: None,
+    })
+}
+
+fn extract_spotify_track_id(url: &str) -> Result<String, String> {
+    // Extract track ID from various Spotify URL formats
+    if let Some(track_id) = url.split("track/").nth(1) {
+        // Remove query parameters and other parts
+        let clean_id = track_id.split('?').next().unwrap_or(track_id);
+        Ok(clean_id.to_string())
+    } else {
+        Err("Could not extract track ID from Spotify URL".to_string())
+    }
+}
+
+pub fn local_file_info(engine: &Engine, file_path: String) -> Result<VideoInfo, AppError> {
+    if !PathBuf::from(&file_path).exists() {
+        return Err(AppError::not_found(file_path));
+    }
+
+    // Use ffprobe to get file information
+    let ffprobe_path = engine.ffprobe_binary()?;
+
+    let output = engine
+        .runner
+        .output(&ProcessSpec::new(ffprobe_path).args([
+            "-v", "quiet",
+            "-print_format", "json",
+            "-show_format",
+            "-show_streams",
+            &file_path,
+        ]))
+        .map_err(|e| AppError::tool_spawn(Tool::Ffprobe, e))?;
+
+    if !output.status.success() {
+        return Err(AppError::tool_failed(Tool::Ffprobe, &output.stderr).with_path(file_path));
+    }
+
+    // Parse the JSON output
+    let json_value: Value = serde_json::from_str(&output.stdout)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    // Extract file information
+    let title = PathBuf::from(&file_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Unknown File")
+        .to_string();
+
+    let duration = json_value["format"]["duration"]
+        .as_str()
+        .and_then(|d| d.parse::<f64>().ok());
+
+    let uploader = Some("Local File".to_string());
+
+    Ok(VideoInfo {
+        title,
+        duration,
+        thumbnail: None,
+        uploader,
+        view_count: None,
+        video_url: None,
+        error: None,
+    })
+}
+
+/// Run one yt-dlp download, reporting progress as it is printed, and return its
+/// stdout. The run is added to `log`.
+fn run_ytdlp_download(
+    engine: &Engine,
+    log: &mut JobLog,
+    spec: &ProcessSpec,
+    emit_progress: &(dyn Fn(f64, &str, &str) + Sync),
+) -> Result<String, AppError> {
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let result = stream_ytdlp_download(engine, spec, emit_progress);
+    log.runs.push(RunRecord::new(
+        spec,
+        started_at,
+        timer.elapsed(),
+        result.as_ref().map_err(|e| e.message.as_str()),
+    ));
+    let output = result?;
+
+    if !output.status.success() {
+        log_error!(job = &log.job_id; "yt-dlp failed: {}", output.stderr);
+        return Err(AppError::tool_failed(Tool::YtDlp, &output.stderr));
+    }
+    Ok(output.stdout)
+}
+
+fn stream_ytdlp_download(
+    engine: &Engine,
+    spec: &ProcessSpec,
+    emit_progress: &(dyn Fn(f64, &str, &str) + Sync),
+) -> Result<ProcessOutput, AppError> {
+    let mut process = engine
+        .runner
+        .spawn(spec)
+        .map_err(|e| AppError::tool_spawn(Tool::YtDlp, e))?;
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    loop {
+        if DOWNLOAD_CANCELLED.load(Ordering::SeqCst) {
+            if let Err(e) = process.kill() {
+                log_warn!("Failed to stop yt-dlp: {}", e);
+            }
+            break;
+        }
+        let (stream, line) = match process.poll_line(CANCEL_POLL_INTERVAL) {
+            LineEvent::Line(stream, line) => (stream, line),
+            LineEvent::Idle => continue,
+            LineEvent::Closed => break,
+        };
+        // --print makes yt-dlp quiet, which sends progress lines to stderr. They
+        // are kept out of the captured output so errors are classified on the rest.
+        if let Some(percent) = parse_download_percent(&line) {
+            emit_progress(percent, "Downloading...", "downloading");
+            continue;
+        }
+        let captured = match stream {
+            Stream::Stdout => &mut stdout,
+            Stream::Stderr => &mut stderr,
+        };
+        captured.push_str(&line);
+        captured.push('\n');
+    }
+    let status = process.wait().map_err(|e| format!("Failed to wait for yt-dlp: {}", e))?;
+    Ok(ProcessOutput { status, stdout, stderr })
+}
+
+/// Download `input` into `<download base>/Downloads` and record it in `library`.
+/// `emit_progress` receives (percent, message, status) updates.
+pub async fn download(
+    engine: &Engine,
+    library: &Mutex<Library>,
+    input: String,
+    input_type: InputType,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    emit_progress: &(dyn Fn(f64, &str, &str) + Sync),
+) -> Result<DownloadResult, AppError> {
+    match input_type {
+        InputType::LocalFile => {
+            // For local files, nothing to download. Optionally trim later.
+            emit_progress(100.0, "Local file ready", "completed");
+            Ok(DownloadResult {
+                success: true,
+                message: "Local file is ready".to_string(),
+                file_path: Some(input),
+                job_id: None,
+            })
+        }
+        InputType::YouTube | InputType::Spotify => {
+            // Becomes the source record's id once the download succeeds
+            let job_id = library::new_id();
+            let mut log = JobLog::start(&job_id, JobKind::Download);
+            let result: Result<DownloadResult, AppError> = async {
+                emit_progress(0.0, "Starting download", "downloading");
+
+                // Resolve output directory and download settings from saved settings
+                let download_base = engine.download_base()?;
+                let download_settings = DownloadSettings::from_settings_value(&engine.settings());
+
+                let downloads_dir = output::downloads_dir(&download_base);
+                fs::create_dir_all(&downloads_dir)
+                    .map_err(|e| format!("Failed to create downloads directory: {}", e))?;
+
+                // If Spotify, resolve to a YouTube URL first
+                let mut final_input = input.clone();
+                if matches!(input_type, InputType::Spotify) {
+                    let yt_dlp_path = engine.ytdlp_binary()?;
+                
+                    if let Ok(info) = handle_spotify_url(engine.runner.as_ref(), &input, &yt_dlp_path).await {
+                        if let Some(yurl) = info.video_url {
+                            final_input = yurl;
+                        }
+                    }
+                }
+
+                // Get ffmpeg path for environment setup
+                let ffmpeg_path = engine.ffmpeg_binary()?;
+            
+                // Build yt-dlp command arguments for standalone script
+                let args = build_download_args(
+                    &download_settings,
+                    &downloads_dir,
+                    start_time,
+                    end_time,
+                    &final_input,
+                );
+
+                // Get yt-dlp standalone script path
+                let yt_dlp_path = engine.ytdlp_binary()?;
+                log_info!(job = &job_id; "Using yt-dlp standalone script: {:?}", yt_dlp_path);
+            
+                // Set up environment variables for the standalone script
+                let mut path_var = std::env::var("PATH").unwrap_or_default();
+            
+                // Add ffmpeg to PATH for the standalone script
+                if let Some(ffmpeg_dir) = ffmpeg_path.parent() {
+                    path_var = if cfg!(target_os = "windows") {
+                        format!("{};{}", path_var, ffmpeg_dir.to_string_lossy())
+                    } else {
+                        format!("{}:{}", path_var, ffmpeg_dir.to_string_lossy())
+                    };
+                    log_info!(job = &job_id; "Added ffmpeg to PATH: {}", ffmpeg_dir.to_string_lossy());
+                }
+            
+                // Execute yt-dlp standalone script
+                log_info!(job = &job_id; "Executing yt-dlp with args: {:?}", args);
+            
+                let spec = ProcessSpec::new(&yt_dlp_path)
+                    .args(args)
+                    .env_clear()
+                    .env("PATH", path_var)
+                    .current_dir(&downloads_dir)
+                    .process_group();
+                let mut backoff = RATE_LIMIT_BACKOFF.iter();
+                let stdout = loop {
+                    let error = match run_ytdlp_download(engine, &mut log, &spec, emit_progress) {
+                        Ok(stdout) => break stdout,
+                        Err(error) => error,
+                    };
+                    match backoff.next() {
+                        Some(delay) if error.code.is_retryable() => {
+                            log_warn!(job = &job_id; "yt-dlp was rate limited, retrying in {}s", delay.as_secs());
+                            emit_progress(
+                                0.0,
+                                &format!("Rate limited by the site, retrying in {}s", delay.as_secs()),
+                                "downloading",
+                            );
+                            if !sleep_unless_cancelled(*delay, &DOWNLOAD_CANCELLED).await {
+                                return Err(AppError::cancelled("Download cancelled"));
+                            }
+                        }
+                        _ => {
+                            emit_progress(0.0, &format!("Download failed: {}", error.message), "error");
+                            return Err(error);
+                        }
+                    }
+                };
+                // Download completed successfully
+
+                // Locate the downloaded file, preferring the path yt-dlp reported
+                let report = parse_download_report(&stdout);
+                let reported_path = report
+                    .as_ref()
+                    .and_then(|r| r.get("filepath").and_then(|v| v.as_str()))
+                    .map(PathBuf::from)
+                    .filter(|p| p.exists());
+                let file_path: PathBuf;
+                if let Some(p) = reported_path {
+                    file_path = p;
+                } else {
+                    let files = fs::read_dir(&downloads_dir)
+                        .map_err(|e| format!("Failed to read downloads directory: {}", e))?;
+                    let now = SystemTime::now();
+                    let valid_exts_audio = ["mp3", "m4a", "opus", "wav", "flac", "aac", "ogg"];
+                    let mut newest: Option<(SystemTime, PathBuf)> = None;
+
+                    for entry in files.flatten() {
+                        let path = entry.path();
+                        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+                            if valid_exts_audio.contains(&ext.to_lowercase().as_str()) {
+                                if let Ok(meta) = fs::metadata(&path) {
+                                    if let Ok(created) = meta.created() {
+                                        if let Ok(age) = now.duration_since(created) {
+                                            // consider files created within last 30 seconds
+                                            if age.as_secs() < 30 {
+                                                match &newest {
+                                                    Some((t, _)) if *t >= created => {}
+                                                    _ => newest = Some((created, path.clone())),
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    if let Some((_, p)) = newest {
+                        file_path = p;
+                    } else {
+                        emit_progress(0.0, "Download completed, but file not found", "error");
+                        return Err("Download completed, but could not locate output file".into());
+                    }
+                }
+
+                // Record the source and the downloaded file in the library
+                let field = |key: &str| {
+                    report
+                        .as_ref()
+                        .and_then(|r| r.get(key).and_then(|v| v.as_str()))
+                        .map(|s| s.to_string())
+                };
+                let source = library::SourceRecord {
+                    id: job_id.clone(),
+                    url: input.clone(),
+                    resolved_url: field("webpage_url").or_else(|| Some(final_input.clone())),
+                    title: field("title"),
+                    uploader: field("uploader"),
+                    created_at: library::now_secs(),
+                };
+                let fingerprint = library::Fingerprint::take(&file_path);
+                let recorded = library
+                    .lock()
+                    .map_err(|_| "Library lock poisoned".to_string())
+                    .and_then(|mut lib| record_download(&mut lib, source, &file_path, fingerprint, start_time, end_time));
+                if let Err(e) = recorded {
+                    log_warn!(job = &job_id; "Failed to record download in library: {}", e);
+                }
+
+                emit_progress(100.0, "Download completed", "completed");
+                Ok(DownloadResult {
+                    success: true,
+                    message: "Download completed".to_string(),
+                    file_path: Some(file_path.to_string_lossy().to_string()),
+                    job_id: Some(job_id.clone()),
+                })
+            }
+            .await;
+            save_job_log(engine, log, &result);
+            result.map_err(|e| e.with_job(job_id))
+        }
+        InputType::Unknown => Err(AppError::invalid_input("Unknown input type")),
+    }
+}
+
+pub fn record_download(
+    lib: &mut Library,
+    source: library::SourceRecord,
+    file_path: &Path,
+    fingerprint: library::Fingerprint,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<(), String> {
+    // A re-download to the same path keeps its id and tags so existing jobs stay linked
+    let existing = lib.file_by_path(file_path).cloned();
+    let download = library::FileRecord {
+        id: existing.as_ref().map(|f| f.id.clone()).unwrap_or_else(library::new_id),
+        role: library::FileRole::Download,
+        parent_id: Some(source.id.clone()),
+        path: file_path.to_string_lossy().to_string(),
+        stem: None,
+        start_time,
+        end_time,
+        created_at: library::now_secs(),
+        fingerprinted: fingerprint.stamp,
+        fingerprint: fingerprint.hash,
+        annotations: existing.map(|f| f.annotations).unwrap_or_default(),
+    };
+    lib.put(source.into())?;
+    lib.put(download.into())
+}
+
+/// Every model audio-separator knows about, downloaded or not.
+pub fn list_models(engine: &Engine) -> Result<Vec<ModelInfo>, AppError> {
+    log_info!("Starting model listing...");
+    
+    // Get audio-separator binary path
+    let executable_path = engine.audio_separator_binary()?;
+    log_info!("Using executable: {:?}", executable_path);
+
+    // Test basic functionality first
+    let help_output = engine.runner.output(&ProcessSpec::new(&executable_path).arg("--help"));
+    
+    match help_output {
+        Ok(help) => {
+            if !help.status.success() {
+                log_warn!("--help command failed: {}", help.stderr);
+            } else {
+                log_info!("Executable test successful");
+            }
+        }
+        Err(e) => {
+            log_warn!("Executable test failed: {}", e);
+        }
+    }
+
+    // Execute audio-separator --list_models --list_format json
+    log_info!("Executing --list_models command...");
+    let output = engine
+        .runner
+        .output(&ProcessSpec::new(&executable_path).args(["--list_models", "--list_format", "json"]))
+        .map_err(|e| AppError::tool_spawn(Tool::AudioSeparator, e))?;
+
+    if !output.status.success() {
+        log_error!("Command failed: {}", output.stderr);
+        return Err(AppError::tool_failed(Tool::AudioSeparator, &output.stderr));
+    }
+
+    log_info!("Received {} bytes of model data", output.stdout.len());
+    let models = parse_model_list(&output.stdout)?;
+    log_info!("Successfully parsed {} models", models.len());
+    Ok(models)
+}
+
+/// Parse `audio-separator --list_models --list_format json` output.
+pub fn parse_model_list(json_str: &str) -> Result<Vec<ModelInfo>, String> {
+    let json_value: serde_json::Value = serde_json::from_str(json_str)
+        .map_err(|e| format!("Failed to parse models JSON: {}", e))?;
+
+    let mut models = Vec::new();
+    
+    // Parse the JSON structure (object with architecture keys, each containing model objects)
+    if let Some(json_obj) = json_value.as_object() {
+        log_info!("Found {} architecture categories", json_obj.len());
+        
+        for (architecture, category_data) in json_obj {
+            if let Some(category_obj) = category_data.as_object() {
+                for (model_name, model_data) in category_obj {
+                    if let Some(model_obj) = model_data.as_object() {
+                        if let Some(filename) = model_obj.get("filename").and_then(|v| v.as_str()) {
+                            // Extract stems from the model data
+                            let stems = if let Some(stems_array) = model_obj.get("stems") {
+                                if let Some(stems_vec) = stems_array.as_array() {
+                                    stems_vec.iter()
+                                        .filter_map(|s| s.as_str())
+                                        .collect::<Vec<&str>>()
+                                        .join(", ")
+                                } else {
+                                    "Unknown".to_string()
+                                }
+                            } else {
+                                "Unknown".to_string()
+                            };
+
+                            models.push(ModelInfo {
+                                filename: filename.to_string(),
+                                arch: architecture.to_string(),
+                                output_stems: stems,
+                                friendly_name: model_name.to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    } else {
+        return Err("Invalid JSON format: expected object structure".to_string());
+    }
+    Ok(models)
+}
+
+pub fn list_downloaded_models(model_dir: &Path) -> Result<Vec<DownloadedModel>, AppError> {
+    if !model_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut models = Vec::new();
+    
+    if let Ok(entries) = fs::read_dir(model_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() {
+                if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
+                    // Only include actual model files
+                    if is_model_filename(filename) {
+                        // Create a friendly name by replacing underscores and hyphens with spaces
+                        let friendly_name = filename
+                            .replace("_", " ")
+                            .replace("-", " ")
+                            .replace(".ckpt", "")
+                            .replace(".pth", "")
+                            .replace(".onnx", "")
+                            .replace(".safetensors", "")
+                            .replace(".bin", "");
+                        
+                        models.push(DownloadedModel {
+                            filename: filename.to_string(),
+                                friendly_name,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        
+    Ok(models)
+}
+
+pub fn is_model_filename(filename: &str) -> bool {
+    [".ckpt", ".pth", ".onnx", ".safetensors", ".bin"]
+        .iter()
+        .any(|ext| filename.ends_with(ext))
+}
+
+pub fn download_model(engine: &Engine, model_filename: &str, model_dir: &Path) -> Result<(), AppError> {
+    log_info!("Starting model download: {}", model_filename);
+    
+    // Get audio-separator binary path
+    let executable_path = engine.audio_separator_binary()?;
+
+    // Create model directory if it doesn't exist
+    fs::create_dir_all(model_dir)
+        .map_err(|e| format!("Failed to create model directory: {}", e))?;
+
+    // Get ffmpeg directory for PATH
+    let ffmpeg_dir = engine.ffmpeg_binary()
+        .ok()
+        .and_then(|ffmpeg_path| ffmpeg_path.parent().map(|p| p.to_string_lossy().to_string()));
+
+    // Build command
+    let mut spec = ProcessSpec::new(&executable_path)
+        .args(["--download_model_only", "-m", model_filename, "--model_file_dir"])
+        .arg(model_dir.to_string_lossy())
+        .current_dir(model_dir);
+    
+    // Add ffmpeg to PATH if available
+    if let Some(ffmpeg_path) = ffmpeg_dir {
+        let current_path = std::env::var("PATH").unwrap_or_default();
+        let new_path = if cfg!(target_os = "windows") {
+            format!("{};{}", current_path, ffmpeg_path)
+        } else {
+            format!("{}:{}", current_path, ffmpeg_path)
+        };
+        spec = spec.env("PATH", new_path);
+        log_info!("Added ffmpeg to PATH: {}", ffmpeg_path);
+    }
+
+    // Execute download
+    log_info!("Executing model download...");
+    let output = engine
+        .runner
+        .output(&spec)
+        .map_err(|e| AppError::tool_spawn(Tool::AudioSeparator, e))?;
+
+    if !output.status.success() {
+        log_error!("Model download failed: {}", output.stderr);
+        return Err(AppError::tool_failed(Tool::AudioSeparator, &output.stderr));
+    }
+
+    // Verify the model was downloaded
+    let expected_model_path = model_dir.join(model_filename);
+    if !expected_model_path.exists() {
+        return Err(AppError::new(ErrorCode::ModelMissing, "Model download completed but file not found")
+            .with_path(expected_model_path.to_string_lossy()));
+    }
+
+    log_info!("Model downloaded successfully: {:?}", expected_model_path);
+    Ok(())
+}
+
+/// What to change when audio-separator is run again after running out of memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MemoryFallback {
+    SmallerBatches,
+    /// Smaller batches with CUDA hidden, so torch and ONNX Runtime use the CPU
+    Cpu,
+}
+
+impl MemoryFallback {
+    fn describe(self) -> &'static str {
+        match self {
+            MemoryFallback::SmallerBatches => "smaller batch and segment sizes",
+            MemoryFallback::Cpu => "the CPU",
+        }
+    }
+}
+
+/// Batch and segment sizes for every architecture, small enough for most GPUs.
+/// audio-separator ignores the ones that don't apply to the model.
+const LOW_MEMORY_ARGS: [&str; 12] = [
+    "--mdx_batch_size", "1",
+    "--mdx_segment_size", "64",
+    "--vr_batch_size", "1",
+    "--demucs_segment_size", "10",
+    "--mdxc_batch_size", "1",
+    "--mdxc_segment_size", "64",
+];
+
+/// `spec` with `fallback` applied. The new options go before the input file, which stays last.
+fn with_memory_fallback(spec: &ProcessSpec, fallback: MemoryFallback) -> ProcessSpec {
+    let mut spec = spec.clone();
+    let input = spec.args.pop();
+    spec.args.extend(LOW_MEMORY_ARGS.iter().map(|a| a.to_string()));
+    spec.args.extend(input);
+    if fallback == MemoryFallback::Cpu {
+        spec = spec.env("CUDA_VISIBLE_DEVICES", "");
+    }
+    spec
+}
+
+/// Run `spec` to completion and add the run to `log`.
+fn run_logged(engine: &Engine, log: &mut JobLog, spec: &ProcessSpec) -> Result<ProcessOutput, String> {
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let result = engine.runner.output(spec);
+    log.runs.push(RunRecord::new(spec, started_at, timer.elapsed(), result.as_ref().map_err(String::as_str)));
+    result
+}
+
+/// Stamp `log` with how the job ended and save it for `get_job_log`.
+fn save_job_log<T>(engine: &Engine, mut log: JobLog, result: &Result<T, AppError>) {
+    log.finish(result.as_ref().err());
+    if let Err(e) = job_log::save(&engine.job_log_dir(), &log) {
+        log_warn!(job = &log.job_id; "Failed to save job log: {}", e);
+    }
+}
+
+/// Separate `input_file` with audio-separator, rename the outputs per the naming
+/// template and record the job in `library`. The tool runs are kept in a job log.
+pub fn separate(
+    engine: &Engine,
+    library: &Mutex<Library>,
+    input_file: String,
+    settings: SeparationSettings,
+    selected_stems: Vec<String>,
+) -> Result<SeparationResult, AppError> {
+    let job_id = library::new_id();
+    let mut log = JobLog::start(&job_id, JobKind::Separation);
+    let result = run_separation(engine, library, &job_id, &mut log, input_file, settings, selected_stems);
+    save_job_log(engine, log, &result);
+    result.map_err(|e| e.with_job(job_id))
+}
+
+fn run_separation(
+    engine: &Engine,
+    library: &Mutex<Library>,
+    job_id: &str,
+    log: &mut JobLog,
+    input_file: String,
+    settings: SeparationSettings,
+    selected_stems: Vec<String>,
+) -> Result<SeparationResult, AppError> {
+    log_info!(job = job_id; "Starting audio separation...");
+    log_info!(job = job_id; "Input file: {}", input_file);
+    log_info!(job = job_id; "Model: {}", settings.model_filename);
+    
+    // Get audio-separator binary path
+    let executable_path = engine.audio_separator_binary()?;
+    log_info!(job = job_id; "Using executable: {:?}", executable_path);
+
+    // Create output directory
+    let output_dir = output::resolve_separation_dir(
+        &engine.download_base()?,
+        Path::new(&input_file),
+        &settings.output_dir,
+        settings.output_location,
+    );
+    
+    log_info!(job = job_id; "Output directory: {:?}", output_dir);
+    fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    // Get ffmpeg directory for PATH
+    let ffmpeg_dir = engine.ffmpeg_binary()
+        .ok()
+        .and_then(|ffmpeg_path| ffmpeg_path.parent().map(|p| p.to_string_lossy().to_string()));
+
+    // Build command arguments - MINIMAL for now (only essential parameters)
+    let mut args = Vec::new();
+    
+    // Store essential string values
+    let output_dir_str = output_dir.to_string_lossy().to_string();
+    
+    // Essential arguments only
+    args.push("-m");
+    args.push(&settings.model_filename);
+    args.push("--output_dir");
+    args.push(&output_dir_str);
+
+    // Add single stem if specified
+    if let Some(single_stem) = &settings.single_stem {
+        args.push("--single_stem");
+        args.push(single_stem);
+    }
+
+    // audio-separator writes FLAC unless told otherwise
+    let output_format = match settings.output_format.trim() {
+        "" => "FLAC".to_string(),
+        format => format.to_uppercase(),
+    };
+    args.push("--output_format");
+    args.push(&output_format);
+
+    // Add model file directory if specified
+    if !settings.model_file_dir.is_empty() {
+        args.push("--model_file_dir");
+        args.push(&settings.model_file_dir);
+    }
+
+    // Simplified GPU handling - autocast disabled for safety
+    log_info!(job = job_id; "Using safe GPU settings (autocast disabled)");
+
+    // Architecture-specific parameters are disabled for now - using minimal arguments
+    log_info!(job = job_id; "Using minimal arguments for audio separation");
+    log_info!(job = job_id; "Model: {}", settings.model_filename);
+    log_info!(job = job_id; "Output directory: {}", output_dir_str);
+
+    // Simplified GPU handling - no autocast for safety
+    if settings.use_gpu {
+        log_info!(job = job_id; "GPU acceleration enabled (autocast disabled for safety)");
+    } else {
+        log_info!(job = job_id; "Using CPU processing (safest option)");
+    }
+
+    // Add input file
+    args.push(&input_file);
+
+    // Execute audio-separator with proper environment setup
+    log_info!(job = job_id; "Executing audio-separator with {} arguments", args.len());
+    log_info!(job = job_id; "Command: {} {}", executable_path.display(), args.join(" "));
+    
+    let mut spec = ProcessSpec::new(&executable_path).args(args.iter().copied());
+    
+    // Set working directory
+    spec = spec.current_dir(&output_dir);
+    
+    // Add ffmpeg to PATH if available
+    if let Some(ffmpeg_path) = ffmpeg_dir {
+        let current_path = std::env::var("PATH").unwrap_or_default();
+        let new_path = if cfg!(target_os = "windows") {
+            format!("{};{}", current_path, ffmpeg_path)
+        } else {
+            format!("{}:{}", current_path, ffmpeg_path)
+        };
+        spec = spec.env("PATH", new_path);
+        log_info!(job = job_id; "Added ffmpeg to PATH: {}", ffmpeg_path);
+    }
+
+    // Remember what was already in the output folder so only this run's files are reported
+    let existing_outputs = snapshot_dir(&output_dir);
+
+    // Execute with timeout protection
+    log_info!(job = job_id; "Starting audio separation process...");
+    let start_time = std::time::Instant::now();
+    
+    let mut output =
+        run_logged(engine, log, &spec).map_err(|e| AppError::tool_spawn(Tool::AudioSeparator, e))?;
+
+    // MPS can't be hidden from torch the way CUDA can, so macOS only gets smaller batches
+    let fallbacks: &[MemoryFallback] = if !settings.retry_on_out_of_memory {
+        &[]
+    } else if cfg!(target_os = "macos") {
+        &[MemoryFallback::SmallerBatches]
+    } else {
+        &[MemoryFallback::SmallerBatches, MemoryFallback::Cpu]
+    };
+    let mut used_fallback = None;
+    for &fallback in fallbacks {
+        if output.status.success()
+            || classify_tool_output(Tool::AudioSeparator, &output.stderr) != ErrorCode::OutOfMemory
+        {
+            break;
+        }
+        log_warn!(job = job_id; "audio-separator ran out of memory, retrying with {}", fallback.describe());
+        output = run_logged(engine, log, &with_memory_fallback(&spec, fallback))
+            .map_err(|e| AppError::tool_spawn(Tool::AudioSeparator, e))?;
+        used_fallback = Some(fallback);
+    }
+
+    let duration = start_time.elapsed();
+    log_info!(job = job_id; "Process completed in {:?}", duration);
+    log_info!(job = job_id; "Exit status: {}", output.status);
+    
+    if !output.status.success() {
+        log_error!(job = job_id; "Audio separation failed: {}", output.stderr);
+        return Err(AppError::tool_failed(Tool::AudioSeparator, &output.stderr));
+    }
+    
+    // Find output files
+    log_info!(job = job_id; "Searching for output files in: {:?}", output_dir);
+    let mut output_files: Vec<String> = new_outputs(&output_dir, &output_format.to_lowercase(), &existing_outputs)
+        .iter()
+        .map(|path| {
+            log_info!(job = job_id; "Found output file: {:?}", path);
+            path.to_string_lossy().to_string()
+        })
+        .collect();
+    output_files.sort();
+    // Stems come from audio-separator's names, so read them before renaming
+    let output_stems: Vec<Option<String>> = output_files
+        .iter()
+        .map(|f| library::stem_from_filename(Path::new(f)))
+        .collect();
+
+    if !settings.output_name_template.trim().is_empty() {
+        let context = output_name_context(library, &input_file, &settings);
+        output_files = output::apply_name_template(
+            &engine.download_base()?,
+            &output_dir,
+            &output_files,
+            &settings.output_name_template,
+            &context,
+            library::stem_from_filename,
+        );
+    }
+    
+    log_info!(job = job_id; "Audio separation completed successfully with {} output files", output_files.len());
+
+    // Hashed before locking the library, which scans and the watcher wait on
+    let outputs: Vec<(String, Option<String>, library::Fingerprint)> = output_files
+        .iter()
+        .zip(output_stems)
+        .map(|(output, stem)| (output.clone(), stem, library::Fingerprint::take(Path::new(output))))
+        .collect();
+    let input_path = Path::new(&input_file);
+    let input_fingerprint = library
+        .lock()
+        .is_ok_and(|lib| lib.needs_fingerprint(input_path))
+        .then(|| library::Fingerprint::take(input_path));
+    let trashed = trash::record_ids(&engine.download_base()?);
+    let recorded = library
+        .lock()
+        .map_err(|_| "Library lock poisoned".to_string())
+        .and_then(|mut lib| {
+            let input = lib.ensure_file_with(input_path, library::FileRole::Local, &trashed, input_fingerprint)?;
+            record_separation(&mut lib, job_id, &input.id, &settings, &selected_stems, &output_dir, &outputs)
+        });
+    if let Err(e) = recorded {
+        log_warn!(job = job_id; "Failed to record separation in library: {}", e);
+    }
+    
+    let message = match used_fallback {
+        Some(fallback) => format!(
+            "Audio separation completed successfully after running out of memory, using {}",
+            fallback.describe()
+        ),
+        None => "Audio separation completed successfully".to_string(),
+    };
+    Ok(SeparationResult {
+        success: true,
+        message,
+        output_files,
+        job_id: Some(job_id.to_string()),
+    })
+}
+
+/// The source title for downloads, otherwise the file name without extension.
+pub fn track_title(library: &Mutex<Library>, path: &Path) -> String {
+    let source_title = library.lock().ok().and_then(|lib| {
+        lib.file_by_path(path)
+            .and_then(|f| lib.source_for_file(f))
+            .and_then(|s| s.title.clone())
+    });
+    source_title.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    })
+}
+
+/// Placeholder values for output naming.
+pub fn output_name_context(library: &Mutex<Library>, input_file: &str, settings: &SeparationSettings) -> output::NameContext {
+    let title = track_title(library, Path::new(input_file));
+    let model = Path::new(&settings.model_filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| settings.model_filename.clone());
+
+    output::NameContext {
+        title,
+        model,
+        date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// Modification times of the files currently in `dir`.
+fn snapshot_dir(dir: &Path) -> std::collections::HashMap<PathBuf, Option<SystemTime>> {
+    let mut snapshot = std::collections::HashMap::new();
+    for path in files_under(dir) {
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        snapshot.insert(path, modified);
+    }
+    snapshot
+}
+
+/// Every file under `dir`, in subfolders too.
+fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => files.extend(files_under(&path)),
+            Ok(kind) if kind.is_file() => files.push(path),
+            _ => {}
+        }
+    }
+    files
+}
+
+/// Files with `extension` under `dir` that are new or changed since `before`.
+fn new_outputs(
+    dir: &Path,
+    extension: &str,
+    before: &std::collections::HashMap<PathBuf, Option<SystemTime>>,
+) -> Vec<PathBuf> {
+    files_under(dir)
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case(extension))
+        })
+        .filter(|path| {
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+            before.get(path).is_none_or(|previous| *previous != modified)
+        })
+        .collect()
+}
+
+pub fn record_separation(
+    lib: &mut Library,
+    job_id: &str,
+    input_id: &str,
+    settings: &SeparationSettings,
+    selected_stems: &[String],
+    output_dir: &Path,
+    outputs: &[(String, Option<String>, library::Fingerprint)],
+) -> Result<(), String> {
+    let job = library::JobRecord {
+        id: job_id.to_string(),
+        input_id: input_id.to_string(),
+        model: settings.model_filename.clone(),
+        output_dir: output_dir.to_string_lossy().to_string(),
+        settings: serde_json::to_value(settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?,
+        selected_stems: selected_stems.to_vec(),
+        created_at: library::now_secs(),
+    };
+    lib.put(job.into())?;
+
+    for (output, stem, fingerprint) in outputs {
+        let path = Path::new(output);
+        // Overwritten outputs keep their id and tags but move to the new job
+        let existing = lib.file_by_path(path).cloned();
+        lib.put(
+            library::FileRecord {
+                id: existing.as_ref().map(|f| f.id.clone()).unwrap_or_else(library::new_id),
+                role: library::FileRole::Stem,
+                parent_id: Some(job_id.to_string()),
+                path: output.clone(),
+                stem: stem.clone(),
+                start_time: None,
+                end_time: None,
+                created_at: library::now_secs(),
+                fingerprinted: fingerprint.stamp,
+                fingerprint: fingerprint.hash.clone(),
+                annotations: existing.map(|f| f.annotations).unwrap_or_default(),
+            }
+            .into(),
+        )?;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::stderr_fixture;
+    use crate::process::{FakeRunner, Recording};
+    use serde_json::json;
+
+    /// An engine over fresh temp folders whose tool calls are answered by the
+    /// recordings in `fixtures/recordings/<scenario>`. Returns the temp root too.
+    fn fixture_engine(scenario: &str) -> (Engine, Arc<FakeRunner>, tempfile::TempDir) {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/recordings").join(scenario);
+        test_engine(FakeRunner::load_dir(&fixtures).unwrap())
+    }
+
+    /// yt-dlp runs failing with the recorded stderr of each named category, in order.
+    fn ytdlp_failures(names: &[&str]) -> FakeRunner {
+        FakeRunner::new(
+            names
+                .iter()
+                .map(|name| Recording {
+                    program: "yt-dlp".to_string(),
+                    stderr: stderr_fixture(Tool::YtDlp, name),
+                    exit_code: Some(1),
+                    ..Recording::default()
+                })
+                .collect(),
+        )
+    }
+
+    fn test_engine(runner: FakeRunner) -> (Engine, Arc<FakeRunner>, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let resources = root.join("app").join("resources");
+        fs::create_dir_all(resources.join("ffmpeg")).unwrap();
+        fs::create_dir_all(root.join("data")).unwrap();
+        // Only found when the real tools aren't on PATH; the runner never starts them
+        for tool in [
+            "yt-dlp-standalone.sh",
+            "yt-dlp-standalone.bat",
+            "audio-separator-standalone.sh",
+            "audio-separator-standalone.bat",
+            "ffmpeg/ffmpeg",
+            "ffmpeg/ffmpeg.exe",
+        ] {
+            fs::write(resources.join(tool), "").unwrap();
+        }
+        let settings = json!({ "download_path": root.join("music") });
+        fs::write(root.join("data").join("settings.json"), settings.to_string()).unwrap();
+
+        let runner = Arc::new(runner);
+        let engine = Engine::new(root.join("data"), Some(root.join("app"))).with_runner(runner.clone());
+        (engine, runner, tmp)
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn download_url(engine: &Engine, root: &Path, url: &str) -> Result<DownloadResult, AppError> {
+        let library = Mutex::new(Library::open(root.join("library.jsonl")).unwrap());
+        block_on(download(engine, &library, url.to_string(), InputType::YouTube, None, None, &|_, _, _| {}))
+    }
+
+    fn args_for(settings: &DownloadSettings, start: Option<f64>, end: Option<f64>) -> Vec<String> {
+        build_download_args(settings, Path::new("/music/Downloads"), start, end, "https://youtu.be/abc")
+    }
+
+    /// The values following each occurrence of `flag`.
+    fn values_of<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+        args.windows(2).filter(|w| w[0] == flag).map(|w| w[1].as_str()).collect()
+    }
+
+    #[test]
+    fn default_settings_map_to_audio_extraction() {
+        let args = args_for(&DownloadSettings::default(), None, None);
+        assert_eq!(
+            args,
+            [
+                "--extract-audio",
+                "--audio-format",
+                "mp3",
+                "--audio-quality",
+                "0",
+                "-o",
+                "%(title)s.%(ext)s",
+                "-P",
+                "/music/Downloads",
+                "--no-playlist",
+                "--no-warnings",
+                "--print",
+                "after_move:%(.{title,uploader,webpage_url,filepath})j",
+                "--progress",
+                "--newline",
+                "--retries",
+                "10",
+                "--fragment-retries",
+                "10",
+                "--file-access-retries",
+                "3",
+                "https://youtu.be/abc",
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_map_to_flags() {
+        let settings = DownloadSettings {
+            audio_format: "flac".to_string(),
+            audio_quality: " ".to_string(),
+            write_subtitles: true,
+            write_thumbnail: true,
+            write_description: true,
+            write_info: true,
+            write_comments: true,
+            write_automatic_subtitles: true,
+            write_manual_subtitles: true,
+            retries: 2,
+            fragment_retries: 5,
+            file_access_retries: 1,
+            concurrent_fragments: 4,
+        };
+        let args = args_for(&settings, Some(30.0), Some(90.0));
+        assert_eq!(
+            args,
+            [
+                "--extract-audio",
+                "--audio-format",
+                "flac",
+                "--audio-quality",
+                "0",
+                "-o",
+                "%(title)s.%(ext)s",
+                "-P",
+                "/music/Downloads",
+                "--no-playlist",
+                "--no-warnings",
+                "--print",
+                "after_move:%(.{title,uploader,webpage_url,filepath})j",
+                "--progress",
+                "--newline",
+                "--write-thumbnail",
+                "--write-info-json",
+                "--write-description",
+                "--write-comments",
+                "--write-subs",
+                "--write-auto-subs",
+                "--retries",
+                "2",
+                "--fragment-retries",
+                "5",
+                "--file-access-retries",
+                "1",
+                "--concurrent-fragments",
+                "4",
+                "--download-sections",
+                "*00:00:30-00:01:30",
+                "https://youtu.be/abc",
+            ]
+        );
+    }
+
+    #[test]
+    fn subtitle_settings_pick_their_own_tracks() {
+        let flags = |settings: DownloadSettings| {
+            let args = args_for(&settings, None, None);
+            (args.contains(&"--write-subs".to_string()), args.contains(&"--write-auto-subs".to_string()))
+        };
+        let all = DownloadSettings { write_subtitles: true, ..DownloadSettings::default() };
+        let manual = DownloadSettings { write_manual_subtitles: true, ..DownloadSettings::default() };
+        let automatic = DownloadSettings { write_automatic_subtitles: true, ..DownloadSettings::default() };
+        assert_eq!(flags(all), (true, true));
+        assert_eq!(flags(manual), (true, false));
+        assert_eq!(flags(automatic), (false, true));
+    }
+
+    #[test]
+    fn time_range_maps_to_sections_or_ffmpeg_args() {
+        let settings = DownloadSettings::default();
+        let both = args_for(&settings, Some(30.0), Some(3725.5));
+        assert_eq!(values_of(&both, "--download-sections"), ["*00:00:30-01:02:05"]);
+        assert!(values_of(&both, "--postprocessor-args").is_empty());
+
+        let start = args_for(&settings, Some(12.0), None);
+        assert_eq!(values_of(&start, "--postprocessor-args"), ["ffmpeg:-ss 12"]);
+
+        let end = args_for(&settings, None, Some(45.0));
+        assert_eq!(values_of(&end, "--postprocessor-args"), ["ffmpeg:-t 45"]);
+
+        let backwards = args_for(&settings, Some(60.0), Some(10.0));
+        assert!(values_of(&backwards, "--download-sections").is_empty());
+        assert!(values_of(&backwards, "--postprocessor-args").is_empty());
+    }
+
+    #[test]
+    fn report_is_the_last_json_line_with_a_filepath() {
+        let stdout = "[download]  50.0% of 3.00MiB\n\
+                      {\"title\": \"Song\", \"filepath\": \"/music/Downloads/Song.mp3\"}\n\
+                      [download] 100% of 3.00MiB\n";
+        let report = parse_download_report(stdout).expect("report");
+        assert_eq!(report["filepath"], "/music/Downloads/Song.mp3");
+        assert!(parse_download_report("[download] 100%\n{\"title\": \"no path\"}").is_none());
+    }
+
+    #[test]
+    fn bad_setting_only_resets_its_own_field() {
+        let settings = DownloadSettings::from_settings_value(&json!({
+            "audio_format": "wav",
+            "audio_quality": "3",
+            "write_thumbnail": true,
+            "retries": "lots",
+        }));
+        assert_eq!(settings.audio_format, "wav");
+        assert_eq!(settings.audio_quality, "3");
+        assert!(settings.write_thumbnail);
+        assert_eq!(settings.retries, DownloadSettings::default().retries);
+    }
+
+    #[test]
+    fn progress_fixture_streams_percentages() {
+        let (engine, _runner, _tmp) = fixture_engine("ytdlp-progress");
+        let spec = ProcessSpec::new("yt-dlp").args(args_for(&DownloadSettings::default(), None, None));
+        let percents = Mutex::new(Vec::new());
+        let output = stream_ytdlp_download(&engine, &spec, &|percent, _, _| percents.lock().unwrap().push(percent))
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(percents.into_inner().unwrap(), [0.0, 0.1, 12.5, 47.3, 100.0, 100.0]);
+        // The progress lines came on stderr and aren't kept for error classification
+        assert!(output.stderr.is_empty());
+        let report = parse_download_report(&output.stdout).expect("report");
+        assert_eq!(report["filepath"], "/music/Downloads/Song.mp3");
+    }
+
+    #[test]
+    fn model_list_fixture_parses() {
+        let (engine, runner, _tmp) = fixture_engine("model-list");
+        let models = list_models(&engine).unwrap();
+        assert_eq!(models.len(), 4);
+        let roformer = models
+            .iter()
+            .find(|m| m.filename == "model_bs_roformer_ep_317_sdr_12.9755.ckpt")
+            .expect("roformer");
+        assert_eq!(roformer.arch, "MDXC");
+        assert_eq!(roformer.friendly_name, "Roformer Model: BS-Roformer-Viperx-1297");
+        assert_eq!(roformer.output_stems, "vocals, other");
+        let no_stems = models.iter().find(|m| m.filename == "MDX23C-8KFFT-InstVoc_HQ.ckpt").expect("mdx23c");
+        assert_eq!(no_stems.output_stems, "Unknown");
+        // --help, then --list_models
+        assert_eq!(runner.calls().len(), 2);
+    }
+
+    #[test]
+    fn failed_download_fixture_maps_to_its_error() {
+        let (engine, runner, tmp) = fixture_engine("ytdlp-failure");
+        let root = tmp.path();
+        let error = download_url(&engine, root, "https://www.youtube.com/watch?v=AAAAAAAAAAA").unwrap_err();
+        assert_eq!(error.code, ErrorCode::VideoPrivate);
+        assert!(error.message.contains("Private video"));
+        assert!(error.job_id.is_some());
+        assert_eq!(runner.calls().len(), 1);
+    }
+
+    #[test]
+    fn only_rate_limiting_is_retried() {
+        for name in [
+            "video_private",
+            "age_restricted",
+            "geo_blocked",
+            "members_only",
+            "live_not_finished",
+            "format_unavailable",
+            "extractor_outdated",
+            "video_unavailable",
+            "tool_failed",
+        ] {
+            let (engine, runner, tmp) = test_engine(ytdlp_failures(&[name, name]));
+            let root = tmp.path();
+            let error = download_url(&engine, root, "https://youtu.be/abc").unwrap_err();
+            assert!(!error.code.is_retryable(), "{}", name);
+            assert_eq!(runner.calls().len(), 1, "{} was retried", name);
+        }
+    }
+
+    #[test]
+    fn rate_limited_download_backs_off_then_gives_up() {
+        let (engine, runner, tmp) = test_engine(ytdlp_failures(&["rate_limited"; 5]));
+        let root = tmp.path();
+        let error = download_url(&engine, root, "https://youtu.be/abc").unwrap_err();
+        assert_eq!(error.code, ErrorCode::RateLimited);
+        assert_eq!(runner.calls().len(), 1 + RATE_LIMIT_BACKOFF.len());
+    }
+
+    #[test]
+    fn outputs_are_found_by_the_configured_format_in_subfolders() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("Song")).unwrap();
+        fs::write(dir.join("earlier_(Vocals).m4a"), b"old").unwrap();
+        let before = snapshot_dir(dir);
+
+        fs::write(dir.join("Song").join("Song_(Vocals).m4a"), b"new").unwrap();
+        fs::write(dir.join("Song_(Drums).M4A"), b"new").unwrap();
+        fs::write(dir.join("Song_(Bass).wav"), b"other format").unwrap();
+        let mut found = new_outputs(dir, "m4a", &before);
+        found.sort();
+        assert_eq!(found, [dir.join("Song").join("Song_(Vocals).m4a"), dir.join("Song_(Drums).M4A")]);
+    }
+
+    #[test]
+    fn settings_with_a_hostile_download_path_are_refused() {
+        let (engine, _runner, tmp) = test_engine(FakeRunner::default());
+        let saved = fs::read_to_string(engine.settings_path()).unwrap();
+        for hostile in ["/", "/etc", "relative/music"] {
+            let settings = json!({ "download_path": hostile }).to_string();
+            assert_eq!(engine.save_settings(&settings).unwrap_err().code, ErrorCode::InvalidInput, "{}", hostile);
+        }
+        let settings = json!({ "model_directory": "/" }).to_string();
+        assert_eq!(engine.save_settings(&settings).unwrap_err().code, ErrorCode::InvalidInput);
+        assert_eq!(fs::read_to_string(engine.settings_path()).unwrap(), saved);
+
+        // The current folder is kept even before it exists, a new one must exist
+        engine.save_settings(&saved).unwrap();
+        let new_base = tmp.path().join("elsewhere");
+        let settings = json!({ "download_path": new_base }).to_string();
+        assert!(engine.save_settings(&settings).is_err());
+        fs::create_dir_all(&new_base).unwrap();
+        engine.save_settings(&settings).unwrap();
+        assert_eq!(engine.download_base().unwrap(), new_base);
+    }
+
+    #[test]
+    fn cancelling_cuts_a_backoff_short() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let canceller = {
+            let cancelled = cancelled.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                cancelled.store(true, Ordering::SeqCst);
+            })
+        };
+        let timer = Instant::now();
+        assert!(!block_on(sleep_unless_cancelled(Duration::from_secs(60), &cancelled)));
+        assert!(timer.elapsed() < Duration::from_secs(5));
+        canceller.join().unwrap();
+
+        assert!(block_on(sleep_unless_cancelled(Duration::from_millis(10), &AtomicBool::new(false))));
+    }
+
+    #[test]
+    fn retry_after_rate_limit_reports_the_next_failure() {
+        let (engine, runner, tmp) = test_engine(ytdlp_failures(&["rate_limited", "video_private", "rate_limited"]));
+        let root = tmp.path();
+        let error = download_url(&engine, root, "https://youtu.be/abc").unwrap_err();
+        assert_eq!(error.code, ErrorCode::VideoPrivate);
+        assert_eq!(runner.calls().len(), 2);
+    }
+}
@@ -0,0 +1,613 @@
+// Errors returned to the frontend.
+//
+// Every command fails with an `AppError`: a stable machine-readable `code`, a
+// one-line message for the console, optional details (usually the tail of the
+// tool's stderr), the path involved, and a hint for what the user can do. The UI
+// branches on `code`; the wording of `message` may change at any time.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Anything without a more specific code
+    Failed,
+    InvalidInput,
+    NotFound,
+    OutsideManagedDirectories,
+    InvalidModelFilename,
+    /// A bundled or system tool could not be found or started
+    ToolMissing,
+    /// A tool exited with an error nothing more specific matched
+    ToolFailed,
+    /// The video was removed or is otherwise not downloadable
+    VideoUnavailable,
+    VideoPrivate,
+    /// The video needs a signed-in account old enough to watch it
+    AgeRestricted,
+    /// The video is not available from this country
+    GeoBlocked,
+    /// The video is only for channel members
+    MembersOnly,
+    /// A live stream or premiere that has not finished yet
+    LiveNotFinished,
+    /// None of the formats yt-dlp looked for exist for this video
+    FormatUnavailable,
+    /// The site answered HTTP 429 Too Many Requests
+    RateLimited,
+    /// The site changed and this yt-dlp can no longer read it
+    ExtractorOutdated,
+    /// The separation model file is missing from the model directory
+    ModelMissing,
+    /// The model file exists but could not be loaded
+    ModelCorrupt,
+    /// The GPU (or the machine) ran out of memory during separation
+    OutOfMemory,
+    /// The input file's format or codec could not be decoded
+    UnsupportedInput,
+    /// audio-separator could not load ONNX Runtime
+    OnnxRuntimeMissing,
+    /// A Python package audio-separator needs is missing or broken
+    PythonImportError,
+    Cancelled,
+}
+
+impl ErrorCode {
+    /// Whether the same call may succeed if made again after a pause.
+    pub fn is_retryable(self) -> bool {
+        self == ErrorCode::RateLimited
+    }
+
+    pub fn default_hint(self) -> Option<&'static str> {
+        match self {
+            ErrorCode::NotFound => Some("The file may have been moved or deleted. Refresh the history."),
+            ErrorCode::OutsideManagedDirectories => {
+                Some("Only files in the Resample2 folders or picked with the file dialog can be changed.")
+            }
+            ErrorCode::ToolMissing => Some("Reinstall Resample2, or install the tool and make sure it is on PATH."),
+            ErrorCode::VideoUnavailable => Some("Check that the video plays in a browser without signing in."),
+            ErrorCode::VideoPrivate => Some("Private videos can only be downloaded by accounts the owner shared them with."),
+            ErrorCode::AgeRestricted => Some("Age-restricted videos need a signed-in account and cannot be downloaded here."),
+            ErrorCode::GeoBlocked => Some("The video is blocked in your region. A VPN in another country may work."),
+            ErrorCode::MembersOnly => Some("Members-only videos need a signed-in channel member account."),
+            ErrorCode::LiveNotFinished => Some("Wait until the stream or premiere has ended, then try again."),
+            ErrorCode::FormatUnavailable => Some("Try a different audio format, or update Resample2 to get a newer yt-dlp."),
+            ErrorCode::RateLimited => Some("YouTube is limiting requests from your connection. Wait a few minutes and try again."),
+            ErrorCode::ExtractorOutdated => Some("The site changed since this yt-dlp was released. Update Resample2 to get a newer yt-dlp."),
+            ErrorCode::ModelMissing => Some("Download the model again from the Models tab."),
+            ErrorCode::ModelCorrupt => {
+                Some("The model file is damaged, usually by an interrupted download. Delete it and download it again.")
+            }
+            ErrorCode::OutOfMemory => {
+                Some("Close other GPU-heavy apps, lower the batch or segment size, or turn off GPU acceleration.")
+            }
+            ErrorCode::UnsupportedInput => Some("Convert the file to WAV or FLAC first, or check that it plays."),
+            ErrorCode::OnnxRuntimeMissing => {
+                Some("Reinstall Resample2. On Windows, also install the latest Microsoft Visual C++ Redistributable.")
+            }
+            ErrorCode::PythonImportError => Some("The bundled Python environment is incomplete. Reinstall Resample2."),
+            _ => None,
+        }
+    }
+}
+
+/// External tools whose failures are mapped to codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    YtDlp,
+    Ffmpeg,
+    Ffprobe,
+    AudioSeparator,
+}
+
+impl Tool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::YtDlp => "yt-dlp",
+            Tool::Ffmpeg => "ffmpeg",
+            Tool::Ffprobe => "ffprobe",
+            Tool::AudioSeparator => "audio-separator",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Context for bug reports, e.g. the last lines of a tool's stderr
+    pub details: Option<String>,
+    /// The file or folder involved
+    pub path: Option<String>,
+    pub hint: Option<String>,
+    /// The download or separation that failed; its tool runs are in `get_job_log`.
+    /// Boxed to keep `AppError` small, as every `Result` in the core carries one
+    #[serde(default)]
+    pub job_id: Option<Box<str>>,
+}
+
+/// Lines of stderr kept in `details`
+const DETAILS_LINES: usize = 20;
+
+/// The last `count` non-empty lines of `output`.
+pub fn tail(output: &str, count: usize) -> String {
+    let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+/// The line of tool output that best says what went wrong: the last `ERROR:` or
+/// Python exception line, else the last non-empty line.
+fn summary_line(output: &str) -> &str {
+    let lines: Vec<&str> = output.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines
+        .iter()
+        .rev()
+        .find(|l| l.starts_with("ERROR") || l.contains("Error:") || l.contains("Exception:"))
+        .or(lines.last())
+        .copied()
+        .unwrap_or("no output")
+}
+
+/// Phrases in yt-dlp's stderr, lowercased, and the code each one means. Checked in
+/// order: yt-dlp often adds a generic "video unavailable" line after the specific
+/// reason, so the specific phrases come first. Its "please report this issue"
+/// footer follows all kinds of unexpected errors, so it only counts when nothing
+/// else matched.
+const YTDLP_PATTERNS: &[(&str, ErrorCode)] = &[
+    ("http error 429", ErrorCode::RateLimited),
+    ("too many requests", ErrorCode::RateLimited),
+    ("private video", ErrorCode::VideoPrivate),
+    ("video is private", ErrorCode::VideoPrivate),
+    ("sign in to confirm your age", ErrorCode::AgeRestricted),
+    ("age-restricted", ErrorCode::AgeRestricted),
+    ("inappropriate for some users", ErrorCode::AgeRestricted),
+    ("not available in your country", ErrorCode::GeoBlocked),
+    ("not made this video available in your country", ErrorCode::GeoBlocked),
+    ("geo restriction", ErrorCode::GeoBlocked),
+    ("geo-restricted", ErrorCode::GeoBlocked),
+    ("members-only", ErrorCode::MembersOnly),
+    ("available to this channel's members", ErrorCode::MembersOnly),
+    ("join this channel to get access", ErrorCode::MembersOnly),
+    ("this live event will begin", ErrorCode::LiveNotFinished),
+    ("premieres in", ErrorCode::LiveNotFinished),
+    ("live stream recording is not available", ErrorCode::LiveNotFinished),
+    ("requested format is not available", ErrorCode::FormatUnavailable),
+    ("no video formats found", ErrorCode::FormatUnavailable),
+    ("unable to extract", ErrorCode::ExtractorOutdated),
+    ("signature extraction failed", ErrorCode::ExtractorOutdated),
+    ("video unavailable", ErrorCode::VideoUnavailable),
+    ("this video is unavailable", ErrorCode::VideoUnavailable),
+    ("has been removed", ErrorCode::VideoUnavailable),
+    ("account associated with this video has been terminated", ErrorCode::VideoUnavailable),
+    ("please report this issue", ErrorCode::ExtractorOutdated),
+];
+
+/// The code for yt-dlp's lowercased stderr, if any known phrase is in it.
+fn classify_ytdlp(lower: &str) -> Option<ErrorCode> {
+    YTDLP_PATTERNS
+        .iter()
+        .find(|(phrase, _)| lower.contains(phrase))
+        .map(|(_, code)| *code)
+}
+
+/// Phrases in audio-separator's output, lowercased, and the code each one means.
+/// ONNX Runtime comes before the generic import errors it would also match.
+const SEPARATOR_PATTERNS: &[(&str, ErrorCode)] = &[
+    ("out of memory", ErrorCode::OutOfMemory),
+    ("outofmemoryerror", ErrorCode::OutOfMemory),
+    ("cudnn_status_alloc_failed", ErrorCode::OutOfMemory),
+    ("failed to allocate memory", ErrorCode::OutOfMemory),
+    ("cannot allocate memory", ErrorCode::OutOfMemory),
+    ("no module named 'onnxruntime'", ErrorCode::OnnxRuntimeMissing),
+    ("onnxruntime_pybind11_state", ErrorCode::OnnxRuntimeMissing),
+    ("importing onnxruntime", ErrorCode::OnnxRuntimeMissing),
+    ("libonnxruntime", ErrorCode::OnnxRuntimeMissing),
+    ("modulenotfounderror", ErrorCode::PythonImportError),
+    ("importerror", ErrorCode::PythonImportError),
+    ("no module named", ErrorCode::PythonImportError),
+    ("dll load failed", ErrorCode::PythonImportError),
+    ("invalid load key", ErrorCode::ModelCorrupt),
+    ("pytorchstreamreader failed", ErrorCode::ModelCorrupt),
+    ("failed finding central directory", ErrorCode::ModelCorrupt),
+    ("protobuf parsing failed", ErrorCode::ModelCorrupt),
+    ("invalid_protobuf", ErrorCode::ModelCorrupt),
+    ("error(s) in loading state_dict", ErrorCode::ModelCorrupt),
+    ("ran out of input", ErrorCode::ModelCorrupt),
+    ("invalid data found when processing input", ErrorCode::UnsupportedInput),
+    ("could not find codec parameters", ErrorCode::UnsupportedInput),
+    ("does not contain any stream", ErrorCode::UnsupportedInput),
+    ("format not recognised", ErrorCode::UnsupportedInput),
+    ("no audio could be loaded", ErrorCode::UnsupportedInput),
+];
+
+/// The code for audio-separator's lowercased output, if any known phrase is in it.
+fn classify_separator(lower: &str) -> Option<ErrorCode> {
+    if let Some((_, code)) = SEPARATOR_PATTERNS.iter().find(|(phrase, _)| lower.contains(phrase)) {
+        return Some(*code);
+    }
+    if lower.lines().any(model_file_not_found) || missing_path_is_model(lower) {
+        return Some(ErrorCode::ModelMissing);
+    }
+    None
+}
+
+/// audio-separator's own "Model file x.ckpt not found in supported model files".
+fn model_file_not_found(line: &str) -> bool {
+    line.split_once("model file ").is_some_and(|(_, rest)| rest.contains(" not found"))
+}
+
+/// A FileNotFoundError for the model being loaded, rather than for the input,
+/// an output directory or one of Python's own files.
+fn missing_path_is_model(lower: &str) -> bool {
+    // "Loading model x.ckpt..." names the model; without it any model file counts
+    let loading = lower.lines().find_map(|line| {
+        let (_, rest) = line.split_once("loading model ")?;
+        Some(rest.trim().trim_end_matches('.'))
+    });
+    lower
+        .lines()
+        .filter_map(|line| line.split_once("no such file or directory: ").map(|(_, path)| path))
+        .map(|path| path.trim().trim_matches(['\'', '"']))
+        .any(|path| {
+            let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+            match loading {
+                Some(model) => name == model,
+                None => [".ckpt", ".pth", ".onnx", ".safetensors"].iter().any(|ext| name.ends_with(ext)),
+            }
+        })
+}
+
+/// Map a failed tool's output to the most specific code it matches.
+pub fn classify_tool_output(tool: Tool, output: &str) -> ErrorCode {
+    let lower = output.to_lowercase();
+    match tool {
+        Tool::YtDlp => {
+            if let Some(code) = classify_ytdlp(&lower) {
+                return code;
+            }
+        }
+        Tool::AudioSeparator => {
+            if let Some(code) = classify_separator(&lower) {
+                return code;
+            }
+        }
+        Tool::Ffmpeg | Tool::Ffprobe => {}
+    }
+    ErrorCode::ToolFailed
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        AppError {
+            code,
+            message: message.into(),
+            details: None,
+            path: None,
+            hint: code.default_hint().map(str::to_string),
+            job_id: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn with_job(mut self, job_id: impl Into<String>) -> Self {
+        self.job_id = Some(job_id.into().into_boxed_str());
+        self
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn not_found(path: impl Into<String>) -> Self {
+        let path = path.into();
+        AppError::new(ErrorCode::NotFound, format!("File does not exist: {}", path)).with_path(path)
+    }
+
+    pub fn outside_managed_directories(path: impl Into<String>) -> Self {
+        let path = path.into();
+        AppError::new(
+            ErrorCode::OutsideManagedDirectories,
+            format!("Access denied: {} is outside the Resample2 folders", path),
+        )
+        .with_path(path)
+    }
+
+    pub fn invalid_model_filename(name: impl Into<String>) -> Self {
+        let name = name.into();
+        AppError::new(ErrorCode::InvalidModelFilename, format!("Invalid model filename: {}", name)).with_path(name)
+    }
+
+    /// `tool` could not be found in PATH or the bundled resources.
+    pub fn tool_missing(tool: Tool) -> Self {
+        AppError::new(ErrorCode::ToolMissing, format!("{} binary not found", tool.name()))
+    }
+
+    /// `tool` was found but could not be started.
+    pub fn tool_spawn(tool: Tool, error: impl fmt::Display) -> Self {
+        AppError::new(ErrorCode::ToolMissing, format!("Failed to execute {}: {}", tool.name(), error))
+    }
+
+    /// `tool` exited with an error; `output` is its stderr.
+    pub fn tool_failed(tool: Tool, output: &str) -> Self {
+        let code = classify_tool_output(tool, output);
+        AppError::new(code, format!("{} failed: {}", tool.name(), summary_line(output)))
+            .with_details(tail(output, DETAILS_LINES))
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        AppError::new(ErrorCode::Cancelled, message)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::new(ErrorCode::Failed, message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::new(ErrorCode::Failed, message)
+    }
+}
+
+/// The stderr recorded in `fixtures/stderr/<tool>/<name>.txt`.
+#[cfg(test)]
+pub(crate) fn stderr_fixture(tool: Tool, name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures/stderr")
+        .join(tool.name())
+        .join(format!("{}.txt", name));
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn classify_fixture(tool: Tool, name: &str) -> ErrorCode {
+        classify_tool_output(tool, &stderr_fixture(tool, name))
+    }
+
+    #[test]
+    fn codes_serialize_to_their_stable_names() {
+        let names = [
+            (ErrorCode::Failed, "failed"),
+            (ErrorCode::InvalidInput, "invalid_input"),
+            (ErrorCode::NotFound, "not_found"),
+            (ErrorCode::OutsideManagedDirectories, "outside_managed_directories"),
+            (ErrorCode::InvalidModelFilename, "invalid_model_filename"),
+            (ErrorCode::ToolMissing, "tool_missing"),
+            (ErrorCode::ToolFailed, "tool_failed"),
+            (ErrorCode::VideoUnavailable, "video_unavailable"),
+            (ErrorCode::VideoPrivate, "video_private"),
+            (ErrorCode::AgeRestricted, "age_restricted"),
+            (ErrorCode::GeoBlocked, "geo_blocked"),
+            (ErrorCode::MembersOnly, "members_only"),
+            (ErrorCode::LiveNotFinished, "live_not_finished"),
+            (ErrorCode::FormatUnavailable, "format_unavailable"),
+            (ErrorCode::RateLimited, "rate_limited"),
+            (ErrorCode::ExtractorOutdated, "extractor_outdated"),
+            (ErrorCode::ModelMissing, "model_missing"),
+            (ErrorCode::ModelCorrupt, "model_corrupt"),
+            (ErrorCode::OutOfMemory, "out_of_memory"),
+            (ErrorCode::UnsupportedInput, "unsupported_input"),
+            (ErrorCode::OnnxRuntimeMissing, "onnx_runtime_missing"),
+            (ErrorCode::PythonImportError, "python_import_error"),
+            (ErrorCode::Cancelled, "cancelled"),
+        ];
+        for (code, name) in names {
+            assert_eq!(serde_json::to_value(code).unwrap(), json!(name));
+            assert_eq!(serde_json::from_value::<ErrorCode>(json!(name)).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn constructors_map_to_their_codes() {
+        let cases = [
+            (AppError::invalid_input("bad"), ErrorCode::InvalidInput),
+            (AppError::not_found("/a.wav"), ErrorCode::NotFound),
+            (AppError::outside_managed_directories("/etc/passwd"), ErrorCode::OutsideManagedDirectories),
+            (AppError::invalid_model_filename("../x.ckpt"), ErrorCode::InvalidModelFilename),
+            (AppError::tool_missing(Tool::YtDlp), ErrorCode::ToolMissing),
+            (AppError::tool_spawn(Tool::Ffmpeg, "permission denied"), ErrorCode::ToolMissing),
+            (AppError::tool_failed(Tool::Ffprobe, "moov atom not found"), ErrorCode::ToolFailed),
+            (AppError::cancelled("Download cancelled"), ErrorCode::Cancelled),
+            (AppError::from("plain message"), ErrorCode::Failed),
+            (AppError::from("owned message".to_string()), ErrorCode::Failed),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.code, code, "{}", error.message);
+            assert_eq!(error.hint.as_deref(), code.default_hint());
+        }
+        assert_eq!(AppError::not_found("/a.wav").path.as_deref(), Some("/a.wav"));
+        assert_eq!(AppError::invalid_model_filename("../x.ckpt").path.as_deref(), Some("../x.ckpt"));
+    }
+
+    #[test]
+    fn tool_codes_come_with_a_hint() {
+        for code in [
+            ErrorCode::ToolMissing,
+            ErrorCode::VideoUnavailable,
+            ErrorCode::VideoPrivate,
+            ErrorCode::AgeRestricted,
+            ErrorCode::GeoBlocked,
+            ErrorCode::MembersOnly,
+            ErrorCode::LiveNotFinished,
+            ErrorCode::FormatUnavailable,
+            ErrorCode::RateLimited,
+            ErrorCode::ExtractorOutdated,
+            ErrorCode::ModelMissing,
+            ErrorCode::ModelCorrupt,
+            ErrorCode::OutOfMemory,
+            ErrorCode::UnsupportedInput,
+            ErrorCode::OnnxRuntimeMissing,
+            ErrorCode::PythonImportError,
+        ] {
+            assert!(code.default_hint().is_some(), "{:?} has no hint", code);
+        }
+    }
+
+    #[test]
+    fn tool_failure_keeps_the_error_line_and_the_stderr_tail() {
+        let noise: String = (0..30).map(|i| format!("[debug] line {}\n", i)).collect();
+        let stderr = format!("{}ERROR: something broke\n\n[debug] after\n", noise);
+        let error = AppError::tool_failed(Tool::Ffmpeg, &stderr);
+        assert_eq!(error.message, "ffmpeg failed: ERROR: something broke");
+        let details = error.details.expect("details");
+        assert_eq!(details.lines().count(), DETAILS_LINES);
+        assert!(details.ends_with("ERROR: something broke\n[debug] after"));
+    }
+
+    #[test]
+    fn serialized_error_has_the_fields_the_ui_reads() {
+        let error = AppError::not_found("/a.wav").with_details("stderr").with_job("job1");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "not_found",
+                "message": "File does not exist: /a.wav",
+                "details": "stderr",
+                "path": "/a.wav",
+                "hint": ErrorCode::NotFound.default_hint(),
+                "job_id": "job1",
+            })
+        );
+    }
+
+    #[test]
+    fn ytdlp_rate_limited() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "rate_limited"), ErrorCode::RateLimited);
+    }
+
+    #[test]
+    fn ytdlp_video_private() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "video_private"), ErrorCode::VideoPrivate);
+    }
+
+    #[test]
+    fn ytdlp_age_restricted() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "age_restricted"), ErrorCode::AgeRestricted);
+    }
+
+    #[test]
+    fn ytdlp_geo_blocked_wins_over_the_generic_unavailable_line() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "geo_blocked"), ErrorCode::GeoBlocked);
+    }
+
+    #[test]
+    fn ytdlp_members_only() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "members_only"), ErrorCode::MembersOnly);
+    }
+
+    #[test]
+    fn ytdlp_live_not_finished() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "live_not_finished"), ErrorCode::LiveNotFinished);
+    }
+
+    #[test]
+    fn ytdlp_format_unavailable() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "format_unavailable"), ErrorCode::FormatUnavailable);
+    }
+
+    #[test]
+    fn ytdlp_extractor_outdated() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "extractor_outdated"), ErrorCode::ExtractorOutdated);
+    }
+
+    #[test]
+    fn ytdlp_report_footer_does_not_override_a_specific_reason() {
+        let stderr = "ERROR: [youtube] GGGGGGGGGGG: Video unavailable. This video has been removed by the uploader; \
+                      please report this issue on  https://github.com/yt-dlp/yt-dlp/issues?q= , filling out the \
+                      appropriate issue template. Confirm you are on the latest version using  yt-dlp -U";
+        assert_eq!(classify_tool_output(Tool::YtDlp, stderr), ErrorCode::VideoUnavailable);
+    }
+
+    #[test]
+    fn ytdlp_video_unavailable() {
+        assert_eq!(classify_fixture(Tool::YtDlp, "video_unavailable"), ErrorCode::VideoUnavailable);
+    }
+
+    #[test]
+    fn ytdlp_unrecognised_failure() {
+        let error = AppError::tool_failed(Tool::YtDlp, &stderr_fixture(Tool::YtDlp, "tool_failed"));
+        assert_eq!(error.code, ErrorCode::ToolFailed);
+        assert_eq!(error.message, "yt-dlp failed: ERROR: unable to write data: [Errno 28] No space left on device");
+    }
+
+    #[test]
+    fn separator_cuda_out_of_memory() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "out_of_memory"), ErrorCode::OutOfMemory);
+    }
+
+    #[test]
+    fn separator_mps_out_of_memory() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "out_of_memory_mps"), ErrorCode::OutOfMemory);
+    }
+
+    #[test]
+    fn separator_model_missing() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "model_missing"), ErrorCode::ModelMissing);
+    }
+
+    #[test]
+    fn separator_model_file_not_found() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "model_file_not_found"), ErrorCode::ModelMissing);
+    }
+
+    #[test]
+    fn separator_missing_input_next_to_a_model_name_is_not_a_missing_model() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "input_file_not_found"), ErrorCode::ToolFailed);
+    }
+
+    #[test]
+    fn separator_model_corrupt() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "model_corrupt"), ErrorCode::ModelCorrupt);
+    }
+
+    #[test]
+    fn separator_unsupported_input() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "unsupported_input"), ErrorCode::UnsupportedInput);
+    }
+
+    #[test]
+    fn separator_onnx_runtime_wins_over_the_generic_import_error() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "onnx_runtime_missing"), ErrorCode::OnnxRuntimeMissing);
+    }
+
+    #[test]
+    fn separator_python_import_error() {
+        assert_eq!(classify_fixture(Tool::AudioSeparator, "python_import_error"), ErrorCode::PythonImportError);
+    }
+
+    #[test]
+    fn separator_unrecognised_failure() {
+        let error = AppError::tool_failed(Tool::AudioSeparator, &stderr_fixture(Tool::AudioSeparator, "tool_failed"));
+        assert_eq!(error.code, ErrorCode::ToolFailed);
+        assert!(error.message.starts_with("audio-separator failed: PermissionError: [Errno 13]"));
+    }
+}
@@ -0,0 +1,195 @@
+// Library export for provenance paperwork.
+//
+// One row per history item, carrying where it came from (source URL, uploader,
+// title, time range, download date) and, for separation outputs, which model and
+// settings produced it. Written as CSV or JSON.
+
+use crate::history::AudioFileInfoFrontend;
+use crate::library::{FileRecord, FileRole, JobRecord, Library};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportRow {
+    pub id: String,
+    pub name: String,
+    pub file_path: String,
+    /// "downloads" or "separated"
+    pub directory_type: String,
+    pub stem: Option<String>,
+    pub source_url: Option<String>,
+    pub uploader: Option<String>,
+    pub title: Option<String>,
+    /// Seconds into the source, for partial downloads
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    /// RFC 3339, when the source was downloaded
+    pub downloaded_at: Option<String>,
+    pub job_id: Option<String>,
+    pub model: Option<String>,
+    /// SHA-256 of the job's separation settings, to tell runs with different settings apart
+    pub settings_hash: Option<String>,
+    /// File the job separated, for separation outputs
+    pub input_file: Option<String>,
+    /// For downloads, every file separated from it; for outputs, the other files of the same job
+    pub output_files: Vec<String>,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportReport {
+    pub path: String,
+    pub rows: usize,
+}
+
+fn rfc3339(secs: u64) -> Option<String> {
+    chrono::DateTime::from_timestamp(secs as i64, 0).map(|dt| dt.to_rfc3339())
+}
+
+/// Settings serialize with sorted keys, so equal settings always hash the same.
+pub fn settings_hash(job: &JobRecord) -> String {
+    let digest = Sha256::digest(job.settings.to_string().as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn output_paths(library: &Library, job: &JobRecord) -> Vec<String> {
+    library.outputs_of_job(&job.id).into_iter().map(|f| f.path.clone()).collect()
+}
+
+pub fn export_row(library: &Library, item: &AudioFileInfoFrontend) -> ExportRow {
+    let mut row = ExportRow {
+        id: item.id.clone(),
+        name: item.name.clone(),
+        file_path: item.file_path.clone(),
+        directory_type: item.directory_type.clone(),
+        stem: item.stem.clone(),
+        tags: item.annotations.tags.clone(),
+        favorite: item.annotations.favorite,
+        note: item.annotations.note.clone(),
+        ..Default::default()
+    };
+    let Some(file) = library.file(&item.id) else { return row };
+
+    // The file the source and time range belong to: the download itself, or the
+    // input of the job that produced a stem
+    let job = match file.role {
+        FileRole::Stem => file.parent_id.as_deref().and_then(|id| library.job(id)),
+        _ => None,
+    };
+    let origin: Option<&FileRecord> = match job {
+        Some(job) => library.file(&job.input_id),
+        None => Some(file),
+    };
+
+    if let Some(source) = library.source_for_file(file) {
+        row.source_url = Some(source.resolved_url.clone().unwrap_or_else(|| source.url.clone()));
+        row.uploader = source.uploader.clone();
+        row.title = source.title.clone();
+        row.downloaded_at = rfc3339(source.created_at);
+    }
+    if let Some(origin) = origin {
+        row.start_time = origin.start_time;
+        row.end_time = origin.end_time;
+    }
+
+    match job {
+        Some(job) => {
+            row.job_id = Some(job.id.clone());
+            row.model = Some(job.model.clone());
+            row.settings_hash = Some(settings_hash(job));
+            row.input_file = origin.map(|f| f.path.clone());
+            row.output_files = output_paths(library, job)
+                .into_iter()
+                .filter(|p| p != &file.path)
+                .collect();
+        }
+        None => {
+            row.output_files = library
+                .jobs_for_input(&file.id)
+                .into_iter()
+                .flat_map(|job| output_paths(library, job))
+                .collect();
+        }
+    }
+    row
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+const CSV_HEADER: &[&str] = &[
+    "id", "name", "file_path", "directory_type", "stem", "source_url", "uploader", "title",
+    "start_time", "end_time", "downloaded_at", "job_id", "model", "settings_hash", "input_file",
+    "output_files", "tags", "favorite", "note",
+];
+
+pub fn to_csv(rows: &[ExportRow]) -> String {
+    let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+    let mut out = CSV_HEADER.join(",");
+    out.push('\n');
+    for row in rows {
+        // Lists are joined with "; " to keep one row per file
+        let fields = [
+            row.id.clone(),
+            row.name.clone(),
+            row.file_path.clone(),
+            row.directory_type.clone(),
+            opt(&row.stem),
+            opt(&row.source_url),
+            opt(&row.uploader),
+            opt(&row.title),
+            row.start_time.map(|t| t.to_string()).unwrap_or_default(),
+            row.end_time.map(|t| t.to_string()).unwrap_or_default(),
+            opt(&row.downloaded_at),
+            opt(&row.job_id),
+            opt(&row.model),
+            opt(&row.settings_hash),
+            opt(&row.input_file),
+            row.output_files.join("; "),
+            row.tags.join("; "),
+            row.favorite.to_string(),
+            opt(&row.note),
+        ];
+        out.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn write_export(path: &Path, rows: &[ExportRow], format: ExportFormat) -> Result<(), String> {
+    let content = match format {
+        ExportFormat::Csv => to_csv(rows),
+        ExportFormat::Json => serde_json::to_string_pretty(rows)
+            .map_err(|e| format!("Failed to serialize export: {}", e))?,
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export folder: {}", e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write export: {}", e))
+}
@@ -0,0 +1,109 @@
+// Revealing files in the platform file manager.
+//
+// Finder and Explorer can select a file directly. On Linux the
+// `org.freedesktop.FileManager1.ShowItems` D-Bus method does the same in Nautilus,
+// Dolphin, Nemo, Thunar and others; without it we can only open the folder.
+
+use crate::process::{ProcessRunner, ProcessSpec};
+use std::path::PathBuf;
+
+/// Show `paths` selected in the file manager. Several files in one folder are
+/// selected together where the platform allows it.
+#[cfg(target_os = "macos")]
+pub fn reveal(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> Result<(), String> {
+    let spec = ProcessSpec::new("open")
+        .arg("-R")
+        .args(paths.iter().map(|p| p.to_string_lossy().to_string()))
+        .discard_output();
+    let output = runner.output(&spec).map_err(|e| format!("Failed to open Finder: {}", e))?;
+    if output.status.success() { Ok(()) } else { Err("Failed to open Finder".into()) }
+}
+
+/// Show `paths` selected in the file manager. Explorer's `/select` takes a single
+/// item, so only the first of several files is selected.
+#[cfg(target_os = "windows")]
+pub fn reveal(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> Result<(), String> {
+    let first = paths.first().ok_or_else(|| "No files to show".to_string())?;
+    // Canonical paths carry a \\?\ prefix Explorer doesn't understand
+    let target = first.to_string_lossy().trim_start_matches(r"\\?\").to_string();
+    // Explorer exits with 1 even when it opened the window
+    runner
+        .output(&ProcessSpec::new("explorer").args(["/select,", &target]).discard_output())
+        .map_err(|e| format!("Failed to open Explorer: {}", e))?;
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_items_dbus_send(runner: &dyn ProcessRunner, uris: &[String]) -> Result<(), String> {
+    // URIs are percent-encoded, so the comma separator is unambiguous
+    let spec = ProcessSpec::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{}", uris.join(",")))
+        .arg("string:")
+        .discard_output();
+    let output = runner.output(&spec).map_err(|e| format!("Failed to run dbus-send: {}", e))?;
+    if output.status.success() { Ok(()) } else { Err(format!("dbus-send failed ({})", output.status)) }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_items_gdbus(runner: &dyn ProcessRunner, uris: &[String]) -> Result<(), String> {
+    let list = format!(
+        "[{}]",
+        uris.iter().map(|u| format!("'{}'", u)).collect::<Vec<_>>().join(", ")
+    );
+    let spec = ProcessSpec::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.FileManager1",
+            "--object-path",
+            "/org/freedesktop/FileManager1",
+            "--method",
+            "org.freedesktop.FileManager1.ShowItems",
+            &list,
+            "",
+        ])
+        .discard_output();
+    let output = runner.output(&spec).map_err(|e| format!("Failed to run gdbus: {}", e))?;
+    if output.status.success() { Ok(()) } else { Err(format!("gdbus failed ({})", output.status)) }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+type ShowItems = fn(&dyn ProcessRunner, &[String]) -> Result<(), String>;
+
+/// Show `paths` selected in the file manager through FileManager1, falling back to
+/// opening the first file's folder with `xdg-open`.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn reveal(runner: &dyn ProcessRunner, paths: &[PathBuf]) -> Result<(), String> {
+    let first = paths.first().ok_or_else(|| "No files to show".to_string())?;
+    let uris: Vec<String> = paths.iter().map(|p| crate::clipboard::file_uri(p)).collect();
+
+    let attempts: [(&str, ShowItems); 2] = [("dbus-send", show_items_dbus_send), ("gdbus", show_items_gdbus)];
+    for (tool, show_items) in attempts {
+        if which::which(tool).is_err() {
+            continue;
+        }
+        match show_items(runner, &uris) {
+            Ok(()) => {
+                log_info!("Revealed {} item(s) via FileManager1 ({})", paths.len(), tool);
+                return Ok(());
+            }
+            Err(e) => log_warn!("FileManager1.ShowItems unavailable: {}", e),
+        }
+    }
+
+    let dir = first.parent().unwrap_or(std::path::Path::new("."));
+    // The file manager it starts may inherit our streams and outlive us
+    let output = runner
+        .output(&ProcessSpec::new("xdg-open").arg(dir.to_string_lossy()).discard_output())
+        .map_err(|e| format!("Failed to open file manager: {}", e))?;
+    if output.status.success() { Ok(()) } else { Err("Failed to open file manager".into()) }
+}
@@ -0,0 +1,797 @@
+// File history built on the persistent library.
+//
+// Files found in the managed Downloads/Separated folders are registered in the
+// library the first time they are seen; files the library already knows about
+// keep their links to sources and jobs wherever they live on disk.
+
+use crate::library::{FileAnnotations, FileRecord, FileRole, Fingerprint, Library};
+use crate::output;
+use crate::probe::{self, AudioProperties};
+use crate::trash;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioFileInfoFrontend {
+    pub id: String,
+    pub name: String,
+    pub file_path: String,
+    pub directory_type: String,   // "downloads" or "separated"
+    pub created_timestamp: u64,   // Unix timestamp for sorting
+    pub created_display: String,  // Human readable time
+    pub duration: Option<String>, // Display duration, filled in once probed
+    pub file_size: u64,           // File size in bytes
+    pub parent_id: Option<String>,
+    pub source_url: Option<String>,
+    pub source_title: Option<String>,
+    pub stem: Option<String>,
+    pub model: Option<String>,
+    /// Subfolder of Downloads/Separated the file is grouped under, if any
+    pub set_path: Option<String>,
+    #[serde(flatten)]
+    pub annotations: FileAnnotations,
+    #[serde(flatten)]
+    pub properties: AudioProperties,
+}
+
+fn format_timestamp(ts_secs: u64) -> String {
+    use chrono::{DateTime, Utc};
+    if let Some(dt) = DateTime::<Utc>::from_timestamp(ts_secs as i64, 0) {
+        dt.format("%Y-%m-%d %H:%M").to_string()
+    } else {
+        "".to_string()
+    }
+}
+
+impl AudioFileInfoFrontend {
+    pub fn apply_properties(&mut self, properties: AudioProperties) {
+        self.duration = properties.duration_seconds.map(probe::format_duration);
+        self.properties = properties;
+    }
+
+    /// A download with nothing but the fields tests vary, at `/music/Downloads/<name>`.
+    #[cfg(test)]
+    pub(crate) fn for_test(id: &str, name: &str, created: u64, size: u64) -> Self {
+        AudioFileInfoFrontend {
+            id: id.to_string(),
+            name: name.to_string(),
+            file_path: format!("/music/Downloads/{}", name),
+            directory_type: "downloads".to_string(),
+            created_timestamp: created,
+            created_display: format_timestamp(created),
+            duration: None,
+            file_size: size,
+            parent_id: None,
+            source_url: None,
+            source_title: None,
+            stem: None,
+            model: None,
+            set_path: None,
+            annotations: FileAnnotations::default(),
+            properties: AudioProperties::default(),
+        }
+    }
+}
+
+/// Build a history entry for a library file, or `None` if the file is gone.
+pub fn history_item(library: &Library, file: &FileRecord) -> Option<AudioFileInfoFrontend> {
+    let path = PathBuf::from(&file.path);
+    let meta = fs::metadata(&path).ok().filter(|m| m.is_file())?;
+
+    let directory_type = match file.role {
+        FileRole::Stem => "separated",
+        FileRole::Download | FileRole::Local => "downloads",
+    };
+    // Not every filesystem records creation time
+    let created_ts = meta
+        .created()
+        .or_else(|_| meta.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let source = library.source_for_file(file);
+    let job = match file.role {
+        FileRole::Stem => file.parent_id.as_deref().and_then(|id| library.job(id)),
+        _ => None,
+    };
+
+    Some(AudioFileInfoFrontend {
+        id: file.id.clone(),
+        name: path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string(),
+        file_path: file.path.clone(),
+        directory_type: directory_type.to_string(),
+        created_timestamp: created_ts,
+        created_display: format_timestamp(created_ts),
+        duration: None,
+        file_size: meta.len(),
+        parent_id: file.parent_id.clone(),
+        source_url: source.map(|s| s.resolved_url.clone().unwrap_or_else(|| s.url.clone())),
+        source_title: source.and_then(|s| s.title.clone()),
+        stem: file.stem.clone(),
+        model: job.map(|j| j.model.clone()),
+        set_path: None,
+        annotations: file.annotations.clone(),
+        properties: AudioProperties::default(),
+    })
+}
+
+/// No `webm`: that is yt-dlp's intermediate download, which only exists in
+/// Downloads until it has been converted.
+pub const DEFAULT_AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "wav", "flac", "m4a", "aac", "ogg", "opus", "aiff", "aif", "wma",
+];
+
+/// How deep and for what the managed folders are scanned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryScanOptions {
+    /// Levels of subfolders to descend into; 0 only lists files directly inside
+    pub max_depth: usize,
+    /// Lowercase extensions without the dot
+    pub extensions: Vec<String>,
+}
+
+impl Default for HistoryScanOptions {
+    fn default() -> Self {
+        HistoryScanOptions {
+            max_depth: 2,
+            extensions: DEFAULT_AUDIO_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+impl HistoryScanOptions {
+    /// Read `history_scan_depth` and `history_extensions` from the settings JSON.
+    pub fn from_settings_value(val: &Value) -> Self {
+        let mut options = HistoryScanOptions::default();
+        if let Some(depth) = val.get("history_scan_depth").and_then(|v| v.as_u64()) {
+            options.max_depth = depth as usize;
+        }
+        if let Some(extensions) = val.get("history_extensions").and_then(|v| v.as_array()) {
+            let extensions: Vec<String> = extensions
+                .iter()
+                .filter_map(|e| e.as_str())
+                .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect();
+            if !extensions.is_empty() {
+                options.extensions = extensions;
+            }
+        }
+        options
+    }
+
+    pub fn accepts(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| self.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+}
+
+fn scan_dir(dir: &Path, depth: usize, options: &HistoryScanOptions, role: FileRole, out: &mut Vec<(PathBuf, FileRole)>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| format!("Failed to read dir: {}", e))?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden && depth < options.max_depth {
+                scan_dir(&path, depth + 1, options, role, out)?;
+            }
+        } else if path.is_file() && options.accepts(&path) {
+            out.push((path, role));
+        }
+    }
+    Ok(())
+}
+
+/// Audio files in the managed Downloads and Separated folders (and their subfolders,
+/// down to `options.max_depth`), with the role a file found there gets when it is
+/// not in the library yet.
+pub fn managed_files(download_base: &Path, options: &HistoryScanOptions) -> Result<Vec<(PathBuf, FileRole)>, String> {
+    let downloads_dir = output::downloads_dir(download_base);
+    let separated_dir = output::separated_dir(download_base);
+
+    let mut files = Vec::new();
+    for (dir, role) in [(&downloads_dir, FileRole::Download), (&separated_dir, FileRole::Stem)] {
+        if !dir.exists() { continue; }
+        scan_dir(dir, 0, options, role, &mut files)?;
+    }
+    Ok(files)
+}
+
+/// The top-level subfolder of Downloads/Separated that `path` lives in, if any.
+fn set_folder(download_base: &Path, path: &Path) -> Option<PathBuf> {
+    [output::downloads_dir(download_base), output::separated_dir(download_base)]
+        .into_iter()
+        .find_map(|root| {
+            let relative = path.strip_prefix(&root).ok()?;
+            let mut components = relative.components();
+            let first = components.next()?;
+            // Files directly inside the managed folder are not part of a set
+            components.next()?;
+            Some(root.join(first))
+        })
+}
+
+/// Whether `dir` is a history set folder, i.e. a direct subfolder of Downloads or
+/// Separated. Both paths should be canonical.
+pub fn is_set_folder(download_base: &Path, dir: &Path) -> bool {
+    let parent = dir.parent();
+    [output::downloads_dir(download_base), output::separated_dir(download_base)]
+        .iter()
+        .any(|root| parent == Some(root.as_path()))
+}
+
+/// Look up a history item by id. If the file has been renamed or moved within the
+/// managed folders it is found again by content fingerprint and the library updated.
+pub fn find_history_item(
+    library: &mut Library,
+    download_base: &Path,
+    options: &HistoryScanOptions,
+    id: &str,
+) -> Result<AudioFileInfoFrontend, String> {
+    let file = library
+        .file(id)
+        .cloned()
+        .ok_or_else(|| "History item not found".to_string())?;
+
+    if !Path::new(&file.path).exists() {
+        let fp = file
+            .fingerprint
+            .clone()
+            .filter(|_| !trash::record_ids(download_base).contains(id))
+            .ok_or_else(|| "File no longer exists".to_string())?;
+        let candidate = managed_files(download_base, options)?
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| library.file_by_path(path).is_none() && file.may_have_moved_to(path))
+            .find(|path| crate::library::fingerprint(path).as_deref() == Some(fp.as_str()))
+            .ok_or_else(|| "File no longer exists".to_string())?;
+        library.relocate(id, &candidate)?;
+    }
+
+    let file = library
+        .file(id)
+        .ok_or_else(|| "History item not found".to_string())?;
+    let mut item = history_item(library, file).ok_or_else(|| "File no longer exists".to_string())?;
+    item.set_path = set_folder(download_base, Path::new(&item.file_path)).map(|p| p.to_string_lossy().to_string());
+    Ok(item)
+}
+
+/// Register any unknown files in the managed folders, then list every library
+/// download and stem that still exists, newest first.
+pub fn collect_history(
+    library: &mut Library,
+    download_base: &Path,
+    options: &HistoryScanOptions,
+) -> Result<Vec<AudioFileInfoFrontend>, String> {
+    let trashed = trash::record_ids(download_base);
+    for (path, role) in managed_files(download_base, options)? {
+        library.ensure_file(&path, role, &trashed)?;
+    }
+    Ok(list_history(library, download_base, options))
+}
+
+/// `collect_history` on the shared library. New and changed files are hashed
+/// before the library is locked, so a scan over large files doesn't hold up the
+/// commands and jobs waiting on it. Files that change again in between are left
+/// for the next scan. The guard is returned for work on the same state.
+pub fn lock_and_collect<'a>(
+    library: &'a Mutex<Library>,
+    download_base: &Path,
+    options: &HistoryScanOptions,
+) -> Result<(MutexGuard<'a, Library>, Vec<AudioFileInfoFrontend>), String> {
+    let lock = || library.lock().map_err(|_| "Library lock poisoned".to_string());
+    let files = managed_files(download_base, options)?;
+    let pending: Vec<PathBuf> = {
+        let lib = lock()?;
+        files.iter().map(|(path, _)| path).filter(|path| lib.needs_fingerprint(path)).cloned().collect()
+    };
+    let mut taken: HashMap<PathBuf, Fingerprint> = pending
+        .into_iter()
+        .map(|path| {
+            let fingerprint = Fingerprint::take(&path);
+            (path, fingerprint)
+        })
+        .collect();
+
+    let trashed = trash::record_ids(download_base);
+    let mut lib = lock()?;
+    for (path, role) in files {
+        if let Some(fingerprint) = taken.remove(&path) {
+            lib.ensure_file_with(&path, role, &trashed, Some(fingerprint))?;
+        }
+    }
+    let items = list_history(&lib, download_base, options);
+    Ok((lib, items))
+}
+
+/// Every library download and stem that still exists, newest first.
+fn list_history(library: &Library, download_base: &Path, options: &HistoryScanOptions) -> Vec<AudioFileInfoFrontend> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut items: Vec<AudioFileInfoFrontend> = library
+        .files()
+        .filter(|f| f.role != FileRole::Local && options.accepts(Path::new(&f.path)))
+        .filter_map(|f| history_item(library, f))
+        .filter(|item| seen.insert(item.file_path.clone()))
+        .map(|mut item| {
+            item.set_path = set_folder(download_base, Path::new(&item.file_path))
+                .map(|p| p.to_string_lossy().to_string());
+            item
+        })
+        .collect();
+
+    // Newest first
+    items.sort_by_key(|item| std::cmp::Reverse(item.created_timestamp));
+    items
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySortField {
+    #[default]
+    Created,
+    Name,
+    Size,
+    Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Case-insensitive match against file name, source title, uploader, tags and note
+    pub text: Option<String>,
+    /// Items must carry every one of these tags
+    pub tags: Vec<String>,
+    pub favorites_only: bool,
+    /// "downloads" or "separated"
+    pub directory_type: Option<String>,
+    pub stems: Vec<String>,
+    pub models: Vec<String>,
+    /// File extensions without the dot, e.g. "wav"
+    pub formats: Vec<String>,
+    /// Unix timestamps, inclusive
+    pub created_after: Option<u64>,
+    pub created_before: Option<u64>,
+    pub sort_by: HistorySortField,
+    /// Defaults to A→Z for names and largest/newest first for everything else
+    pub descending: Option<bool>,
+    /// Opaque cursor from a previous page's `next_cursor`
+    pub cursor: Option<String>,
+    pub limit: usize,
+}
+
+impl HistoryQuery {
+    fn is_descending(&self) -> bool {
+        self.descending.unwrap_or(self.sort_by != HistorySortField::Name)
+    }
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        HistoryQuery {
+            text: None,
+            tags: Vec::new(),
+            favorites_only: false,
+            directory_type: None,
+            stems: Vec::new(),
+            models: Vec::new(),
+            formats: Vec::new(),
+            created_after: None,
+            created_before: None,
+            sort_by: HistorySortField::Created,
+            descending: None,
+            cursor: None,
+            limit: 100,
+        }
+    }
+}
+
+/// A subfolder of Downloads/Separated shown as one collapsible group. Delete,
+/// copy and open act on `path` to treat the folder as a unit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySet {
+    pub path: String,
+    pub name: String,
+    pub directory_type: String,
+    pub file_count: usize,
+    pub total_size: u64,
+    /// Newest `created_timestamp` of the files in the set
+    pub latest_timestamp: u64,
+}
+
+/// Group items by `set_path`, newest set first.
+pub fn history_sets<'a>(items: impl IntoIterator<Item = &'a AudioFileInfoFrontend>) -> Vec<HistorySet> {
+    let mut sets: Vec<HistorySet> = Vec::new();
+    for item in items {
+        let Some(path) = &item.set_path else { continue };
+        let set = match sets.iter().position(|s| &s.path == path) {
+            Some(index) => &mut sets[index],
+            None => {
+                sets.push(HistorySet {
+                    path: path.clone(),
+                    name: Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or("").to_string(),
+                    directory_type: item.directory_type.clone(),
+                    file_count: 0,
+                    total_size: 0,
+                    latest_timestamp: 0,
+                });
+                sets.last_mut().unwrap()
+            }
+        };
+        set.file_count += 1;
+        set.total_size += item.file_size;
+        set.latest_timestamp = set.latest_timestamp.max(item.created_timestamp);
+    }
+    sets.sort_by_key(|s| std::cmp::Reverse(s.latest_timestamp));
+    sets
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub items: Vec<AudioFileInfoFrontend>,
+    /// Sets the items on this page belong to, totals across the whole filtered history
+    pub sets: Vec<HistorySet>,
+    /// Pass back as `cursor` to get the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+    /// Number of items matching the filters, across all pages
+    pub total: usize,
+}
+
+/// Position of an item in the sort order. The id breaks ties so the order is total
+/// and a cursor still works after the item it points at has been deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryCursor {
+    text: String,
+    number: f64,
+    id: String,
+}
+
+fn contains_ci(haystack: Option<&str>, needle: &str) -> bool {
+    haystack.is_some_and(|h| h.to_lowercase().contains(needle))
+}
+
+fn sort_position(item: &AudioFileInfoFrontend, field: HistorySortField) -> HistoryCursor {
+    let (text, number) = match field {
+        HistorySortField::Created => (String::new(), item.created_timestamp as f64),
+        HistorySortField::Name => (item.name.to_lowercase(), 0.0),
+        HistorySortField::Size => (String::new(), item.file_size as f64),
+        // Unprobed items sort as if they were shortest
+        HistorySortField::Duration => (String::new(), item.properties.duration_seconds.unwrap_or(-1.0)),
+    };
+    HistoryCursor { text, number, id: item.id.clone() }
+}
+
+fn compare_positions(a: &HistoryCursor, b: &HistoryCursor, descending: bool) -> std::cmp::Ordering {
+    let ordering = a
+        .text
+        .cmp(&b.text)
+        .then(a.number.total_cmp(&b.number))
+        .then_with(|| a.id.cmp(&b.id));
+    if descending { ordering.reverse() } else { ordering }
+}
+
+pub fn matches_query(library: &Library, item: &AudioFileInfoFrontend, query: &HistoryQuery) -> bool {
+    if let Some(text) = query.text.as_deref().map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+        let uploader = library
+            .file(&item.id)
+            .and_then(|f| library.source_for_file(f))
+            .and_then(|s| s.uploader.as_deref());
+        let hit = contains_ci(Some(&item.name), &text)
+            || contains_ci(item.source_title.as_deref(), &text)
+            || contains_ci(uploader, &text)
+            || item.annotations.tags.iter().any(|t| contains_ci(Some(t), &text))
+            || contains_ci(item.annotations.note.as_deref(), &text);
+        if !hit {
+            return false;
+        }
+    }
+    if query.favorites_only && !item.annotations.favorite {
+        return false;
+    }
+    if !query.tags.iter().all(|tag| item.annotations.has_tag(tag)) {
+        return false;
+    }
+    if let Some(directory_type) = &query.directory_type {
+        if &item.directory_type != directory_type {
+            return false;
+        }
+    }
+    if !query.stems.is_empty()
+        && !item
+            .stem
+            .as_deref()
+            .is_some_and(|stem| query.stems.iter().any(|s| s.eq_ignore_ascii_case(stem)))
+    {
+        return false;
+    }
+    if !query.models.is_empty() && !item.model.as_ref().is_some_and(|m| query.models.contains(m)) {
+        return false;
+    }
+    if !query.formats.is_empty() {
+        let ext = Path::new(&item.file_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        if !ext.is_some_and(|ext| query.formats.iter().any(|f| f.trim_start_matches('.').eq_ignore_ascii_case(&ext))) {
+            return false;
+        }
+    }
+    if query.created_after.is_some_and(|after| item.created_timestamp < after) {
+        return false;
+    }
+    if query.created_before.is_some_and(|before| item.created_timestamp > before) {
+        return false;
+    }
+    true
+}
+
+/// Filter, sort and page a full history listing.
+pub fn query_history(
+    library: &Library,
+    items: Vec<AudioFileInfoFrontend>,
+    query: &HistoryQuery,
+) -> Result<HistoryPage, String> {
+    let mut matching: Vec<(HistoryCursor, AudioFileInfoFrontend)> = items
+        .into_iter()
+        .filter(|item| matches_query(library, item, query))
+        .map(|item| (sort_position(&item, query.sort_by), item))
+        .collect();
+    let descending = query.is_descending();
+    matching.sort_by(|a, b| compare_positions(&a.0, &b.0, descending));
+    let total = matching.len();
+    let all_sets = history_sets(matching.iter().map(|(_, item)| item));
+
+    let start = match query.cursor.as_deref().filter(|c| !c.is_empty()) {
+        Some(cursor) => {
+            let after: HistoryCursor =
+                serde_json::from_str(cursor).map_err(|_| "Invalid history cursor".to_string())?;
+            matching
+                .iter()
+                .position(|(pos, _)| compare_positions(pos, &after, descending) == std::cmp::Ordering::Greater)
+                .unwrap_or(matching.len())
+        }
+        None => 0,
+    };
+
+    let limit = query.limit.max(1);
+    let end = start.saturating_add(limit).min(matching.len());
+    let next_cursor = if end < matching.len() {
+        serde_json::to_string(&matching[end - 1].0).ok()
+    } else {
+        None
+    };
+
+    let items: Vec<AudioFileInfoFrontend> = matching.drain(start..end).map(|(_, item)| item).collect();
+    let sets = all_sets
+        .into_iter()
+        .filter(|set| items.iter().any(|item| item.set_path.as_ref() == Some(&set.path)))
+        .collect();
+
+    Ok(HistoryPage {
+        items,
+        sets,
+        next_cursor,
+        total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::{LibraryRecord, SourceRecord};
+
+    /// A library whose log isn't written until something is put into it.
+    fn empty_library() -> (Library, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        (Library::open(tmp.path().join("library.jsonl")).unwrap(), tmp)
+    }
+
+    fn sample() -> Vec<AudioFileInfoFrontend> {
+        vec![
+            AudioFileInfoFrontend::for_test("a", "Bravo.mp3", 300, 10),
+            AudioFileInfoFrontend::for_test("b", "alpha.wav", 100, 50),
+            AudioFileInfoFrontend::for_test("c", "Delta.flac", 500, 30),
+            AudioFileInfoFrontend::for_test("d", "charlie.mp3", 200, 40),
+            AudioFileInfoFrontend::for_test("e", "Echo.wav", 400, 20),
+        ]
+    }
+
+    fn ids(page: &HistoryPage) -> Vec<&str> {
+        page.items.iter().map(|i| i.id.as_str()).collect()
+    }
+
+    fn run(library: &Library, items: Vec<AudioFileInfoFrontend>, query: HistoryQuery) -> Vec<String> {
+        let page = query_history(library, items, &query).unwrap();
+        ids(&page).into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn default_order_is_newest_first_and_names_are_a_to_z() {
+        let (lib, _tmp) = empty_library();
+        assert_eq!(run(&lib, sample(), HistoryQuery::default()), ["c", "e", "a", "d", "b"]);
+        let by_name = HistoryQuery { sort_by: HistorySortField::Name, ..Default::default() };
+        assert_eq!(run(&lib, sample(), by_name), ["b", "a", "d", "c", "e"]);
+        let by_name_reversed = HistoryQuery {
+            sort_by: HistorySortField::Name,
+            descending: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(run(&lib, sample(), by_name_reversed), ["e", "c", "d", "a", "b"]);
+        let smallest_first = HistoryQuery {
+            sort_by: HistorySortField::Size,
+            descending: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(run(&lib, sample(), smallest_first), ["a", "e", "c", "d", "b"]);
+    }
+
+    #[test]
+    fn unprobed_items_sort_as_shortest() {
+        let (lib, _tmp) = empty_library();
+        let mut items = sample();
+        items[0].properties.duration_seconds = Some(90.0);
+        items[2].properties.duration_seconds = Some(30.0);
+        let query = HistoryQuery { sort_by: HistorySortField::Duration, limit: 2, ..Default::default() };
+        assert_eq!(run(&lib, items, query), ["a", "c"]);
+    }
+
+    #[test]
+    fn cursors_walk_every_item_once() {
+        let (lib, _tmp) = empty_library();
+        for limit in 1..=6 {
+            let mut seen = Vec::new();
+            let mut cursor = None;
+            let mut pages = 0;
+            loop {
+                let query = HistoryQuery { cursor: cursor.clone(), limit, ..Default::default() };
+                let page = query_history(&lib, sample(), &query).unwrap();
+                assert_eq!(page.total, 5);
+                assert!(page.items.len() <= limit);
+                seen.extend(ids(&page).into_iter().map(String::from));
+                pages += 1;
+                match page.next_cursor {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+            assert_eq!(seen, ["c", "e", "a", "d", "b"], "limit {}", limit);
+            // A last page that ends exactly on the boundary has no cursor
+            assert_eq!(pages, 5usize.div_ceil(limit), "limit {}", limit);
+        }
+    }
+
+    #[test]
+    fn cursor_survives_deletion_of_its_item() {
+        let (lib, _tmp) = empty_library();
+        let first = query_history(&lib, sample(), &HistoryQuery { limit: 2, ..Default::default() }).unwrap();
+        assert_eq!(ids(&first), ["c", "e"]);
+        let remaining: Vec<_> = sample().into_iter().filter(|i| i.id != "e").collect();
+        let query = HistoryQuery { cursor: first.next_cursor, limit: 2, ..Default::default() };
+        assert_eq!(run(&lib, remaining, query), ["a", "d"]);
+    }
+
+    #[test]
+    fn invalid_cursor_is_an_error() {
+        let (lib, _tmp) = empty_library();
+        let query = HistoryQuery { cursor: Some("not a cursor".to_string()), ..Default::default() };
+        assert!(query_history(&lib, sample(), &query).is_err());
+    }
+
+    #[test]
+    fn each_filter_narrows_the_items() {
+        let (mut lib, _tmp) = empty_library();
+        let mut items = sample();
+        items[0].annotations.add_tag("Live");
+        items[0].annotations.add_tag("Demo");
+        items[0].annotations.favorite = true;
+        items[1].annotations.add_tag("live");
+        items[1].annotations.note = Some("Great bassline".to_string());
+        items[2].directory_type = "separated".to_string();
+        items[2].stem = Some("Vocals".to_string());
+        items[2].model = Some("htdemucs.yaml".to_string());
+        items[3].source_title = Some("Midnight Session".to_string());
+        items[4].id = "f".to_string();
+        lib.put(LibraryRecord::Source(SourceRecord {
+            id: "s".to_string(),
+            url: "https://youtu.be/abc".to_string(),
+            resolved_url: None,
+            title: None,
+            uploader: Some("The Uploader".to_string()),
+            created_at: 0,
+        }))
+        .unwrap();
+        lib.put(LibraryRecord::File(FileRecord {
+            id: "f".to_string(),
+            role: FileRole::Download,
+            parent_id: Some("s".to_string()),
+            path: "/music/Downloads/Echo.wav".to_string(),
+            stem: None,
+            start_time: None,
+            end_time: None,
+            created_at: 0,
+            fingerprint: None,
+            fingerprinted: None,
+            annotations: FileAnnotations::default(),
+        }))
+        .unwrap();
+
+        let check = |query: HistoryQuery, expected: &[&str]| {
+            let mut got = run(&lib, items.clone(), query);
+            got.sort();
+            assert_eq!(got, expected);
+        };
+        check(HistoryQuery { text: Some("BRAVO".into()), ..Default::default() }, &["a"]);
+        check(HistoryQuery { text: Some("demo".into()), ..Default::default() }, &["a"]);
+        check(HistoryQuery { text: Some("bassline".into()), ..Default::default() }, &["b"]);
+        check(HistoryQuery { text: Some("midnight".into()), ..Default::default() }, &["d"]);
+        check(HistoryQuery { text: Some("uploader".into()), ..Default::default() }, &["f"]);
+        check(HistoryQuery { text: Some("  ".into()), ..Default::default() }, &["a", "b", "c", "d", "f"]);
+        check(HistoryQuery { tags: vec!["LIVE".into()], ..Default::default() }, &["a", "b"]);
+        check(HistoryQuery { tags: vec!["live".into(), "demo".into()], ..Default::default() }, &["a"]);
+        check(HistoryQuery { favorites_only: true, ..Default::default() }, &["a"]);
+        check(HistoryQuery { directory_type: Some("separated".into()), ..Default::default() }, &["c"]);
+        check(HistoryQuery { stems: vec!["vocals".into()], ..Default::default() }, &["c"]);
+        check(HistoryQuery { models: vec!["htdemucs.yaml".into()], ..Default::default() }, &["c"]);
+        check(HistoryQuery { formats: vec![".WAV".into()], ..Default::default() }, &["b", "f"]);
+        check(HistoryQuery { created_after: Some(300), ..Default::default() }, &["a", "c", "f"]);
+        check(HistoryQuery { created_before: Some(200), ..Default::default() }, &["b", "d"]);
+        check(
+            HistoryQuery { created_after: Some(200), created_before: Some(300), ..Default::default() },
+            &["a", "d"],
+        );
+    }
+
+    #[test]
+    fn intermediate_downloads_are_not_history_items() {
+        let options = HistoryScanOptions::default();
+        assert!(options.accepts(Path::new("/music/Downloads/Song.MP3")));
+        assert!(!options.accepts(Path::new("/music/Downloads/Song.webm")));
+        assert!(!options.accepts(Path::new("/music/Downloads/Song.webm.part")));
+    }
+
+    #[test]
+    fn only_direct_subfolders_of_downloads_and_separated_are_sets() {
+        let base = Path::new("/music");
+        assert!(is_set_folder(base, Path::new("/music/Separated/Song")));
+        assert!(is_set_folder(base, Path::new("/music/Downloads/Album")));
+        assert!(!is_set_folder(base, Path::new("/music")));
+        assert!(!is_set_folder(base, Path::new("/music/Separated")));
+        assert!(!is_set_folder(base, Path::new("/music/Downloads")));
+        assert!(!is_set_folder(base, Path::new("/music/.trash")));
+        assert!(!is_set_folder(base, Path::new("/music/Separated/Song/Vocals")));
+    }
+
+    #[test]
+    fn shared_library_scan_registers_new_files_with_fingerprints() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let song = output::downloads_dir(base).join("Song.mp3");
+        let stem = output::separated_dir(base).join("Song").join("Song_(Vocals)_htdemucs.wav");
+        for (path, content) in [(&song, b"song".as_slice()), (&stem, b"vocals".as_slice())] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let library = Mutex::new(Library::open(base.join("library.jsonl")).unwrap());
+        let options = HistoryScanOptions::default();
+
+        let (lib, items) = lock_and_collect(&library, base, &options).unwrap();
+        assert_eq!(items.len(), 2);
+        for path in [&song, &stem] {
+            let record = lib.file_by_path(path).unwrap();
+            assert_eq!(record.fingerprint, crate::library::fingerprint(path));
+            assert!(!lib.needs_fingerprint(path));
+        }
+        assert_eq!(lib.file_by_path(&stem).unwrap().role, FileRole::Stem);
+        drop(lib);
+
+        // A second scan finds nothing left to hash and keeps the same records
+        let (_lib, again) = lock_and_collect(&library, base, &options).unwrap();
+        let ids = |items: &[AudioFileInfoFrontend]| items.iter().map(|i| i.id.clone()).collect::<HashSet<_>>();
+        assert_eq!(ids(&again), ids(&items));
+    }
+}
@@ -0,0 +1,198 @@
+// Hot folders: watched directories whose new audio files are separated automatically.
+//
+// Each folder carries its own separation settings. A file is picked up once its
+// size and modification time have not changed for the folder's stable period, so
+// files still being copied in are left alone. Stems are written to a mirror of
+// the folder's structure under the output root, and the source is then moved to a
+// `done` or `failed` subfolder. The folder list lives in `hot_folders.json` in the
+// app data dir.
+
+use crate::watcher::{FileStamp, Snapshot};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const DONE_DIR: &str = "done";
+pub const FAILED_DIR: &str = "failed";
+pub const DEFAULT_STABLE_SECONDS: u64 = 10;
+/// How deep subfolders of a hot folder are scanned
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotFolder {
+    pub id: String,
+    pub path: String,
+    /// Root of the mirrored output tree
+    pub output_dir: String,
+    /// Separation settings used for this folder's jobs
+    pub profile: Value,
+    /// Seconds a file must stay unchanged before it is queued
+    pub stable_seconds: u64,
+    pub enabled: bool,
+    pub created_at: u64,
+}
+
+impl HotFolder {
+    /// Where the stems of `file` go: its folder's position in the hot folder,
+    /// mirrored under the output root.
+    pub fn output_dir_for(&self, file: &Path) -> PathBuf {
+        let root = PathBuf::from(&self.output_dir);
+        match file.strip_prefix(&self.path).ok().and_then(|rel| rel.parent()) {
+            Some(parent) => root.join(parent),
+            None => root,
+        }
+    }
+}
+
+pub struct HotFolderStore {
+    path: PathBuf,
+    folders: Vec<HotFolder>,
+}
+
+impl HotFolderStore {
+    pub fn open(path: PathBuf) -> Self {
+        let folders = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log_warn!("Ignoring unreadable hot folder list: {}", e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        HotFolderStore { path, folders }
+    }
+
+    pub fn list(&self) -> &[HotFolder] {
+        &self.folders
+    }
+
+    pub fn add(&mut self, folder: HotFolder) -> Result<(), String> {
+        let watched = Path::new(&folder.path);
+        if !watched.is_dir() {
+            return Err(format!("Folder does not exist: {}", folder.path));
+        }
+        if self.folders.iter().any(|f| Path::new(&f.path) == watched) {
+            return Err(format!("Folder is already watched: {}", folder.path));
+        }
+        // Stems written inside the hot folder would be picked up and separated again
+        if Path::new(&folder.output_dir).starts_with(watched) {
+            return Err("Output folder must not be inside the watched folder".to_string());
+        }
+        self.folders.push(folder);
+        self.save()
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<HotFolder, String> {
+        let index = self
+            .folders
+            .iter()
+            .position(|f| f.id == id)
+            .ok_or_else(|| format!("Hot folder not found: {}", id))?;
+        let removed = self.folders.remove(index);
+        self.save()?;
+        Ok(removed)
+    }
+
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> Result<HotFolder, String> {
+        let folder = self
+            .folders
+            .iter_mut()
+            .find(|f| f.id == id)
+            .ok_or_else(|| format!("Hot folder not found: {}", id))?;
+        folder.enabled = enabled;
+        let updated = folder.clone();
+        self.save()?;
+        Ok(updated)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.folders)
+            .map_err(|e| format!("Failed to serialize hot folders: {}", e))?;
+        fs::write(&self.path, content).map_err(|e| format!("Failed to save hot folders: {}", e))
+    }
+}
+
+fn collect(dir: &Path, depth: usize, accepts: &impl Fn(&Path) -> bool, out: &mut Snapshot) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_dir() {
+            let processed = depth == 0 && (name == DONE_DIR || name == FAILED_DIR);
+            if !processed && depth + 1 < MAX_DEPTH {
+                collect(&path, depth + 1, accepts, out);
+            }
+        } else if meta.is_file() && accepts(&path) {
+            out.insert(path, FileStamp::of(&meta));
+        }
+    }
+}
+
+/// Files in `folder` that `accepts` lets through, skipping its `done` and
+/// `failed` subfolders.
+pub fn pending_files(folder: &Path, accepts: impl Fn(&Path) -> bool) -> Snapshot {
+    let mut out = Snapshot::new();
+    collect(folder, 0, &accepts, &mut out);
+    out
+}
+
+/// Tracks how long each file has looked the same, to tell finished copies from
+/// files still being written.
+#[derive(Default)]
+pub struct StabilityTracker {
+    seen: HashMap<PathBuf, (FileStamp, Instant)>,
+    handled: HashSet<PathBuf>,
+}
+
+impl StabilityTracker {
+    /// Files in `current` unchanged for at least `stable_for`, excluding ones already
+    /// handed out. Files no longer present are forgotten.
+    pub fn ready(&mut self, current: &Snapshot, now: Instant, stable_for: Duration) -> Vec<PathBuf> {
+        self.seen.retain(|path, _| current.contains_key(path));
+        self.handled.retain(|path| current.contains_key(path));
+
+        let mut ready = Vec::new();
+        for (path, stamp) in current {
+            let entry = self.seen.entry(path.clone()).or_insert((*stamp, now));
+            if entry.0 != *stamp {
+                *entry = (*stamp, now);
+            }
+            if now.duration_since(entry.1) >= stable_for && !self.handled.contains(path) {
+                ready.push(path.clone());
+            }
+        }
+        ready.sort();
+        self.handled.extend(ready.iter().cloned());
+        ready
+    }
+}
+
+/// Move a processed source into `done` or `failed`, keeping its place in the
+/// folder structure. A file of the same name already there gets a numeric suffix.
+pub fn move_processed(folder: &Path, file: &Path, succeeded: bool) -> Result<PathBuf, String> {
+    let file_name = file.file_name().ok_or_else(|| format!("Not a file: {}", file.display()))?;
+    let parent = file.strip_prefix(folder).ok().and_then(|rel| rel.parent()).unwrap_or(Path::new(""));
+    let target_dir = folder.join(if succeeded { DONE_DIR } else { FAILED_DIR }).join(parent);
+    fs::create_dir_all(&target_dir).map_err(|e| format!("Failed to create {}: {}", target_dir.display(), e))?;
+
+    let stem = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = file.extension().map(|e| e.to_string_lossy().to_string());
+    let mut target = target_dir.join(file_name);
+    let mut counter = 2;
+    while target.exists() {
+        let name = match &extension {
+            Some(ext) => format!("{} ({}).{}", stem, counter, ext),
+            None => format!("{} ({})", stem, counter),
+        };
+        target = target_dir.join(name);
+        counter += 1;
+    }
+    fs::rename(file, &target).map_err(|e| format!("Failed to move {}: {}", file.display(), e))?;
+    Ok(target)
+}
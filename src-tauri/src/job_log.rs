@@ -0,0 +1,191 @@
+// Per-job record of the tool runs behind a download or separation.
+//
+// Each run keeps the exact program, arguments, environment additions and
+// working directory, the complete stdout and stderr, the exit code and how long
+// it took, so a failed job can be reproduced by hand. Logs are stored as
+// `job_logs/<job id>.json` in the app data dir, under the same id as the
+// library's job (separations) or source (downloads) record.
+
+use crate::error::{AppError, ErrorCode};
+use crate::library::now_secs;
+use crate::process::{ProcessOutput, ProcessSpec};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Job logs kept; older ones are deleted when a new one is saved
+const MAX_JOB_LOGS: usize = 500;
+/// Longest stdout or stderr kept per run; longer output keeps its end
+const MAX_STREAM_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Download,
+    Separation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Variables set on top of the inherited environment, e.g. the ffmpeg PATH
+    pub envs: Vec<(String, String)>,
+    /// The tool started from an empty environment plus `envs`
+    pub env_clear: bool,
+    pub current_dir: Option<String>,
+    /// Program and arguments quoted for this platform's shell
+    pub command_line: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` if the tool was killed or never started
+    pub exit_code: Option<i32>,
+    /// Why the tool could not be started
+    pub spawn_error: Option<String>,
+    /// Unix milliseconds
+    pub started_at: u64,
+    pub duration_ms: u64,
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c) || (cfg!(windows) && c == '\\'));
+    if plain {
+        arg.to_string()
+    } else if cfg!(windows) {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// `spec`'s program and arguments as one line to paste into a shell.
+pub fn command_line(spec: &ProcessSpec) -> String {
+    std::iter::once(spec.program.to_string_lossy().to_string())
+        .chain(spec.args.iter().cloned())
+        .map(|arg| quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The last `MAX_STREAM_BYTES` of `output`, cut at a character boundary.
+fn cap(output: &str) -> String {
+    if output.len() <= MAX_STREAM_BYTES {
+        return output.to_string();
+    }
+    let mut start = output.len() - MAX_STREAM_BYTES;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[{} bytes truncated]\n{}", start, &output[start..])
+}
+
+impl RunRecord {
+    pub fn new(
+        spec: &ProcessSpec,
+        started_at: SystemTime,
+        duration: Duration,
+        result: Result<&ProcessOutput, &str>,
+    ) -> Self {
+        let (stdout, stderr, exit_code, spawn_error) = match result {
+            Ok(output) => (cap(&output.stdout), cap(&output.stderr), output.status.code, None),
+            Err(e) => (String::new(), String::new(), None, Some(e.to_string())),
+        };
+        RunRecord {
+            program: spec.program.to_string_lossy().to_string(),
+            args: spec.args.clone(),
+            envs: spec.envs.clone(),
+            env_clear: spec.env_clear,
+            current_dir: spec.current_dir.as_ref().map(|d| d.to_string_lossy().to_string()),
+            command_line: command_line(spec),
+            stdout,
+            stderr,
+            exit_code,
+            spawn_error,
+            started_at: unix_millis(started_at),
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLog {
+    pub job_id: String,
+    pub kind: JobKind,
+    /// Unix seconds
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    /// Tool runs in order, including retries
+    pub runs: Vec<RunRecord>,
+    /// Why the job failed, if it did
+    pub error: Option<AppError>,
+}
+
+impl JobLog {
+    pub fn start(job_id: &str, kind: JobKind) -> Self {
+        JobLog {
+            job_id: job_id.to_string(),
+            kind,
+            started_at: now_secs(),
+            finished_at: None,
+            runs: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn finish(&mut self, error: Option<&AppError>) {
+        self.finished_at = Some(now_secs());
+        self.error = error.cloned();
+    }
+}
+
+/// Job ids are generated by us, but `get_job_log` takes them from the frontend.
+fn log_path(dir: &Path, job_id: &str) -> Result<PathBuf, AppError> {
+    if job_id.is_empty() || !job_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::invalid_input(format!("Invalid job id: {}", job_id)));
+    }
+    Ok(dir.join(format!("{}.json", job_id)))
+}
+
+/// Delete all but the newest `MAX_JOB_LOGS` logs in `dir`.
+fn prune(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    let mut logs: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    if logs.len() <= MAX_JOB_LOGS {
+        return;
+    }
+    logs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in &logs[MAX_JOB_LOGS..] {
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn save(dir: &Path, log: &JobLog) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create job log directory: {}", e))?;
+    let path = log_path(dir, &log.job_id).map_err(|e| e.message)?;
+    let content = serde_json::to_string(log).map_err(|e| format!("Failed to serialize job log: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to save job log: {}", e))?;
+    prune(dir);
+    Ok(())
+}
+
+pub fn load(dir: &Path, job_id: &str) -> Result<JobLog, AppError> {
+    let path = log_path(dir, job_id)?;
+    let content = fs::read_to_string(&path).map_err(|_| {
+        AppError::new(ErrorCode::NotFound, format!("No log for job {}", job_id))
+            .with_path(path.to_string_lossy())
+            .with_hint(format!("Logs are kept for the last {} jobs, and for jobs run since this version was installed.", MAX_JOB_LOGS))
+    })?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse job log: {}", e).into())
+}
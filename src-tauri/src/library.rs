@@ -0,0 +1,623 @@
+// Persistent media library.
+//
+// The library is an append-only JSON log (`library.jsonl` in the app data dir).
+// Every line is either a full record (`put`) or a tombstone (`remove`); replaying
+// the log in order yields the current state. Records link together so a stem can
+// be traced back to the separation job that made it, the file that job consumed
+// and the URL that file was downloaded from.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRecord {
+    pub id: String,
+    pub url: String,
+    pub resolved_url: Option<String>,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileRole {
+    /// Audio downloaded by yt-dlp, parent is a source
+    Download,
+    /// A file separation was run on that the app did not download
+    Local,
+    /// Separation output, parent is a job
+    Stem,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub id: String,
+    pub role: FileRole,
+    /// Source id for downloads, job id for stems
+    pub parent_id: Option<String>,
+    pub path: String,
+    pub stem: Option<String>,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    pub created_at: u64,
+    /// Content fingerprint used to find the file again after a rename
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// Size and modification time of the file when `fingerprint` was taken
+    #[serde(default)]
+    pub fingerprinted: Option<FileStamp>,
+    #[serde(flatten, default)]
+    pub annotations: FileAnnotations,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub len: u64,
+    /// Milliseconds since the epoch
+    pub modified: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+        Some(FileStamp { len: meta.len(), modified })
+    }
+}
+
+/// A file's content fingerprint with the stamp it describes. Hashing reads the
+/// whole file, so these are taken before the library is locked and handed to it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub hash: Option<String>,
+    /// `None` when the file changed while it was read, so the hash is retaken later
+    pub stamp: Option<FileStamp>,
+}
+
+impl Fingerprint {
+    pub fn take(path: &Path) -> Self {
+        let stamp = FileStamp::of(path);
+        let hash = fingerprint(path);
+        let stamp = stamp.filter(|s| FileStamp::of(path) == Some(*s));
+        Fingerprint { hash, stamp }
+    }
+}
+
+impl FileRecord {
+    /// Whether the fingerprint still describes the file at `path`. Records from
+    /// before stamps existed used a partial hash and are always re-taken.
+    fn fingerprint_is_current(&self, path: &Path) -> bool {
+        self.fingerprint.is_some() && self.fingerprinted.is_some() && self.fingerprinted == FileStamp::of(path)
+    }
+
+    /// A record only follows a file with the same content to a new name in the
+    /// same folder, or to the same name in another folder. Identical stems (silent
+    /// ones, say) in unrelated places stay separate items.
+    pub fn may_have_moved_to(&self, path: &Path) -> bool {
+        let old = Path::new(&self.path);
+        old.parent() == path.parent() || old.file_name() == path.file_name()
+    }
+}
+
+/// User-supplied metadata. Lives on the record rather than in the filename so it
+/// follows the file through renames.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileAnnotations {
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub note: Option<String>,
+}
+
+impl FileAnnotations {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !self.has_tag(tag) {
+            self.tags.push(tag.to_string());
+        }
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        self.tags.retain(|t| !t.eq_ignore_ascii_case(tag.trim()));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: String,
+    /// File record the job consumed
+    pub input_id: String,
+    pub model: String,
+    pub output_dir: String,
+    pub settings: serde_json::Value,
+    pub selected_stems: Vec<String>,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LibraryRecord {
+    Source(SourceRecord),
+    File(FileRecord),
+    Job(JobRecord),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogEntry {
+    Put { record: Box<LibraryRecord> },
+    Remove { id: String },
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+pub struct Library {
+    log_path: PathBuf,
+    sources: HashMap<String, SourceRecord>,
+    files: HashMap<String, FileRecord>,
+    jobs: HashMap<String, JobRecord>,
+    /// path -> file id, so scans of large folders stay linear
+    paths: HashMap<String, String>,
+}
+
+impl Library {
+    /// Load the library from `log_path`, creating an empty one if the file does not exist yet.
+    /// Lines that fail to parse are skipped so a torn write never loses the whole library.
+    pub fn open(log_path: PathBuf) -> Result<Self, String> {
+        let mut library = Library {
+            log_path,
+            sources: HashMap::new(),
+            files: HashMap::new(),
+            jobs: HashMap::new(),
+            paths: HashMap::new(),
+        };
+
+        if !library.log_path.exists() {
+            return Ok(library);
+        }
+
+        let file = fs::File::open(&library.log_path)
+            .map_err(|e| format!("Failed to open library: {}", e))?;
+        let mut entries = 0usize;
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read library: {}", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<LogEntry>(&line) {
+                Ok(entry) => {
+                    entries += 1;
+                    library.apply(entry);
+                }
+                Err(e) => log_warn!("Skipping unreadable library entry: {}", e),
+            }
+        }
+
+        // Rewrite the log once superseded entries outnumber live records
+        if entries > 2 * library.len() + 100 {
+            library.compact()?;
+        }
+
+        Ok(library)
+    }
+
+    fn len(&self) -> usize {
+        self.sources.len() + self.files.len() + self.jobs.len()
+    }
+
+    fn apply(&mut self, entry: LogEntry) {
+        match entry {
+            LogEntry::Put { record } => match *record {
+                LibraryRecord::Source(r) => {
+                    self.sources.insert(r.id.clone(), r);
+                }
+                LibraryRecord::File(r) => {
+                    if let Some(old) = self.files.get(&r.id) {
+                        if self.paths.get(&old.path) == Some(&r.id) {
+                            self.paths.remove(&old.path);
+                        }
+                    }
+                    self.paths.insert(r.path.clone(), r.id.clone());
+                    self.files.insert(r.id.clone(), r);
+                }
+                LibraryRecord::Job(r) => {
+                    self.jobs.insert(r.id.clone(), r);
+                }
+            },
+            LogEntry::Remove { id } => {
+                self.sources.remove(&id);
+                if let Some(old) = self.files.remove(&id) {
+                    if self.paths.get(&old.path) == Some(&id) {
+                        self.paths.remove(&old.path);
+                    }
+                }
+                self.jobs.remove(&id);
+            }
+        }
+    }
+
+    fn append(&mut self, entry: LogEntry) -> Result<(), String> {
+        if let Some(parent) = self.log_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create library directory: {}", e))?;
+        }
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize library entry: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)
+            .map_err(|e| format!("Failed to open library: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write library: {}", e))?;
+        self.apply(entry);
+        Ok(())
+    }
+
+    fn compact(&mut self) -> Result<(), String> {
+        let tmp_path = self.log_path.with_extension("jsonl.tmp");
+        {
+            let mut file = fs::File::create(&tmp_path)
+                .map_err(|e| format!("Failed to compact library: {}", e))?;
+            let records = self
+                .sources
+                .values()
+                .cloned()
+                .map(LibraryRecord::Source)
+                .chain(self.jobs.values().cloned().map(LibraryRecord::Job))
+                .chain(self.files.values().cloned().map(LibraryRecord::File));
+            for record in records {
+                let line = serde_json::to_string(&LogEntry::Put { record: Box::new(record) })
+                    .map_err(|e| format!("Failed to serialize library entry: {}", e))?;
+                writeln!(file, "{}", line).map_err(|e| format!("Failed to compact library: {}", e))?;
+            }
+        }
+        fs::rename(&tmp_path, &self.log_path).map_err(|e| format!("Failed to compact library: {}", e))
+    }
+
+    /// Insert or replace a record.
+    pub fn put(&mut self, record: LibraryRecord) -> Result<(), String> {
+        self.append(LogEntry::Put { record: Box::new(record) })
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<(), String> {
+        if self.sources.contains_key(id) || self.files.contains_key(id) || self.jobs.contains_key(id) {
+            self.append(LogEntry::Remove { id: id.to_string() })?;
+        }
+        Ok(())
+    }
+
+    pub fn source(&self, id: &str) -> Option<&SourceRecord> {
+        self.sources.get(id)
+    }
+
+    pub fn file(&self, id: &str) -> Option<&FileRecord> {
+        self.files.get(id)
+    }
+
+    pub fn job(&self, id: &str) -> Option<&JobRecord> {
+        self.jobs.get(id)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &FileRecord> {
+        self.files.values()
+    }
+
+    pub fn jobs(&self) -> impl Iterator<Item = &JobRecord> {
+        self.jobs.values()
+    }
+
+    pub fn file_by_path(&self, path: &Path) -> Option<&FileRecord> {
+        let id = self.paths.get(path.to_string_lossy().as_ref())?;
+        self.files.get(id)
+    }
+
+    /// Whether `path` is unknown or its record's fingerprint is out of date, i.e.
+    /// whether `ensure_file` would hash it.
+    pub fn needs_fingerprint(&self, path: &Path) -> bool {
+        self.file_by_path(path).is_none_or(|f| !f.fingerprint_is_current(path))
+    }
+
+    /// Return the existing record for `path`, or register it with the given role.
+    /// Records whose ids are in `trashed` belong to files in the app trash and are
+    /// never taken over by a new file.
+    pub fn ensure_file(&mut self, path: &Path, role: FileRole, trashed: &HashSet<String>) -> Result<FileRecord, String> {
+        self.ensure_file_with(path, role, trashed, None)
+    }
+
+    /// `ensure_file` with a fingerprint taken beforehand, outside the library lock.
+    /// Without one the file is hashed here if it needs it.
+    pub fn ensure_file_with(
+        &mut self,
+        path: &Path,
+        role: FileRole,
+        trashed: &HashSet<String>,
+        taken: Option<Fingerprint>,
+    ) -> Result<FileRecord, String> {
+        if let Some(existing) = self.file_by_path(path).cloned() {
+            // Re-hash when the file changed since the fingerprint was taken, e.g.
+            // because it was still being written then
+            if !existing.fingerprint_is_current(path) {
+                let Fingerprint { hash, stamp } = taken.unwrap_or_else(|| Fingerprint::take(path));
+                if let Some(fp) = hash {
+                    let updated = FileRecord { fingerprint: Some(fp), fingerprinted: stamp, ..existing };
+                    self.put(LibraryRecord::File(updated.clone()))?;
+                    return Ok(updated);
+                }
+            }
+            return Ok(existing);
+        }
+
+        // An unknown path with the content of a missing record is that record, renamed
+        let Fingerprint { hash: fp, stamp } = taken.unwrap_or_else(|| Fingerprint::take(path));
+        if let Some(moved) = fp.as_deref().and_then(|fp| self.missing_file_with_fingerprint(fp, path, trashed)) {
+            let id = moved.id.clone();
+            return self.relocate(&id, path);
+        }
+
+        let created_at = fs::metadata(path)
+            .ok()
+            .and_then(|m| m.created().or_else(|_| m.modified()).ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_else(now_secs);
+        let record = FileRecord {
+            id: new_id(),
+            role,
+            parent_id: None,
+            path: path.to_string_lossy().to_string(),
+            stem: if role == FileRole::Stem { stem_from_filename(path) } else { None },
+            start_time: None,
+            end_time: None,
+            created_at,
+            fingerprint: fp,
+            fingerprinted: stamp,
+            annotations: FileAnnotations::default(),
+        };
+        self.put(LibraryRecord::File(record.clone()))?;
+        Ok(record)
+    }
+
+    /// A record whose file is gone from its recorded path but whose content matches
+    /// `fp` and which may have been moved to `path`. Oldest first, so the choice is
+    /// stable when several match.
+    pub fn missing_file_with_fingerprint(&self, fp: &str, path: &Path, trashed: &HashSet<String>) -> Option<&FileRecord> {
+        self.files
+            .values()
+            .filter(|f| f.fingerprint.as_deref() == Some(fp) && !trashed.contains(&f.id))
+            .filter(|f| f.may_have_moved_to(path) && !Path::new(&f.path).exists())
+            .min_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)))
+    }
+
+    /// Point an existing file record at a new path, keeping its id and links.
+    pub fn relocate(&mut self, id: &str, new_path: &Path) -> Result<FileRecord, String> {
+        let mut record = self
+            .file(id)
+            .cloned()
+            .ok_or_else(|| "History item not found".to_string())?;
+        log_info!("Library file {} moved: {} -> {}", id, record.path, new_path.display());
+        record.path = new_path.to_string_lossy().to_string();
+        self.put(LibraryRecord::File(record.clone()))?;
+        Ok(record)
+    }
+
+    /// Apply `update` to a file record and persist the result.
+    pub fn update_file<F>(&mut self, id: &str, update: F) -> Result<FileRecord, String>
+    where
+        F: FnOnce(&mut FileRecord),
+    {
+        let mut record = self
+            .file(id)
+            .cloned()
+            .ok_or_else(|| "History item not found".to_string())?;
+        update(&mut record);
+        self.put(LibraryRecord::File(record.clone()))?;
+        Ok(record)
+    }
+
+    /// The source a file ultimately came from, following stem -> job -> input -> source.
+    pub fn source_for_file(&self, file: &FileRecord) -> Option<&SourceRecord> {
+        match file.role {
+            FileRole::Download => file.parent_id.as_deref().and_then(|id| self.source(id)),
+            FileRole::Stem => {
+                let job = file.parent_id.as_deref().and_then(|id| self.job(id))?;
+                let input = self.file(&job.input_id)?;
+                if input.role == FileRole::Stem {
+                    // Guard against cycles from hand-edited logs
+                    return None;
+                }
+                self.source_for_file(input)
+            }
+            FileRole::Local => None,
+        }
+    }
+
+    /// Jobs that consumed the given file, oldest first.
+    pub fn jobs_for_input(&self, file_id: &str) -> Vec<&JobRecord> {
+        let mut jobs: Vec<&JobRecord> = self.jobs.values().filter(|j| j.input_id == file_id).collect();
+        jobs.sort_by_key(|j| j.created_at);
+        jobs
+    }
+
+    /// Output files written by a job.
+    pub fn outputs_of_job(&self, job_id: &str) -> Vec<&FileRecord> {
+        let mut outputs: Vec<&FileRecord> = self
+            .files
+            .values()
+            .filter(|f| f.role == FileRole::Stem && f.parent_id.as_deref() == Some(job_id))
+            .collect();
+        outputs.sort_by(|a, b| a.path.cmp(&b.path));
+        outputs
+    }
+}
+
+/// SHA-256 of the whole file. Stems from one separation share their length and
+/// headers, so nothing short of the full content tells them apart. Records keep
+/// the stamp it was taken at and it is only re-run when the file changes; see
+/// `Fingerprint::take` for taking one without holding the library lock.
+pub fn fingerprint(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 256 * 1024];
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    let digest = hasher.finalize();
+    Some(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// audio-separator names outputs `<input>_(<Stem>)_<model>.<ext>`; pull out the stem.
+pub fn stem_from_filename(path: &Path) -> Option<String> {
+    let name = path.file_stem()?.to_str()?;
+    let start = name.rfind("_(")? + 2;
+    let end = start + name[start..].find(')')?;
+    let stem = &name[start..end];
+    if stem.is_empty() {
+        None
+    } else {
+        Some(stem.to_string())
+    }
+}
+
+impl From<SourceRecord> for LibraryRecord {
+    fn from(r: SourceRecord) -> Self {
+        LibraryRecord::Source(r)
+    }
+}
+
+impl From<FileRecord> for LibraryRecord {
+    fn from(r: FileRecord) -> Self {
+        LibraryRecord::File(r)
+    }
+}
+
+impl From<JobRecord> for LibraryRecord {
+    fn from(r: JobRecord) -> Self {
+        LibraryRecord::Job(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn renamed_file_keeps_its_record() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let old = dir.join("Downloads").join("Song.mp3");
+        write(&old, b"audio");
+        let record = lib.ensure_file(&old, FileRole::Download, &HashSet::new()).unwrap();
+        lib.update_file(&record.id, |f| f.annotations.add_tag("keep")).unwrap();
+
+        let renamed = dir.join("Downloads").join("Song (live).mp3");
+        fs::rename(&old, &renamed).unwrap();
+        let found = lib.ensure_file(&renamed, FileRole::Download, &HashSet::new()).unwrap();
+        assert_eq!(found.id, record.id);
+        assert!(found.annotations.has_tag("keep"));
+
+        // Same name, other folder
+        let moved = dir.join("Elsewhere").join("Song (live).mp3");
+        fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        fs::rename(&renamed, &moved).unwrap();
+        assert_eq!(lib.ensure_file(&moved, FileRole::Download, &HashSet::new()).unwrap().id, record.id);
+
+        // The rename survives a reload
+        let lib = Library::open(dir.join("library.jsonl")).unwrap();
+        assert_eq!(lib.file_by_path(&moved).unwrap().id, record.id);
+    }
+
+    #[test]
+    fn identical_stems_elsewhere_are_new_items() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut lib = Library::open(dir.join("library.jsonl")).unwrap();
+        // Two silent stems of one length: same bytes, from different jobs
+        let silent = vec![0u8; 300 * 1024];
+        let old = dir.join("Separated").join("a").join("Intro_(Vocals)_model.wav");
+        write(&old, &silent);
+        let old_record = lib.ensure_file(&old, FileRole::Stem, &HashSet::new()).unwrap();
+        fs::remove_file(&old).unwrap();
+
+        let new = dir.join("Separated").join("b").join("Outro_(Drums)_model.wav");
+        write(&new, &silent);
+        let new_record = lib.ensure_file(&new, FileRole::Stem, &HashSet::new()).unwrap();
+        assert_ne!(new_record.id, old_record.id);
+        assert_eq!(new_record.stem.as_deref(), Some("Drums"));
+    }
+
+    #[test]
+    fn stems_differing_only_in_the_middle_have_different_fingerprints() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut a = vec![0u8; 300 * 1024];
+        let b = a.clone();
+        a[150 * 1024] = 1;
+        write(&dir.join("a.wav"), &a);
+        write(&dir.join("b.wav"), &b);
+        assert_ne!(fingerprint(&dir.join("a.wav")), fingerprint(&dir.join("b.wav")));
+    }
+
+    #[test]
+    fn trashed_records_are_not_taken_over() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let old = dir.join("Downloads").join("Song.mp3");
+        write(&old, b"audio");
+        let record = lib.ensure_file(&old, FileRole::Download, &HashSet::new()).unwrap();
+        fs::remove_file(&old).unwrap();
+
+        let copy = dir.join("Downloads").join("Song copy.mp3");
+        write(&copy, b"audio");
+        let trashed = HashSet::from([record.id.clone()]);
+        assert_ne!(lib.ensure_file(&copy, FileRole::Download, &trashed).unwrap().id, record.id);
+    }
+
+    #[test]
+    fn fingerprint_is_retaken_when_the_file_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let path = dir.join("Downloads").join("Song.mp3");
+        // Seen while yt-dlp was still writing it
+        write(&path, b"partial");
+        let first = lib.ensure_file(&path, FileRole::Download, &HashSet::new()).unwrap();
+
+        write(&path, b"partial and the rest");
+        let second = lib.ensure_file(&path, FileRole::Download, &HashSet::new()).unwrap();
+        assert_eq!(second.id, first.id);
+        assert_ne!(second.fingerprint, first.fingerprint);
+        assert_eq!(second.fingerprint, fingerprint(&path));
+        assert_eq!(second.fingerprinted, FileStamp::of(&path));
+    }
+}
@@ -0,0 +1,239 @@
+// Structured logging.
+//
+// Every entry has a level, an optional job id and a message. Entries are printed
+// to stderr as `[LEVEL] message`, so stdout stays free for the CLI's JSON output,
+// appended as JSON lines to `resample2.log` in the log directory once `init` has
+// been called, and handed to the listener, which the app forwards to the UI as
+// `log-entry` events. The file is rotated when it grows past `MAX_FILE_BYTES`,
+// keeping `KEPT_FILES` old ones.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const LOG_FILE: &str = "resample2.log";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept next to the current one: resample2.1.log is the newest
+const KEPT_FILES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warning => "WARNING",
+            Level::Error => "ERROR",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Level> {
+        match value.trim().to_lowercase().as_str() {
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warning" | "warn" => Some(Level::Warning),
+            "error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// RFC 3339, local time
+    pub timestamp: String,
+    pub level: Level,
+    pub job_id: Option<String>,
+    pub message: String,
+}
+
+type Listener = Arc<dyn Fn(&LogEntry) + Send + Sync>;
+
+struct Logger {
+    dir: Option<PathBuf>,
+    file: Option<File>,
+    size: u64,
+    listener: Option<Listener>,
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger {
+    dir: None,
+    file: None,
+    size: 0,
+    listener: None,
+});
+
+/// Start writing log files to `dir`.
+pub fn init(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+    let (file, size) = open_log(dir)?;
+    let mut logger = LOGGER.lock().map_err(|_| "Logger lock poisoned".to_string())?;
+    logger.dir = Some(dir.to_path_buf());
+    logger.file = Some(file);
+    logger.size = size;
+    Ok(())
+}
+
+/// Call `listener` with every entry logged from now on.
+pub fn set_listener(listener: impl Fn(&LogEntry) + Send + Sync + 'static) {
+    if let Ok(mut logger) = LOGGER.lock() {
+        logger.listener = Some(Arc::new(listener));
+    }
+}
+
+/// The directory passed to `init`, if logging to files.
+pub fn log_dir() -> Option<PathBuf> {
+    LOGGER.lock().ok().and_then(|logger| logger.dir.clone())
+}
+
+fn open_log(dir: &Path) -> Result<(File, u64), String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE))
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Ok((file, size))
+}
+
+/// `resample2.log` for 0, `resample2.<n>.log` for the rotated ones.
+fn log_file_name(index: usize) -> String {
+    if index == 0 {
+        LOG_FILE.to_string()
+    } else {
+        format!("resample2.{}.log", index)
+    }
+}
+
+/// Every log file in `dir` that exists, oldest first.
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    (0..=KEPT_FILES)
+        .rev()
+        .map(|index| dir.join(log_file_name(index)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn rotate(logger: &mut Logger) {
+    let Some(dir) = logger.dir.clone() else { return };
+    logger.file = None;
+    for index in (1..=KEPT_FILES).rev() {
+        let from = dir.join(log_file_name(index - 1));
+        if from.exists() {
+            let _ = fs::rename(&from, dir.join(log_file_name(index)));
+        }
+    }
+    if let Ok((file, size)) = open_log(&dir) {
+        logger.file = Some(file);
+        logger.size = size;
+    }
+}
+
+pub fn log(level: Level, job_id: Option<&str>, message: String) {
+    eprintln!("[{}] {}", level.label(), message);
+    let entry = LogEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        level,
+        job_id: job_id.map(str::to_string),
+        message,
+    };
+
+    let listener = match LOGGER.lock() {
+        Ok(mut logger) => {
+            if let Ok(mut line) = serde_json::to_string(&entry) {
+                line.push('\n');
+                if logger.size + line.len() as u64 > MAX_FILE_BYTES {
+                    rotate(&mut logger);
+                }
+                if let Some(file) = logger.file.as_mut() {
+                    if file.write_all(line.as_bytes()).is_ok() {
+                        logger.size += line.len() as u64;
+                    }
+                }
+            }
+            logger.listener.clone()
+        }
+        Err(_) => None,
+    };
+    // Outside the lock, so a listener that logs doesn't deadlock
+    if let Some(listener) = listener {
+        listener(&entry);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    /// Entries at this level or above
+    pub min_level: Option<Level>,
+    pub job_id: Option<String>,
+    /// Most recent entries to return; 0 means all
+    pub limit: usize,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        self.min_level.is_none_or(|level| entry.level >= level)
+            && self.job_id.as_ref().is_none_or(|job| entry.job_id.as_ref() == Some(job))
+    }
+}
+
+/// The newest entries in `dir` matching `filter`, oldest first. Lines that don't
+/// parse are skipped.
+pub fn recent(dir: &Path, filter: &LogFilter) -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+    for path in log_files(dir) {
+        let Ok(file) = File::open(&path) else { continue };
+        entries.extend(
+            BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter_map(|line| serde_json::from_str::<LogEntry>(&line).ok())
+                .filter(|entry| filter.matches(entry)),
+        );
+    }
+    if filter.limit > 0 && entries.len() > filter.limit {
+        entries.drain(..entries.len() - filter.limit);
+    }
+    entries
+}
+
+#[macro_export]
+macro_rules! log_info {
+    (job = $job:expr; $($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, Some($job), format!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, None, format!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    (job = $job:expr; $($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warning, Some($job), format!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warning, None, format!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    (job = $job:expr; $($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Error, Some($job), format!($($arg)*))
+    };
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Error, None, format!($($arg)*))
+    };
+}
@@ -0,0 +1,175 @@
+// Desktop notifications for finished jobs.
+//
+// Downloads and separations post a notification when they complete, fail or are
+// cancelled, so the app can stay in the background while a DAW has focus. On
+// Linux, `notify-send` can attach a "Copy stems" action; elsewhere the
+// notification is informational and is shown through the Tauri notification plugin.
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::process::ProcessRunner;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// The `notifications` object of settings.json.
+#[derive(Debug, Clone)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Skip notifications while the app window has focus
+    pub only_when_unfocused: bool,
+    pub downloads: bool,
+    pub separations: bool,
+    /// Notify about failed and cancelled jobs too
+    pub failures: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            only_when_unfocused: false,
+            downloads: true,
+            separations: true,
+            failures: true,
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn from_settings_value(settings: &Value) -> Self {
+        let defaults = Self::default();
+        let section = settings.get("notifications");
+        let flag = |key: &str, default: bool| {
+            section
+                .and_then(|s| s.get(key))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(default)
+        };
+        Self {
+            enabled: flag("enabled", defaults.enabled),
+            only_when_unfocused: flag("only_when_unfocused", defaults.only_when_unfocused),
+            downloads: flag("downloads", defaults.downloads),
+            separations: flag("separations", defaults.separations),
+            failures: flag("failures", defaults.failures),
+        }
+    }
+
+    /// Whether `notice` should be shown, given whether the app window has focus.
+    pub fn allows(&self, notice: &JobNotice, window_focused: bool) -> bool {
+        if !self.enabled || (self.only_when_unfocused && window_focused) {
+            return false;
+        }
+        let kind_enabled = match notice.kind {
+            JobKind::Download => self.downloads,
+            JobKind::Separation => self.separations,
+        };
+        kind_enabled && (self.failures || matches!(notice.outcome, JobOutcome::Completed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Download,
+    Separation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobOutcome {
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+/// A finished job, as shown in its notification.
+#[derive(Debug, Clone)]
+pub struct JobNotice {
+    pub kind: JobKind,
+    pub outcome: JobOutcome,
+    pub title: String,
+    pub elapsed: Duration,
+    /// Separation outputs, offered by the "Copy stems" action
+    pub stems: Vec<PathBuf>,
+}
+
+/// "45s", "3m 07s" or "1h 02m".
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+impl JobNotice {
+    pub fn summary(&self) -> String {
+        let job = match self.kind {
+            JobKind::Download => "Download",
+            JobKind::Separation => "Separation",
+        };
+        match self.outcome {
+            JobOutcome::Completed => format!("{} finished", job),
+            JobOutcome::Failed(_) => format!("{} failed", job),
+            JobOutcome::Cancelled => format!("{} cancelled", job),
+        }
+    }
+
+    pub fn body(&self) -> String {
+        let mut details = vec![format_elapsed(self.elapsed)];
+        if self.kind == JobKind::Separation && self.outcome == JobOutcome::Completed {
+            let count = self.stems.len();
+            details.push(format!("{} stem{}", count, if count == 1 { "" } else { "s" }));
+        }
+        let mut body = format!("{}\n{}", self.title, details.join(" · "));
+        if let JobOutcome::Failed(error) = &self.outcome {
+            // Tool errors can run to pages of stderr; the first line is enough here
+            let first_line = error.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+            let short: String = first_line.chars().take(160).collect();
+            body.push('\n');
+            body.push_str(short.trim());
+        }
+        body
+    }
+
+    /// Whether the notification should offer to copy the stems.
+    pub fn has_copy_action(&self) -> bool {
+        self.outcome == JobOutcome::Completed && !self.stems.is_empty()
+    }
+}
+
+/// Show `notice` with notify-send, blocking until it is dismissed when it carries
+/// an action. Returns whether the user chose "Copy stems".
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn show_with_notify_send(runner: &dyn ProcessRunner, notice: &JobNotice) -> Result<bool, String> {
+    use crate::process::ProcessSpec;
+
+    which::which("notify-send").map_err(|_| "notify-send not installed".to_string())?;
+    let urgency = match notice.outcome {
+        JobOutcome::Failed(_) => "critical",
+        _ => "normal",
+    };
+    let base_args = [
+        "--app-name=Resample2".to_string(),
+        format!("--urgency={}", urgency),
+        notice.summary(),
+        notice.body(),
+    ];
+
+    if notice.has_copy_action() {
+        // --action needs libnotify 0.7.10 or newer; older versions reject it and we
+        // fall through to a plain notification
+        let spec = ProcessSpec::new("notify-send")
+            .args(["--action=copy=Copy stems", "--wait"])
+            .args(base_args.clone());
+        let output = runner.output(&spec).map_err(|e| format!("Failed to run notify-send: {}", e))?;
+        if output.status.success() {
+            return Ok(output.stdout.trim() == "copy");
+        }
+        log_warn!("notify-send does not support actions: {}", output.stderr.trim());
+    }
+
+    let output = runner
+        .output(&ProcessSpec::new("notify-send").args(base_args).discard_output())
+        .map_err(|e| format!("Failed to run notify-send: {}", e))?;
+    if output.status.success() { Ok(false) } else { Err(format!("notify-send failed ({})", output.status)) }
+}
@@ -0,0 +1,193 @@
+// Output locations and naming.
+//
+// Downloads always go to `<download base>/Downloads`. Separation output goes to
+// `<download base>/Separated` so it shows up in history, unless the user picks an
+// explicit output folder or asks for the stems to be written next to the source.
+// Separated files can then be renamed with a template such as
+// `{title}/{title} - {stem}`.
+
+use crate::trash;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputLocation {
+    /// `<download base>/Separated`
+    #[default]
+    Managed,
+    /// A `Separated` folder next to the input file
+    NextToSource,
+}
+
+pub fn downloads_dir(download_base: &Path) -> PathBuf {
+    download_base.join("Downloads")
+}
+
+pub fn separated_dir(download_base: &Path) -> PathBuf {
+    download_base.join("Separated")
+}
+
+/// Where a separation of `input` writes its files. A non-empty `explicit_dir`
+/// always wins.
+pub fn resolve_separation_dir(
+    download_base: &Path,
+    input: &Path,
+    explicit_dir: &str,
+    location: OutputLocation,
+) -> PathBuf {
+    if !explicit_dir.trim().is_empty() {
+        return PathBuf::from(explicit_dir);
+    }
+    match location {
+        OutputLocation::Managed => separated_dir(download_base),
+        OutputLocation::NextToSource => input
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("Separated"),
+    }
+}
+
+/// Values substituted into an output name template.
+#[derive(Debug, Clone)]
+pub struct NameContext {
+    pub title: String,
+    pub model: String,
+    pub date: String,
+}
+
+/// Make a placeholder value safe to use inside a single path component.
+fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let trimmed = cleaned.trim().trim_matches('.');
+    if trimmed.is_empty() { "untitled".to_string() } else { trimmed.to_string() }
+}
+
+/// Render `template` for one output file, as a path relative to the output folder
+/// without extension. `/` in the template itself creates subfolders. Returns `None`
+/// for an empty template or one that would escape the output folder.
+pub fn render_name(template: &str, ctx: &NameContext, stem: Option<&str>) -> Option<PathBuf> {
+    let template = template.trim();
+    if template.is_empty() {
+        return None;
+    }
+    let rendered = template
+        .replace("{title}", &sanitize(&ctx.title))
+        .replace("{model}", &sanitize(&ctx.model))
+        .replace("{date}", &sanitize(&ctx.date))
+        .replace("{stem}", &sanitize(stem.unwrap_or("output")));
+
+    let mut relative = PathBuf::new();
+    for part in rendered.split(['/', '\\']) {
+        let part = part.trim();
+        if part.is_empty() || part == "." {
+            continue;
+        }
+        if part == ".." {
+            return None;
+        }
+        relative.push(part);
+    }
+    if relative.as_os_str().is_empty() { None } else { Some(relative) }
+}
+
+/// Rename separation outputs according to `template`. Files whose rendered name
+/// collides with another output of the same run get a numeric suffix, and files
+/// from earlier runs already at a target are moved to the trash under
+/// `download_base`. Returns the final paths in the same order; a file that can't
+/// be renamed keeps its name.
+pub fn apply_name_template(
+    download_base: &Path,
+    output_dir: &Path,
+    files: &[String],
+    template: &str,
+    ctx: &NameContext,
+    stem_of: impl Fn(&Path) -> Option<String>,
+) -> Vec<String> {
+    let mut renamed = Vec::with_capacity(files.len());
+    let mut taken: Vec<PathBuf> = Vec::new();
+
+    for file in files {
+        let path = Path::new(file);
+        let Some(relative) = render_name(template, ctx, stem_of(path).as_deref()) else {
+            renamed.push(file.clone());
+            continue;
+        };
+        let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+        let with_suffix = |suffix: &str| {
+            let mut target = output_dir.join(&relative).into_os_string();
+            target.push(suffix);
+            if let Some(ext) = &extension {
+                target.push(".");
+                target.push(ext);
+            }
+            PathBuf::from(target)
+        };
+        let mut target = with_suffix("");
+        let mut counter = 2;
+        while taken.contains(&target) {
+            target = with_suffix(&format!(" ({})", counter));
+            counter += 1;
+        }
+
+        match rename_output(download_base, path, &target) {
+            Ok(()) => {
+                taken.push(target.clone());
+                renamed.push(target.to_string_lossy().to_string());
+            }
+            Err(e) => {
+                log_warn!("{}", e);
+                renamed.push(file.clone());
+            }
+        }
+    }
+    renamed
+}
+
+fn rename_output(download_base: &Path, from: &Path, to: &Path) -> Result<(), String> {
+    if from == to {
+        return Ok(());
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create output folder: {}", e))?;
+    }
+    // Earlier runs with the same name are replaced, as audio-separator itself
+    // does, but kept in the trash so they can be restored
+    if to.exists() {
+        trash::move_to_trash(download_base, to, trash::TrashKind::File, None, Vec::new())
+            .map_err(|e| format!("Failed to replace {}: {}", to.display(), e))?;
+    }
+    fs::rename(from, to).map_err(|e| format!("Failed to rename {}: {}", from.display(), e))?;
+    log_info!("Renamed output: {} -> {}", from.display(), to.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaced_output_goes_to_the_trash() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let dir = separated_dir(base);
+        fs::create_dir_all(&dir).unwrap();
+        let (new, old) = (dir.join("song_(Vocals).wav"), dir.join("Song - Vocals.wav"));
+        fs::write(&new, b"new").unwrap();
+        fs::write(&old, b"old").unwrap();
+
+        rename_output(base, &new, &old).unwrap();
+        assert_eq!(fs::read(&old).unwrap(), b"new");
+        let trashed = trash::list(base).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(fs::read(&trashed[0].trashed_path).unwrap(), b"old");
+    }
+}
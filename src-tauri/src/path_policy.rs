@@ -0,0 +1,222 @@
+// Path policy for file-operation commands.
+//
+// Paths coming from the webview are canonicalized and only accepted when they
+// fall inside a directory the app manages (download base, model directory),
+// are files the library recorded as downloads or separation outputs, or were
+// picked by the user through the native file dialog.
+
+use crate::error::AppError;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+pub struct PathPolicy {
+    roots: Vec<PathBuf>,
+    allowed_files: HashSet<PathBuf>,
+}
+
+impl PathPolicy {
+    /// `roots` are directories whose whole subtree is allowed; `allowed_files` are
+    /// individual files outside them. Entries that don't exist are ignored.
+    pub fn new<R, F>(roots: R, allowed_files: F) -> Self
+    where
+        R: IntoIterator<Item = PathBuf>,
+        F: IntoIterator<Item = PathBuf>,
+    {
+        PathPolicy {
+            roots: roots.into_iter().filter_map(|r| r.canonicalize().ok()).collect(),
+            allowed_files: allowed_files.into_iter().filter_map(|f| f.canonicalize().ok()).collect(),
+        }
+    }
+
+    /// Canonicalize `path` and check it against the policy.
+    pub fn check(&self, path: &str) -> Result<PathBuf, AppError> {
+        let canonical = Path::new(path)
+            .canonicalize()
+            .map_err(|_| AppError::not_found(path))?;
+
+        let allowed = self.allowed_files.contains(&canonical)
+            || self.roots.iter().any(|root| canonical.starts_with(root));
+        if allowed {
+            Ok(canonical)
+        } else {
+            log_warn!("Rejected file operation outside managed directories: {}", path);
+            Err(AppError::outside_managed_directories(path))
+        }
+    }
+}
+
+/// System folders no managed root may be inside of.
+const SYSTEM_TREES: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/proc", "/sbin", "/sys", "/usr", "/System",
+    "C:\\Windows", "C:\\Program Files", "C:\\Program Files (x86)",
+];
+
+/// Folders that hold far more than the app's files, so they can't be a root
+/// themselves, though a folder inside them can.
+const TOO_BROAD: &[&str] = &[
+    "/var", "/private", "/opt", "/tmp", "/home", "/Users", "/Library", "/Applications", "/Volumes", "/mnt", "/media",
+    "C:\\Users",
+];
+
+/// Check a folder the user wants the app to manage (`download_path`,
+/// `model_directory`). Everything under it becomes open to file operations, so it
+/// must be an existing, absolute folder and not a filesystem root, the home
+/// folder or a system folder. Returns the canonical folder.
+pub fn check_managed_root(path: &str) -> Result<PathBuf, AppError> {
+    let refuse = |why: &str| AppError::invalid_input(format!("{} can't hold the app's files: {}", path, why)).with_path(path);
+    if !Path::new(path).is_absolute() {
+        return Err(refuse("the folder must be an absolute path"));
+    }
+    let canonical = Path::new(path).canonicalize().map_err(|_| refuse("the folder doesn't exist"))?;
+    if !canonical.is_dir() {
+        return Err(refuse("it isn't a folder"));
+    }
+    let home = dirs::home_dir().and_then(|h| h.canonicalize().ok());
+    let too_broad = canonical.parent().is_none()
+        || home.as_deref() == Some(canonical.as_path())
+        || TOO_BROAD.iter().any(|dir| canonical == Path::new(dir));
+    if too_broad || SYSTEM_TREES.iter().any(|dir| canonical.starts_with(dir)) {
+        log_warn!("Refused {} as a managed folder", canonical.display());
+        return Err(refuse("it is a system folder or holds too much else"));
+    }
+    Ok(canonical)
+}
+
+/// Model filenames must be a single plain path component.
+pub fn validate_model_filename(name: &str) -> Result<(), AppError> {
+    let invalid = || AppError::invalid_model_filename(name);
+    if name.is_empty() || name.contains('/') || name.contains('\\') || name.contains("..") {
+        return Err(invalid());
+    }
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use std::fs;
+
+    /// `<tmp>/managed` as the only root, with a file inside and a sibling folder
+    /// `<tmp>/outside` holding a file the policy must not hand out.
+    fn setup() -> (tempfile::TempDir, PathPolicy) {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("managed").join("Downloads")).unwrap();
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        fs::write(dir.join("managed").join("Downloads").join("song.mp3"), b"audio").unwrap();
+        fs::write(dir.join("outside").join("secret.txt"), b"secret").unwrap();
+        fs::write(dir.join("outside").join("picked.wav"), b"audio").unwrap();
+        let policy = PathPolicy::new([dir.join("managed")], [dir.join("outside").join("picked.wav")]);
+        (tmp, policy)
+    }
+
+    fn code(result: Result<PathBuf, AppError>) -> ErrorCode {
+        result.unwrap_err().code
+    }
+
+    #[test]
+    fn files_under_a_root_are_allowed() {
+        let (tmp, policy) = setup();
+        let dir = tmp.path();
+        let song = dir.join("managed").join("Downloads").join("song.mp3");
+        assert_eq!(policy.check(&song.to_string_lossy()).unwrap(), song.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn dot_dot_cannot_climb_out_of_a_root() {
+        let (tmp, policy) = setup();
+        let dir = tmp.path();
+        let escape = dir.join("managed").join("Downloads").join("..").join("..").join("outside").join("secret.txt");
+        assert_eq!(code(policy.check(&escape.to_string_lossy())), ErrorCode::OutsideManagedDirectories);
+    }
+
+    #[test]
+    fn absolute_path_outside_every_root_is_rejected() {
+        let (tmp, policy) = setup();
+        let dir = tmp.path();
+        let secret = dir.join("outside").join("secret.txt");
+        assert_eq!(code(policy.check(&secret.to_string_lossy())), ErrorCode::OutsideManagedDirectories);
+        assert_eq!(code(policy.check(&dir.to_string_lossy())), ErrorCode::OutsideManagedDirectories);
+    }
+
+    #[test]
+    fn missing_path_is_not_found() {
+        let (tmp, policy) = setup();
+        let dir = tmp.path();
+        let missing = dir.join("managed").join("nope.mp3");
+        assert_eq!(code(policy.check(&missing.to_string_lossy())), ErrorCode::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_out_of_a_root_is_rejected() {
+        let (tmp, policy) = setup();
+        let dir = tmp.path();
+        let link = dir.join("managed").join("Downloads").join("link.txt");
+        std::os::unix::fs::symlink(dir.join("outside").join("secret.txt"), &link).unwrap();
+        assert_eq!(code(policy.check(&link.to_string_lossy())), ErrorCode::OutsideManagedDirectories);
+        let dir_link = dir.join("managed").join("escape");
+        std::os::unix::fs::symlink(dir.join("outside"), &dir_link).unwrap();
+        let through = dir_link.join("secret.txt");
+        assert_eq!(code(policy.check(&through.to_string_lossy())), ErrorCode::OutsideManagedDirectories);
+    }
+
+    #[test]
+    fn picked_file_outside_the_roots_is_allowed_but_not_its_neighbours() {
+        let (tmp, policy) = setup();
+        let dir = tmp.path();
+        let picked = dir.join("outside").join("picked.wav");
+        assert!(policy.check(&picked.to_string_lossy()).is_ok());
+        let via_dot_dot = dir.join("managed").join("..").join("outside").join("picked.wav");
+        assert!(policy.check(&via_dot_dot.to_string_lossy()).is_ok());
+        let neighbour = dir.join("outside").join("secret.txt");
+        assert!(policy.check(&neighbour.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn managed_root_must_be_an_existing_app_folder() {
+        let tmp = tempfile::tempdir().unwrap();
+        let music = tmp.path().join("music");
+        fs::create_dir_all(&music).unwrap();
+        assert_eq!(check_managed_root(&music.to_string_lossy()).unwrap(), music.canonicalize().unwrap());
+
+        let refused = |path: &str| check_managed_root(path).unwrap_err().code;
+        assert_eq!(refused("/"), ErrorCode::InvalidInput);
+        assert_eq!(refused("/etc"), ErrorCode::InvalidInput);
+        assert_eq!(refused("/usr/share"), ErrorCode::InvalidInput);
+        assert_eq!(refused("music"), ErrorCode::InvalidInput);
+        assert_eq!(refused(&tmp.path().join("missing").to_string_lossy()), ErrorCode::InvalidInput);
+        let file = tmp.path().join("song.mp3");
+        fs::write(&file, b"audio").unwrap();
+        assert_eq!(refused(&file.to_string_lossy()), ErrorCode::InvalidInput);
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(refused(&home.to_string_lossy()), ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn model_filenames_are_one_plain_component() {
+        for name in ["model_bs_roformer_ep_317_sdr_12.9755.ckpt", "htdemucs.yaml", "UVR-MDX-NET-Inst_HQ_3.onnx"] {
+            assert!(validate_model_filename(name).is_ok(), "{}", name);
+        }
+        for name in [
+            "",
+            ".",
+            "..",
+            "../model.ckpt",
+            "models/model.ckpt",
+            "/etc/passwd",
+            "..\\model.ckpt",
+            "C:\\models\\model.ckpt",
+            "model..ckpt",
+        ] {
+            let error = validate_model_filename(name).unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidModelFilename, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+// Audio property probing with a persistent cache.
+//
+// Successful ffprobe results are cached in `probe_cache.json` in the app data
+// dir, keyed by path and invalidated when the file's size or modification time
+// changes, so a long history only pays for probing once.
+
+use crate::process::{ProcessRunner, ProcessSpec};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioProperties {
+    pub duration_seconds: Option<f64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub bit_depth: Option<u32>,
+    pub codec: Option<String>,
+}
+
+impl AudioProperties {
+    fn is_empty(&self) -> bool {
+        self.duration_seconds.is_none()
+            && self.sample_rate.is_none()
+            && self.channels.is_none()
+            && self.bit_depth.is_none()
+            && self.codec.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    mtime: u64,
+    properties: AudioProperties,
+}
+
+pub struct ProbeCache {
+    cache_path: PathBuf,
+    entries: HashMap<String, CacheEntry>,
+    in_flight: HashSet<String>,
+    dirty: bool,
+}
+
+/// Size and modification time used to decide whether a cached probe is still valid.
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some((meta.len(), mtime))
+}
+
+impl ProbeCache {
+    pub fn open(cache_path: PathBuf) -> Self {
+        let mut entries: HashMap<String, CacheEntry> = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        // Failed probes used to be cached as empty properties; probe those again
+        entries.retain(|_, entry| !entry.properties.is_empty());
+        ProbeCache {
+            cache_path,
+            entries,
+            in_flight: HashSet::new(),
+            dirty: false,
+        }
+    }
+
+    /// Cached properties for `path`, if the file has not changed since it was probed.
+    pub fn get(&self, path: &Path) -> Option<AudioProperties> {
+        let entry = self.entries.get(path.to_string_lossy().as_ref())?;
+        let (size, mtime) = file_stamp(path)?;
+        if entry.size == size && entry.mtime == mtime {
+            Some(entry.properties.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, path: &Path, properties: AudioProperties) {
+        let key = path.to_string_lossy().to_string();
+        self.in_flight.remove(&key);
+        if let Some((size, mtime)) = file_stamp(path) {
+            self.entries.insert(key, CacheEntry { size, mtime, properties });
+            self.dirty = true;
+        }
+    }
+
+    /// Mark `path` as being probed. Returns false if a probe is already queued.
+    pub fn claim(&mut self, path: &Path) -> bool {
+        self.in_flight.insert(path.to_string_lossy().to_string())
+    }
+
+    pub fn release(&mut self, path: &Path) {
+        self.in_flight.remove(path.to_string_lossy().as_ref());
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        // Drop entries for files that no longer exist
+        self.entries.retain(|path, _| Path::new(path).exists());
+        if let Some(parent) = self.cache_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        }
+        let content = serde_json::to_string(&self.entries)
+            .map_err(|e| format!("Failed to serialize probe cache: {}", e))?;
+        fs::write(&self.cache_path, content).map_err(|e| format!("Failed to write probe cache: {}", e))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Pull the first audio stream's properties out of `ffprobe -show_format -show_streams` JSON.
+pub fn parse_ffprobe_json(json_value: &Value) -> AudioProperties {
+    let as_u32 = |v: &Value| {
+        v.as_u64()
+            .map(|n| n as u32)
+            .or_else(|| v.as_str().and_then(|s| s.parse::<u32>().ok()))
+    };
+
+    let stream = json_value["streams"]
+        .as_array()
+        .and_then(|streams| streams.iter().find(|s| s["codec_type"].as_str() == Some("audio")));
+
+    let duration_seconds = json_value["format"]["duration"]
+        .as_str()
+        .and_then(|d| d.parse::<f64>().ok())
+        .or_else(|| stream.and_then(|s| s["duration"].as_str()).and_then(|d| d.parse::<f64>().ok()));
+
+    let Some(stream) = stream else {
+        return AudioProperties {
+            duration_seconds,
+            ..Default::default()
+        };
+    };
+
+    // Lossless codecs report bits_per_raw_sample (flac) or bits_per_sample (pcm);
+    // lossy codecs report 0 for both
+    let bit_depth = as_u32(&stream["bits_per_raw_sample"])
+        .filter(|b| *b > 0)
+        .or_else(|| as_u32(&stream["bits_per_sample"]).filter(|b| *b > 0));
+
+    AudioProperties {
+        duration_seconds,
+        sample_rate: as_u32(&stream["sample_rate"]),
+        channels: as_u32(&stream["channels"]),
+        bit_depth,
+        codec: stream["codec_name"].as_str().map(|s| s.to_string()),
+    }
+}
+
+pub fn probe_file(runner: &dyn ProcessRunner, ffprobe_path: &Path, file_path: &Path) -> Result<AudioProperties, String> {
+    let spec = ProcessSpec::new(ffprobe_path)
+        .args(["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(file_path.to_string_lossy());
+    let output = runner.output(&spec).map_err(|e| format!("Failed to execute ffprobe: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("ffprobe failed: {}", output.stderr));
+    }
+
+    let json_value: Value = serde_json::from_str(&output.stdout)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    Ok(parse_ffprobe_json(&json_value))
+}
+
+/// `m:ss` or `h:mm:ss` for display in the history list.
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
@@ -0,0 +1,544 @@
+// Running the external tools: yt-dlp, ffmpeg, ffprobe and audio-separator, and
+// the platform helpers behind the clipboard, file manager and notifications.
+//
+// The core describes each call as a `ProcessSpec` and hands it to a
+// `ProcessRunner` instead of building a `std::process::Command` itself.
+// `SystemRunner` runs the real tools. `FakeRunner` replays recorded stdout,
+// stderr and exit codes, so progress parsing, model-list parsing and error
+// handling can be exercised without any of the tools installed. Recordings
+// used by the tests are in `fixtures/recordings/`, one folder per scenario.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A tool invocation: program, arguments, environment and working directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessSpec {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    /// Variables set on top of the environment the tool starts with
+    pub envs: Vec<(String, String)>,
+    /// Start from an empty environment instead of inheriting ours
+    pub env_clear: bool,
+    pub current_dir: Option<PathBuf>,
+    /// Written to the tool's stdin, which is then closed
+    #[serde(default)]
+    pub stdin: Option<String>,
+    /// Send stdout and stderr to the null device. For tools that leave a
+    /// background copy of themselves holding the streams open (`wl-copy`, `xclip`)
+    #[serde(default)]
+    pub discard_output: bool,
+    /// Start the tool in a process group of its own, so `kill` also stops the
+    /// processes it started (yt-dlp's ffmpeg). Off by default: a group of its own
+    /// doesn't get the terminal's Ctrl+C
+    #[serde(default)]
+    pub process_group: bool,
+}
+
+impl ProcessSpec {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        ProcessSpec {
+            program: program.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn env_clear(mut self) -> Self {
+        self.env_clear = true;
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    pub fn stdin(mut self, input: impl Into<String>) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    pub fn discard_output(mut self) -> Self {
+        self.discard_output = true;
+        self
+    }
+
+    pub fn process_group(mut self) -> Self {
+        self.process_group = true;
+        self
+    }
+
+    /// The program's file name without extension, e.g. `yt-dlp-standalone`.
+    pub fn program_name(&self) -> String {
+        self.program
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// How a tool exited. `code` is `None` when it was killed by a signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitStatus {
+    pub code: Option<i32>,
+}
+
+impl ExitStatus {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "exit code {}", code),
+            None => write!(f, "terminated by signal"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// What `RunningProcess::poll_line` found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineEvent {
+    Line(Stream, String),
+    /// Nothing arrived in time; the streams are still open
+    Idle,
+    /// Both streams are closed
+    Closed,
+}
+
+/// A started tool.
+pub trait RunningProcess: Send {
+    fn id(&self) -> Option<u32>;
+    /// The next line from either stream, without its line ending. `None` once
+    /// both streams are closed.
+    fn next_line(&mut self) -> Option<(Stream, String)>;
+    /// Like `next_line`, but gives up after `timeout`, so a caller waiting on a
+    /// quiet tool can still react to cancellation.
+    fn poll_line(&mut self, timeout: Duration) -> LineEvent;
+    fn wait(&mut self) -> Result<ExitStatus, String>;
+    /// Stop the tool, and what it started if it has a process group of its own.
+    fn kill(&mut self) -> Result<(), String>;
+}
+
+pub trait ProcessRunner: Send + Sync {
+    fn spawn(&self, spec: &ProcessSpec) -> Result<Box<dyn RunningProcess>, String>;
+
+    /// Run `spec` to completion and collect both streams.
+    fn output(&self, spec: &ProcessSpec) -> Result<ProcessOutput, String> {
+        let mut process = self.spawn(spec)?;
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        while let Some((stream, line)) = process.next_line() {
+            let buffer = match stream {
+                Stream::Stdout => &mut stdout,
+                Stream::Stderr => &mut stderr,
+            };
+            buffer.push_str(&line);
+            buffer.push('\n');
+        }
+        let status = process.wait()?;
+        Ok(ProcessOutput { status, stdout, stderr })
+    }
+}
+
+/// Runs the real tools.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemRunner;
+
+struct SystemProcess {
+    child: std::process::Child,
+    lines: mpsc::Receiver<(Stream, String)>,
+    /// The child leads a process group of its own
+    group_leader: bool,
+}
+
+fn forward_lines(stream: Stream, source: impl Read + Send + 'static, sender: mpsc::Sender<(Stream, String)>) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut buffer = Vec::new();
+        // Tools don't always write UTF-8 (Windows code pages), so decode lossily per line
+        while matches!(reader.read_until(b'\n', &mut buffer), Ok(n) if n > 0) {
+            let line = String::from_utf8_lossy(&buffer).trim_end_matches(['\r', '\n']).to_string();
+            if sender.send((stream, line)).is_err() {
+                break;
+            }
+            buffer.clear();
+        }
+    });
+}
+
+impl ProcessRunner for SystemRunner {
+    fn spawn(&self, spec: &ProcessSpec) -> Result<Box<dyn RunningProcess>, String> {
+        let mut cmd = Command::new(&spec.program);
+        cmd.args(&spec.args);
+        if spec.env_clear {
+            cmd.env_clear();
+        }
+        cmd.envs(spec.envs.iter().map(|(k, v)| (k, v)));
+        if let Some(dir) = &spec.current_dir {
+            cmd.current_dir(dir);
+        }
+        #[cfg(unix)]
+        if spec.process_group {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        let output = || if spec.discard_output { Stdio::null() } else { Stdio::piped() };
+        let mut child = cmd
+            .stdin(if spec.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(output())
+            .stderr(output())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        let (sender, lines) = mpsc::channel();
+        if let Some(stdout) = child.stdout.take() {
+            forward_lines(Stream::Stdout, stdout, sender.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_lines(Stream::Stderr, stderr, sender);
+        }
+
+        // Written from its own thread: a tool may fill its output pipes before it
+        // has read all of its input, and they are only drained once we return
+        if let (Some(input), Some(mut stdin)) = (spec.stdin.clone(), child.stdin.take()) {
+            let program = spec.program_name();
+            std::thread::spawn(move || {
+                // Dropping stdin afterwards closes it, which is the tool's cue to go ahead
+                if let Err(e) = stdin.write_all(input.as_bytes()) {
+                    log_warn!("Failed writing to {}'s stdin: {}", program, e);
+                }
+            });
+        }
+        Ok(Box::new(SystemProcess {
+            child,
+            lines,
+            group_leader: cfg!(unix) && spec.process_group,
+        }))
+    }
+}
+
+impl RunningProcess for SystemProcess {
+    fn id(&self) -> Option<u32> {
+        Some(self.child.id())
+    }
+
+    fn next_line(&mut self) -> Option<(Stream, String)> {
+        self.lines.recv().ok()
+    }
+
+    fn poll_line(&mut self, timeout: Duration) -> LineEvent {
+        match self.lines.recv_timeout(timeout) {
+            Ok((stream, line)) => LineEvent::Line(stream, line),
+            Err(mpsc::RecvTimeoutError::Timeout) => LineEvent::Idle,
+            Err(mpsc::RecvTimeoutError::Disconnected) => LineEvent::Closed,
+        }
+    }
+
+    fn wait(&mut self) -> Result<ExitStatus, String> {
+        let status = self.child.wait().map_err(|e| e.to_string())?;
+        Ok(ExitStatus { code: status.code() })
+    }
+
+    fn kill(&mut self) -> Result<(), String> {
+        // The bundled tools are .bat wrappers on Windows; killing cmd.exe alone
+        // would leave Python and its ffmpeg children running
+        #[cfg(target_os = "windows")]
+        {
+            let tree_killed = Command::new("taskkill")
+                .args(["/PID", &self.child.id().to_string(), "/T", "/F"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success());
+            if tree_killed {
+                return Ok(());
+            }
+        }
+        // The group id is the leader's pid; signalling it reaches every process
+        // the tool started that hasn't moved to a group of its own
+        #[cfg(unix)]
+        if self.group_leader {
+            let group = -(self.child.id() as libc::pid_t);
+            if unsafe { libc::kill(group, libc::SIGKILL) } == 0 {
+                return Ok(());
+            }
+        }
+        self.child.kill().map_err(|e| e.to_string())
+    }
+}
+
+/// A recorded tool run, replayed by `FakeRunner`. Stored as JSON fixtures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Recording {
+    /// Matched against the start of the program's name, so `yt-dlp` also
+    /// matches the bundled `yt-dlp-standalone` script
+    pub program: String,
+    /// Arguments that must all appear in the call
+    pub args_contain: Vec<String>,
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` replays a process killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+impl Default for Recording {
+    fn default() -> Self {
+        Recording {
+            program: String::new(),
+            args_contain: Vec::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: Some(0),
+        }
+    }
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read recording {}: {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse recording {}: {}", path.display(), e))
+    }
+
+    fn matches(&self, spec: &ProcessSpec) -> bool {
+        spec.program_name().starts_with(&self.program)
+            && self.args_contain.iter().all(|arg| spec.args.contains(arg))
+    }
+}
+
+/// Replays recordings instead of running tools. Each recording answers one call:
+/// the first unused one that matches. A call nothing matches fails to spawn, as
+/// if the tool were not installed.
+#[derive(Debug, Default)]
+pub struct FakeRunner {
+    recordings: Mutex<Vec<Recording>>,
+    calls: Mutex<Vec<ProcessSpec>>,
+}
+
+impl FakeRunner {
+    pub fn new(recordings: Vec<Recording>) -> Self {
+        FakeRunner {
+            recordings: Mutex::new(recordings),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Every `*.json` recording in `dir`, in file name order.
+    pub fn load_dir(dir: &Path) -> Result<Self, String> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read recordings in {}: {}", dir.display(), e))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        let recordings = paths.iter().map(|path| Recording::load(path)).collect::<Result<_, _>>()?;
+        Ok(FakeRunner::new(recordings))
+    }
+
+    pub fn push(&self, recording: Recording) {
+        if let Ok(mut recordings) = self.recordings.lock() {
+            recordings.push(recording);
+        }
+    }
+
+    /// The calls made so far, in order.
+    pub fn calls(&self) -> Vec<ProcessSpec> {
+        self.calls.lock().map(|calls| calls.clone()).unwrap_or_default()
+    }
+}
+
+struct FakeProcess {
+    lines: VecDeque<(Stream, String)>,
+    status: ExitStatus,
+}
+
+impl ProcessRunner for FakeRunner {
+    fn spawn(&self, spec: &ProcessSpec) -> Result<Box<dyn RunningProcess>, String> {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(spec.clone());
+        }
+        let mut recordings = self.recordings.lock().map_err(|_| "Recordings lock poisoned".to_string())?;
+        let index = recordings
+            .iter()
+            .position(|r| r.matches(spec))
+            .ok_or_else(|| format!("No recording for {} {}", spec.program_name(), spec.args.join(" ")))?;
+        let recording = recordings.remove(index);
+
+        // Recordings keep the streams apart, so stdout is replayed before stderr
+        let lines = recording
+            .stdout
+            .lines()
+            .map(|line| (Stream::Stdout, line.to_string()))
+            .chain(recording.stderr.lines().map(|line| (Stream::Stderr, line.to_string())))
+            .collect();
+        Ok(Box::new(FakeProcess {
+            lines,
+            status: ExitStatus { code: recording.exit_code },
+        }))
+    }
+}
+
+impl RunningProcess for FakeProcess {
+    fn id(&self) -> Option<u32> {
+        None
+    }
+
+    fn next_line(&mut self) -> Option<(Stream, String)> {
+        self.lines.pop_front()
+    }
+
+    fn poll_line(&mut self, _timeout: Duration) -> LineEvent {
+        match self.lines.pop_front() {
+            Some((stream, line)) => LineEvent::Line(stream, line),
+            None => LineEvent::Closed,
+        }
+    }
+
+    fn wait(&mut self) -> Result<ExitStatus, String> {
+        Ok(self.status)
+    }
+
+    fn kill(&mut self) -> Result<(), String> {
+        self.lines.clear();
+        self.status = ExitStatus { code: None };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recordings(scenario: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/recordings").join(scenario)
+    }
+
+    #[test]
+    fn each_recording_answers_one_call() {
+        let runner = FakeRunner::load_dir(&recordings("model-list")).unwrap();
+        let list = ProcessSpec::new("/opt/resources/audio-separator-standalone.sh")
+            .args(["--list_models", "--list_format", "json"]);
+        let output = runner.output(&list).unwrap();
+        assert!(output.status.success());
+        assert!(output.stdout.contains("htdemucs_6s.yaml"));
+
+        assert!(runner.output(&list).is_err(), "the recording is used up");
+        assert_eq!(runner.calls().len(), 2);
+    }
+
+    #[test]
+    fn stdout_is_replayed_before_stderr_with_the_recorded_exit_code() {
+        let runner = FakeRunner::load_dir(&recordings("ytdlp-failure")).unwrap();
+        let mut process = runner.spawn(&ProcessSpec::new("yt-dlp").arg("--extract-audio")).unwrap();
+        let mut streams = Vec::new();
+        while let LineEvent::Line(stream, _) = process.poll_line(Duration::ZERO) {
+            streams.push(stream);
+        }
+        assert_eq!(streams, [Stream::Stdout, Stream::Stdout, Stream::Stderr]);
+        assert_eq!(process.wait().unwrap().code, Some(1));
+    }
+
+    #[test]
+    fn call_without_a_matching_recording_fails_to_spawn() {
+        let runner = FakeRunner::load_dir(&recordings("ytdlp-progress")).unwrap();
+        assert!(runner.spawn(&ProcessSpec::new("ffmpeg").args(["--progress", "--newline"])).is_err());
+        // Every recorded argument has to be present
+        assert!(runner.spawn(&ProcessSpec::new("yt-dlp").arg("--progress")).is_err());
+        assert!(runner.spawn(&ProcessSpec::new("yt-dlp").args(["--newline", "--progress", "--print"])).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn system_runner_writes_stdin_and_closes_it() {
+        let output = SystemRunner.output(&ProcessSpec::new("cat").stdin("line one\nline two\n")).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, "line one\nline two\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn large_stdin_does_not_block_on_a_full_output_pipe() {
+        // Far more than a pipe buffer, echoed back before the input is all read
+        let input = "0123456789abcdef\n".repeat(64 * 1024);
+        let output = SystemRunner.output(&ProcessSpec::new("cat").stdin(input.clone())).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout.len(), input.len());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn killed_process_has_no_exit_code() {
+        let mut process = SystemRunner.spawn(&ProcessSpec::new("sleep").arg("30")).unwrap();
+        assert_eq!(process.poll_line(Duration::from_millis(50)), LineEvent::Idle);
+        process.kill().unwrap();
+        assert_eq!(process.wait().unwrap().code, None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn killing_a_process_group_stops_the_children_too() {
+        let spec = ProcessSpec::new("sh").args(["-c", "sleep 30 & echo $!; wait"]).process_group();
+        let mut process = SystemRunner.spawn(&spec).unwrap();
+        let child = match process.next_line() {
+            Some((Stream::Stdout, line)) => line,
+            other => panic!("expected the child's pid, got {:?}", other),
+        };
+        process.kill().unwrap();
+        assert_eq!(process.wait().unwrap().code, None);
+
+        // The orphaned sleep may linger as a zombie until init reaps it
+        let running = || {
+            fs::read_to_string(format!("/proc/{}/stat", child)).is_ok_and(|stat| !stat.contains(") Z "))
+        };
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while running() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!running(), "sleep {} outlived its process group", child);
+    }
+}
@@ -0,0 +1,373 @@
+// Storage usage reporting and cleanup policies.
+
+use crate::history::AudioFileInfoFrontend;
+use crate::library::{FileRole, Library};
+use crate::trash::path_size;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageBucket {
+    pub name: String,
+    pub bytes: u64,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageReport {
+    pub total_bytes: u64,
+    /// downloads, separated, trash, models
+    pub categories: Vec<UsageBucket>,
+    /// Separated output grouped by the model that produced it
+    pub by_model: Vec<UsageBucket>,
+    /// Downloads and separated output grouped by `YYYY-MM`
+    pub by_month: Vec<UsageBucket>,
+}
+
+fn count_files(path: &Path) -> usize {
+    if path.is_dir() {
+        std::fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|e| count_files(&e.path())).sum())
+            .unwrap_or(0)
+    } else if path.exists() {
+        1
+    } else {
+        0
+    }
+}
+
+fn add_to(buckets: &mut BTreeMap<String, UsageBucket>, name: &str, bytes: u64) {
+    let bucket = buckets.entry(name.to_string()).or_insert_with(|| UsageBucket {
+        name: name.to_string(),
+        ..Default::default()
+    });
+    bucket.bytes += bytes;
+    bucket.files += 1;
+}
+
+pub fn storage_report(download_base: &Path, model_dir: &Path, items: &[AudioFileInfoFrontend]) -> StorageReport {
+    let categories: Vec<UsageBucket> = [
+        ("downloads", crate::output::downloads_dir(download_base)),
+        ("separated", crate::output::separated_dir(download_base)),
+        ("trash", crate::trash::trash_dir(download_base)),
+        ("models", model_dir.to_path_buf()),
+    ]
+    .into_iter()
+    .map(|(name, dir)| UsageBucket {
+        name: name.to_string(),
+        bytes: path_size(&dir),
+        files: count_files(&dir),
+    })
+    .collect();
+
+    let mut by_model = BTreeMap::new();
+    let mut by_month = BTreeMap::new();
+    for item in items {
+        if item.directory_type == "separated" {
+            add_to(&mut by_model, item.model.as_deref().unwrap_or("unknown"), item.file_size);
+        }
+        let month = chrono::DateTime::from_timestamp(item.created_timestamp as i64, 0)
+            .map(|dt| dt.format("%Y-%m").to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        add_to(&mut by_month, &month, item.file_size);
+    }
+
+    // Separated output written outside the managed folder is counted under by_model
+    // and by_month but not in the category totals
+    StorageReport {
+        total_bytes: categories.iter().map(|c| c.bytes).sum(),
+        categories,
+        by_model: by_model.into_values().collect(),
+        by_month: by_month.into_values().rev().collect(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupPolicy {
+    /// Remove items older than this many days
+    pub keep_days: Option<u64>,
+    /// Remove the oldest items until downloads and stems fit in this many GB.
+    /// These are deleted permanently rather than trashed, since a trashed file
+    /// still takes up its space under the same folder.
+    pub max_total_gb: Option<f64>,
+    /// Never remove favorites
+    pub keep_favorites: bool,
+    /// Remove downloads once at least one of their separation outputs exists
+    pub delete_downloads_with_stems: bool,
+    /// Also apply the rules when the app starts, rather than only on request
+    pub automatic: bool,
+}
+
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        CleanupPolicy {
+            keep_days: None,
+            max_total_gb: None,
+            keep_favorites: true,
+            delete_downloads_with_stems: false,
+            automatic: false,
+        }
+    }
+}
+
+impl CleanupPolicy {
+    /// Whether any rule would remove something.
+    pub fn is_active(&self) -> bool {
+        self.keep_days.is_some() || self.max_total_gb.is_some() || self.delete_downloads_with_stems
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupReason {
+    StemsExist,
+    OlderThanKeepDays,
+    OverSizeLimit,
+}
+
+impl CleanupReason {
+    /// Whether candidates removed for this reason skip the trash.
+    pub fn is_permanent(self) -> bool {
+        self == CleanupReason::OverSizeLimit
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupCandidate {
+    pub id: String,
+    pub name: String,
+    pub file_path: String,
+    pub file_size: u64,
+    pub reason: CleanupReason,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanupReport {
+    pub dry_run: bool,
+    pub candidates: Vec<CleanupCandidate>,
+    /// Moved to the trash, and freed once it is emptied or the items expire
+    pub bytes_trashed: u64,
+    /// Deleted permanently to get under the size limit
+    pub bytes_deleted: u64,
+    /// Candidates that could not be moved to the trash, with the reason
+    pub failures: Vec<String>,
+}
+
+/// Decide which history items `policy` would remove. Nothing is touched on disk.
+pub fn plan_cleanup(
+    library: &Library,
+    items: &[AudioFileInfoFrontend],
+    policy: &CleanupPolicy,
+    now: u64,
+) -> Vec<CleanupCandidate> {
+    let mut chosen: Vec<CleanupCandidate> = Vec::new();
+    let mut chosen_ids: HashSet<String> = HashSet::new();
+    let protected = |item: &AudioFileInfoFrontend| policy.keep_favorites && item.annotations.favorite;
+    let mut choose = |item: &AudioFileInfoFrontend, reason: CleanupReason, chosen: &mut Vec<CleanupCandidate>| {
+        if chosen_ids.insert(item.id.clone()) {
+            chosen.push(CleanupCandidate {
+                id: item.id.clone(),
+                name: item.name.clone(),
+                file_path: item.file_path.clone(),
+                file_size: item.file_size,
+                reason,
+            });
+        }
+    };
+
+    if policy.delete_downloads_with_stems {
+        for item in items.iter().filter(|i| !protected(i)) {
+            let is_download = library.file(&item.id).is_some_and(|f| f.role == FileRole::Download);
+            let has_stems = library
+                .jobs_for_input(&item.id)
+                .iter()
+                .flat_map(|job| library.outputs_of_job(&job.id))
+                .any(|stem| Path::new(&stem.path).exists());
+            if is_download && has_stems {
+                choose(item, CleanupReason::StemsExist, &mut chosen);
+            }
+        }
+    }
+
+    if let Some(days) = policy.keep_days {
+        let cutoff = now.saturating_sub(days * 24 * 60 * 60);
+        for item in items.iter().filter(|i| !protected(i) && i.created_timestamp < cutoff) {
+            choose(item, CleanupReason::OlderThanKeepDays, &mut chosen);
+        }
+    }
+
+    if let Some(max_gb) = policy.max_total_gb {
+        let limit = (max_gb.max(0.0) * 1024.0 * 1024.0 * 1024.0) as u64;
+        let mut remaining: u64 = items.iter().map(|i| i.file_size).sum::<u64>()
+            - chosen.iter().map(|c| c.file_size).sum::<u64>();
+        let mut oldest_first: Vec<&AudioFileInfoFrontend> = items.iter().filter(|i| !protected(i)).collect();
+        oldest_first.sort_by_key(|i| i.created_timestamp);
+        for item in oldest_first {
+            if remaining <= limit {
+                break;
+            }
+            if !chosen.iter().any(|c| c.id == item.id) {
+                remaining = remaining.saturating_sub(item.file_size);
+                choose(item, CleanupReason::OverSizeLimit, &mut chosen);
+            }
+        }
+    }
+
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::{FileAnnotations, FileRecord, JobRecord, LibraryRecord};
+    use std::fs;
+
+    const DAY: u64 = 24 * 60 * 60;
+    const GB: u64 = 1024 * 1024 * 1024;
+    const NOW: u64 = 1_000 * DAY;
+
+    fn item(id: &str, age_days: u64, size: u64) -> AudioFileInfoFrontend {
+        AudioFileInfoFrontend::for_test(id, &format!("{}.mp3", id), NOW - age_days * DAY, size)
+    }
+
+    fn file(id: &str, role: FileRole, parent_id: Option<&str>, path: &Path) -> LibraryRecord {
+        LibraryRecord::File(FileRecord {
+            id: id.to_string(),
+            role,
+            parent_id: parent_id.map(String::from),
+            path: path.to_string_lossy().to_string(),
+            stem: None,
+            start_time: None,
+            end_time: None,
+            created_at: 0,
+            fingerprint: None,
+            fingerprinted: None,
+            annotations: FileAnnotations::default(),
+        })
+    }
+
+    fn job(id: &str, input_id: &str) -> LibraryRecord {
+        LibraryRecord::Job(JobRecord {
+            id: id.to_string(),
+            input_id: input_id.to_string(),
+            model: "htdemucs.yaml".to_string(),
+            output_dir: String::new(),
+            settings: serde_json::Value::Null,
+            selected_stems: Vec::new(),
+            created_at: 0,
+        })
+    }
+
+    fn plan(library: &Library, items: &[AudioFileInfoFrontend], policy: CleanupPolicy) -> Vec<(String, CleanupReason)> {
+        plan_cleanup(library, items, &policy, NOW)
+            .into_iter()
+            .map(|c| (c.id, c.reason))
+            .collect()
+    }
+
+    fn ids(candidates: &[(String, CleanupReason)]) -> Vec<&str> {
+        candidates.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    #[test]
+    fn default_policy_removes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let items = [item("old", 400, GB), item("new", 1, GB)];
+        assert!(!CleanupPolicy::default().is_active());
+        // Policies saved before the opt-in existed don't start running on their own
+        let saved: CleanupPolicy = serde_json::from_str(r#"{"keep_days": 30}"#).unwrap();
+        assert!(saved.is_active() && !saved.automatic);
+        assert!(plan(&lib, &items, CleanupPolicy::default()).is_empty());
+    }
+
+    #[test]
+    fn keep_days_removes_older_items() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let items = [item("a", 40, 1), item("b", 29, 1), item("c", 31, 1)];
+        let policy = CleanupPolicy { keep_days: Some(30), ..Default::default() };
+        let planned = plan(&lib, &items, policy);
+        assert_eq!(ids(&planned), ["a", "c"]);
+        assert!(planned.iter().all(|(_, reason)| *reason == CleanupReason::OlderThanKeepDays));
+    }
+
+    #[test]
+    fn size_limit_takes_the_oldest_first_until_under_it() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let items = [item("new", 1, GB), item("oldest", 9, GB), item("middle", 5, GB), item("older", 7, GB)];
+        let policy = CleanupPolicy { max_total_gb: Some(2.0), ..Default::default() };
+        let planned = plan(&lib, &items, policy);
+        assert_eq!(ids(&planned), ["oldest", "older"]);
+        assert!(planned.iter().all(|(_, reason)| *reason == CleanupReason::OverSizeLimit));
+        assert!(CleanupReason::OverSizeLimit.is_permanent());
+        assert!(!CleanupReason::OlderThanKeepDays.is_permanent());
+
+        // Items removed by an earlier rule already count towards the limit
+        let policy = CleanupPolicy { keep_days: Some(8), max_total_gb: Some(2.0), ..Default::default() };
+        assert_eq!(
+            plan(&lib, &items, policy),
+            [
+                ("oldest".to_string(), CleanupReason::OlderThanKeepDays),
+                ("older".to_string(), CleanupReason::OverSizeLimit),
+            ]
+        );
+
+        let policy = CleanupPolicy { max_total_gb: Some(4.0), ..Default::default() };
+        assert!(plan(&lib, &items, policy).is_empty());
+    }
+
+    #[test]
+    fn downloads_with_existing_stems_are_removed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let stem_path = dir.join("song_(Vocals)_htdemucs.wav");
+        fs::write(&stem_path, b"stem").unwrap();
+        lib.put(file("separated", FileRole::Download, None, &dir.join("separated.mp3"))).unwrap();
+        lib.put(job("j1", "separated")).unwrap();
+        lib.put(file("stem", FileRole::Stem, Some("j1"), &stem_path)).unwrap();
+        // Separated, but the stems have been deleted since
+        lib.put(file("stems_gone", FileRole::Download, None, &dir.join("gone.mp3"))).unwrap();
+        lib.put(job("j2", "stems_gone")).unwrap();
+        lib.put(file("deleted_stem", FileRole::Stem, Some("j2"), &dir.join("missing.wav"))).unwrap();
+        lib.put(file("unseparated", FileRole::Download, None, &dir.join("new.mp3"))).unwrap();
+        // A local file that was separated isn't a download
+        lib.put(file("local", FileRole::Local, None, &dir.join("local.wav"))).unwrap();
+        lib.put(job("j3", "local")).unwrap();
+        lib.put(file("local_stem", FileRole::Stem, Some("j3"), &stem_path)).unwrap();
+
+        let items = [
+            item("separated", 1, 1),
+            item("stems_gone", 1, 1),
+            item("unseparated", 1, 1),
+            item("local", 1, 1),
+        ];
+        let policy = CleanupPolicy { delete_downloads_with_stems: true, ..Default::default() };
+        assert_eq!(plan(&lib, &items, policy), [("separated".to_string(), CleanupReason::StemsExist)]);
+    }
+
+    #[test]
+    fn favorites_are_kept_unless_the_policy_says_otherwise() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let lib = Library::open(dir.join("library.jsonl")).unwrap();
+        let mut favorite = item("favorite", 90, GB);
+        favorite.annotations.favorite = true;
+        let items = [favorite, item("plain", 60, GB), item("new", 1, GB)];
+
+        let keep = CleanupPolicy { keep_days: Some(30), max_total_gb: Some(0.5), ..Default::default() };
+        assert!(keep.keep_favorites);
+        // The limit can't be met without the favorite, which stays anyway
+        assert_eq!(ids(&plan(&lib, &items, keep.clone())), ["plain", "new"]);
+
+        let remove = CleanupPolicy { keep_favorites: false, ..keep };
+        assert_eq!(ids(&plan(&lib, &items, remove)), ["favorite", "plain", "new"]);
+    }
+}
@@ -0,0 +1,370 @@
+// App-managed trash.
+//
+// Deleting a file or model moves it into `<download base>/.trash/<entry id>/`
+// and records where it came from in `.trash/index.json`, so it can be restored
+// until the retention period runs out or the trash is emptied.
+
+use crate::library::{new_id, now_secs, FileRecord};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Serializes trash operations; the index is rewritten as a whole on every change.
+static TRASH_LOCK: Mutex<()> = Mutex::new(());
+
+pub const DEFAULT_RETENTION_DAYS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashKind {
+    File,
+    Model,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    pub name: String,
+    pub original_path: String,
+    pub trashed_path: String,
+    pub deleted_at: u64,
+    pub size: u64,
+    /// Library record removed along with the file, put back on restore
+    pub library_record: Option<FileRecord>,
+    /// Records of the files inside a trashed folder
+    #[serde(default)]
+    pub contained_records: Vec<FileRecord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmptyTrashReport {
+    pub removed: usize,
+    pub bytes_freed: u64,
+}
+
+pub fn trash_dir(download_base: &Path) -> PathBuf {
+    download_base.join(".trash")
+}
+
+fn index_path(download_base: &Path) -> PathBuf {
+    trash_dir(download_base).join("index.json")
+}
+
+/// The trash index; empty if there is none yet. An index that doesn't parse is
+/// moved aside rather than left to be overwritten, so the restore data in it
+/// can still be recovered by hand.
+fn read_index(download_base: &Path) -> Result<Vec<TrashEntry>, String> {
+    let path = index_path(download_base);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read trash index: {}", e)),
+    };
+    serde_json::from_str(&content).map_err(|e| {
+        let aside = trash_dir(download_base).join(format!("index.corrupt-{}.json", now_secs()));
+        match fs::rename(&path, &aside) {
+            Ok(()) => {
+                log_error!("Trash index is corrupt ({}); moved it to {}", e, aside.display());
+                format!("The trash index was corrupt and has been moved to {}", aside.display())
+            }
+            Err(rename_error) => format!("The trash index is corrupt ({}) and could not be moved aside: {}", e, rename_error),
+        }
+    })
+}
+
+fn write_index(download_base: &Path, entries: &[TrashEntry]) -> Result<(), String> {
+    fs::create_dir_all(trash_dir(download_base))
+        .map_err(|e| format!("Failed to create trash directory: {}", e))?;
+    let content = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize trash index: {}", e))?;
+    fs::write(index_path(download_base), content).map_err(|e| format!("Failed to write trash index: {}", e))
+}
+
+/// Total size of a file, or of everything under a directory.
+pub fn path_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|e| path_size(&e.path())).sum())
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+pub fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Rename, falling back to copy + delete when source and target are on different volumes.
+pub fn move_path(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursive(from, to).map_err(|e| format!("Failed to move {}: {}", from.display(), e))?;
+    remove_path(from).map_err(|e| format!("Failed to remove {}: {}", from.display(), e))
+}
+
+/// Move `path` (a file or a whole folder) into the trash. Returns the new trash entry,
+/// whose id is fresh for every trashing so entries never share a folder.
+pub fn move_to_trash(
+    download_base: &Path,
+    path: &Path,
+    kind: TrashKind,
+    library_record: Option<FileRecord>,
+    contained_records: Vec<FileRecord>,
+) -> Result<TrashEntry, String> {
+    let _guard = TRASH_LOCK.lock().map_err(|_| "Trash lock poisoned".to_string())?;
+    // Read before moving anything, so a bad index can't strand the file
+    let mut entries = read_index(download_base)?;
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| "Invalid path".to_string())?;
+    let id = new_id();
+    let entry_dir = trash_dir(download_base).join(&id);
+    fs::create_dir_all(&entry_dir).map_err(|e| format!("Failed to create trash directory: {}", e))?;
+    let trashed_path = entry_dir.join(&name);
+    let size = path_size(path);
+
+    if let Err(e) = move_path(path, &trashed_path) {
+        let _ = fs::remove_dir_all(&entry_dir);
+        return Err(e);
+    }
+
+    let entry = TrashEntry {
+        id,
+        kind,
+        name,
+        original_path: path.to_string_lossy().to_string(),
+        trashed_path: trashed_path.to_string_lossy().to_string(),
+        deleted_at: now_secs(),
+        size,
+        library_record,
+        contained_records,
+    };
+    entries.push(entry.clone());
+    if let Err(e) = write_index(download_base, &entries) {
+        // Without an entry the file could never be restored, so put it back
+        return match move_path(&trashed_path, path) {
+            Ok(()) => {
+                let _ = fs::remove_dir_all(&entry_dir);
+                Err(e)
+            }
+            Err(back) => Err(format!("{}; the file was left at {}: {}", e, trashed_path.display(), back)),
+        };
+    }
+    log_info!("Moved to trash: {} ({} bytes)", entry.original_path, entry.size);
+    Ok(entry)
+}
+
+/// Move a trashed item back to where it was deleted from.
+pub fn restore(download_base: &Path, id: &str) -> Result<TrashEntry, String> {
+    let _guard = TRASH_LOCK.lock().map_err(|_| "Trash lock poisoned".to_string())?;
+
+    let mut entries = read_index(download_base)?;
+    let position = entries
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| "Item not found in trash".to_string())?;
+    let entry = entries[position].clone();
+
+    let original = PathBuf::from(&entry.original_path);
+    if original.exists() {
+        return Err(format!("Cannot restore: {} already exists", entry.original_path));
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to recreate folder: {}", e))?;
+    }
+    move_path(Path::new(&entry.trashed_path), &original)?;
+
+    entries.remove(position);
+    if let Err(e) = write_index(download_base, &entries) {
+        // The entry is still listed, so it must still hold the file
+        return match move_path(&original, Path::new(&entry.trashed_path)) {
+            Ok(()) => Err(e),
+            Err(back) => Err(format!("{}; the file was restored anyway: {}", e, back)),
+        };
+    }
+    let _ = fs::remove_dir_all(trash_dir(download_base).join(&entry.id));
+    log_info!("Restored from trash: {}", entry.original_path);
+    Ok(entry)
+}
+
+pub fn list(download_base: &Path) -> Result<Vec<TrashEntry>, String> {
+    let _guard = TRASH_LOCK.lock();
+    let mut entries = read_index(download_base)?;
+    entries.sort_by_key(|e| Reverse(e.deleted_at));
+    Ok(entries)
+}
+
+/// Ids of the library records held by trash entries. A record in here is waiting
+/// to be restored with its file and must not be handed to another one.
+pub fn record_ids(download_base: &Path) -> HashSet<String> {
+    let _guard = TRASH_LOCK.lock();
+    match read_index(download_base) {
+        Ok(entries) => entries
+            .iter()
+            .flat_map(|e| e.library_record.iter().chain(&e.contained_records))
+            .map(|r| r.id.clone())
+            .collect(),
+        Err(e) => {
+            log_warn!("Failed to read trash index: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+/// Permanently delete trash entries matching `predicate`.
+fn purge_where<F>(download_base: &Path, predicate: F) -> Result<EmptyTrashReport, String>
+where
+    F: Fn(&TrashEntry) -> bool,
+{
+    let _guard = TRASH_LOCK.lock().map_err(|_| "Trash lock poisoned".to_string())?;
+
+    let mut report = EmptyTrashReport::default();
+    let mut kept = Vec::new();
+    for entry in read_index(download_base)? {
+        if !predicate(&entry) {
+            kept.push(entry);
+            continue;
+        }
+        let entry_dir = trash_dir(download_base).join(&entry.id);
+        let size = path_size(&entry_dir);
+        match fs::remove_dir_all(&entry_dir) {
+            Ok(()) => {
+                report.removed += 1;
+                report.bytes_freed += size;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => report.removed += 1,
+            Err(e) => {
+                log_warn!("Failed to purge {}: {}", entry.trashed_path, e);
+                kept.push(entry);
+            }
+        }
+    }
+    write_index(download_base, &kept)?;
+    Ok(report)
+}
+
+pub fn empty(download_base: &Path) -> Result<EmptyTrashReport, String> {
+    purge_where(download_base, |_| true)
+}
+
+/// Remove entries older than `retention_days`. A retention of 0 keeps everything.
+pub fn purge_expired(download_base: &Path, retention_days: u64) -> Result<EmptyTrashReport, String> {
+    if retention_days == 0 || !trash_dir(download_base).exists() {
+        return Ok(EmptyTrashReport::default());
+    }
+    let cutoff = now_secs().saturating_sub(retention_days * 24 * 60 * 60);
+    let report = purge_where(download_base, |e| e.deleted_at < cutoff)?;
+    if report.removed > 0 {
+        log_info!(
+            "Purged {} expired trash items ({} bytes)",
+            report.removed, report.bytes_freed
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_and_restore_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let file = base.join("song.mp3");
+        fs::write(&file, b"audio").unwrap();
+
+        let entry = move_to_trash(base, &file, TrashKind::File, None, Vec::new()).unwrap();
+        assert!(!file.exists());
+        assert_eq!(list(base).unwrap().len(), 1);
+
+        restore(base, &entry.id).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"audio");
+        assert!(list(base).unwrap().is_empty());
+    }
+
+    #[test]
+    fn same_file_trashed_twice_gets_two_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let file = base.join("song.mp3");
+        fs::write(&file, b"first").unwrap();
+        let first = move_to_trash(base, &file, TrashKind::File, None, Vec::new()).unwrap();
+        fs::write(&file, b"second").unwrap();
+        let second = move_to_trash(base, &file, TrashKind::File, None, Vec::new()).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(list(base).unwrap().len(), 2);
+
+        restore(base, &second.id).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"second");
+        fs::rename(&file, base.join("second.mp3")).unwrap();
+        restore(base, &first.id).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"first");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_goes_back_when_the_index_cannot_be_written() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        fs::create_dir_all(trash_dir(base)).unwrap();
+        // Reads as a missing index, but writing through it fails
+        std::os::unix::fs::symlink(base.join("missing").join("index.json"), index_path(base)).unwrap();
+        let file = base.join("song.mp3");
+        fs::write(&file, b"audio").unwrap();
+
+        assert!(move_to_trash(base, &file, TrashKind::File, None, Vec::new()).is_err());
+        assert_eq!(fs::read(&file).unwrap(), b"audio");
+        let left: Vec<_> = fs::read_dir(trash_dir(base)).unwrap().flatten().map(|e| e.file_name()).collect();
+        assert_eq!(left, ["index.json"], "no entry folder may be left behind");
+    }
+
+    #[test]
+    fn corrupt_index_is_moved_aside_not_overwritten() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        fs::create_dir_all(trash_dir(base)).unwrap();
+        fs::write(index_path(base), b"[{\"id\": ").unwrap();
+        let file = base.join("song.mp3");
+        fs::write(&file, b"audio").unwrap();
+
+        assert!(move_to_trash(base, &file, TrashKind::File, None, Vec::new()).is_err());
+        assert!(file.exists(), "file must stay put when the index can't be read");
+        let aside: Vec<_> = fs::read_dir(trash_dir(base))
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with("index.corrupt-"))
+            .collect();
+        assert_eq!(aside.len(), 1);
+        assert_eq!(fs::read(aside[0].path()).unwrap(), b"[{\"id\": ");
+
+        // The next operation starts a fresh index
+        move_to_trash(base, &file, TrashKind::File, None, Vec::new()).unwrap();
+        assert_eq!(list(base).unwrap().len(), 1);
+    }
+}
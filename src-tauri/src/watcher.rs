@@ -0,0 +1,211 @@
+// Filesystem watcher.
+//
+// The platform's change notifications (inotify, FSEvents, ReadDirectoryChangesW)
+// say when something under a folder changed. Only then are the folders
+// snapshotted (path -> size + mtime), on an interval, and a change is reported
+// once two consecutive polls agree, which debounces files that are still being
+// written by yt-dlp, audio-separator or an external program. Folders that can't
+// be watched fall back to polling.
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    pub mtime: u64,
+}
+
+impl FileStamp {
+    pub fn of(meta: &fs::Metadata) -> Self {
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        FileStamp { size: meta.len(), mtime }
+    }
+}
+
+pub type Snapshot = HashMap<PathBuf, FileStamp>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+fn collect(dir: &Path, depth: usize, max_depth: usize, out: &mut Snapshot) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_dir() {
+            if depth + 1 < max_depth {
+                collect(&path, depth + 1, max_depth, out);
+            }
+        } else if meta.is_file() {
+            out.insert(path, FileStamp::of(&meta));
+        }
+    }
+}
+
+/// Files under each of `dirs`, descending at most `max_depth` levels (1 = only
+/// the files directly inside).
+pub fn snapshot(dirs: &[PathBuf], max_depth: usize) -> Snapshot {
+    let mut out = Snapshot::new();
+    for dir in dirs {
+        collect(dir, 0, max_depth.max(1), &mut out);
+    }
+    out
+}
+
+pub fn diff(old: &Snapshot, new: &Snapshot) -> SnapshotDiff {
+    let mut result = SnapshotDiff::default();
+    for (path, stamp) in new {
+        match old.get(path) {
+            None => result.added.push(path.clone()),
+            Some(previous) if previous != stamp => result.modified.push(path.clone()),
+            _ => {}
+        }
+    }
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            result.removed.push(path.clone());
+        }
+    }
+    result.added.sort();
+    result.removed.sort();
+    result.modified.sort();
+    result
+}
+
+/// Debounced change detection over repeated snapshots.
+#[derive(Default)]
+pub struct DebouncedWatch {
+    /// Last state that was reported
+    stable: Option<Snapshot>,
+    /// Previous poll, used to tell whether things have settled
+    pending: Option<Snapshot>,
+}
+
+impl DebouncedWatch {
+    pub fn new() -> Self {
+        DebouncedWatch { stable: None, pending: None }
+    }
+
+    /// Feed a fresh snapshot. Returns the changes since the last report once the
+    /// folder has stopped changing; the very first poll only sets the baseline.
+    pub fn poll(&mut self, current: Snapshot) -> Option<SnapshotDiff> {
+        let Some(stable) = &self.stable else {
+            self.stable = Some(current);
+            return None;
+        };
+        if diff(stable, &current).is_empty() {
+            self.pending = None;
+            return None;
+        }
+        let settled = self.pending.as_ref().is_some_and(|p| diff(p, &current).is_empty());
+        if !settled {
+            self.pending = Some(current);
+            return None;
+        }
+        let changes = diff(stable, &current);
+        self.stable = Some(current);
+        self.pending = None;
+        Some(changes)
+    }
+
+    /// Whether the last poll left nothing waiting to settle, so polling can stop
+    /// until the next change notification.
+    pub fn is_settled(&self) -> bool {
+        self.pending.is_none()
+    }
+
+    /// Forget the baseline, e.g. after the watched folders changed.
+    pub fn reset(&mut self) {
+        self.stable = None;
+        self.pending = None;
+    }
+}
+
+/// Change notifications for a set of folders.
+pub struct EventWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    /// Folder, and whether its subfolders are watched too
+    roots: Vec<(PathBuf, bool)>,
+    /// Roots that couldn't be watched, e.g. because they don't exist yet
+    unwatched: Vec<PathBuf>,
+}
+
+impl EventWatcher {
+    pub fn new() -> Result<Self, String> {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender).map_err(|e| format!("Failed to start the file watcher: {}", e))?;
+        Ok(EventWatcher { watcher, events, roots: Vec::new(), unwatched: Vec::new() })
+    }
+
+    /// Watch exactly `roots`. Called every round: a new set replaces the old one,
+    /// and roots that couldn't be watched before are tried again.
+    pub fn watch(&mut self, roots: &[(PathBuf, bool)]) {
+        let retry: Vec<(PathBuf, bool)> = if roots == self.roots.as_slice() {
+            self.roots.iter().filter(|(dir, _)| self.unwatched.contains(dir)).cloned().collect()
+        } else {
+            for (dir, _) in &self.roots {
+                let _ = self.watcher.unwatch(dir);
+            }
+            self.roots = roots.to_vec();
+            self.unwatched.clear();
+            roots.to_vec()
+        };
+
+        for (dir, recursive) in retry {
+            let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+            match self.watcher.watch(&dir, mode) {
+                Ok(()) => self.unwatched.retain(|d| d != &dir),
+                Err(e) => {
+                    if !self.unwatched.contains(&dir) {
+                        log_info!("Polling {} instead of watching it: {}", dir.display(), e);
+                        self.unwatched.push(dir);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `dir` is a root that is being watched, so there's no need to poll it.
+    pub fn is_watched(&self, dir: &Path) -> bool {
+        self.roots.iter().any(|(root, _)| root == dir) && !self.unwatched.iter().any(|d| d == dir)
+    }
+
+    /// Wait up to `timeout` for changes and return the paths they touched, empty
+    /// if there were none. When events were lost, all roots count as changed.
+    pub fn wait(&self, timeout: Duration) -> Vec<PathBuf> {
+        let first = match self.events.recv_timeout(timeout) {
+            Ok(event) => event,
+            Err(_) => return Vec::new(),
+        };
+        let mut changed = Vec::new();
+        for event in std::iter::once(first).chain(self.events.try_iter()) {
+            match event {
+                Ok(event) if !event.need_rescan() => changed.extend(event.paths),
+                _ => changed.extend(self.roots.iter().map(|(dir, _)| dir.clone())),
+            }
+        }
+        changed
+    }
+}
@@ -18,7 +18,9 @@ import { Badge } from "./components/ui/badge";
 import {
   AlertTriangle as AlertTriangleIcon,
   ChevronDown,
+  ChevronRight,
   Clipboard,
+  FileDown,
   FolderOpen,
   Info,
   Loader2,
@@ -31,6 +33,7 @@ import {
 import { invoke, convertFileSrc } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { SettingsPage } from "./Settings";
+import { errorCode, errorMessage } from "./lib/utils";
 
 interface AudioFileInfo {
   id: string;
@@ -41,6 +44,73 @@ interface AudioFileInfo {
   created_display: string;
   duration?: string;
   file_size: number;
+  parent_id?: string;
+  source_url?: string;
+  source_title?: string;
+  stem?: string;
+  model?: string;
+  tags?: string[];
+  favorite?: boolean;
+  note?: string;
+  duration_seconds?: number;
+  sample_rate?: number;
+  channels?: number;
+  bit_depth?: number;
+  codec?: string;
+  set_path?: string;
+}
+
+// A subfolder of Downloads/Separated shown as one collapsible group
+interface HistorySetGroup {
+  path: string;
+  name: string;
+  files: AudioFileInfo[];
+}
+
+type HistoryEntry =
+  | { kind: "file"; file: AudioFileInfo }
+  | { kind: "set"; set: HistorySetGroup };
+
+// Group files by folder, keeping each set where its newest file would appear
+const groupHistory = (files: AudioFileInfo[]): HistoryEntry[] => {
+  const entries: HistoryEntry[] = [];
+  const sets = new Map<string, HistorySetGroup>();
+  for (const file of files) {
+    if (!file.set_path) {
+      entries.push({ kind: "file", file });
+      continue;
+    }
+    let set = sets.get(file.set_path);
+    if (!set) {
+      const name = file.set_path.split(/[\\/]/).pop() || file.set_path;
+      set = { path: file.set_path, name, files: [] };
+      sets.set(file.set_path, set);
+      entries.push({ kind: "set", set });
+    }
+    set.files.push(file);
+  }
+  return entries;
+};
+
+interface HistoryChangedEvent {
+  added: AudioFileInfo[];
+  modified: AudioFileInfo[];
+  removed: string[];
+}
+
+interface CleanupReport {
+  candidates: { name: string }[];
+  failures: string[];
+}
+
+interface HistoryProbeUpdate {
+  id: string;
+  duration?: string;
+  duration_seconds?: number;
+  sample_rate?: number;
+  channels?: number;
+  bit_depth?: number;
+  codec?: string;
 }
 
 interface VideoInfo {
@@ -57,6 +127,7 @@ interface DownloadResult {
   success: boolean;
   message: string;
   file_path?: string;
+  job_id?: string | null;
 }
 
 interface DownloadProgress {
@@ -69,6 +140,7 @@ interface SeparationResult {
   success: boolean;
   message: string;
   output_files: string[];
+  job_id?: string | null;
 }
 
 // GPUInfo interface removed because GPU auto-detection is currently unused in UI
@@ -117,6 +189,9 @@ interface SeparationSettings {
   mdxc_overlap: number;
   mdxc_batch_size: number;
   mdxc_pitch_shift: number;
+  output_location?: "managed" | "next_to_source";
+  output_name_template?: string;
+  retry_on_out_of_memory?: boolean;
 }
 
 interface Settings {
@@ -124,40 +199,37 @@ interface Settings {
   download_path: string;
   audio_format: string;
   audio_quality: string;
-  video_format: string;
-  video_quality: string;
-  extract_audio: boolean;
   write_subtitles: boolean;
   write_thumbnail: boolean;
   write_description: boolean;
   write_info: boolean;
-  write_annotations: boolean;
   write_comments: boolean;
   write_automatic_subtitles: boolean;
   write_manual_subtitles: boolean;
-  max_downloads: number;
   retries: number;
   fragment_retries: number;
   file_access_retries: number;
   concurrent_fragments: number;
-  max_downloads_per_host: number;
-  max_downloads_per_playlist: number;
-  max_downloads_per_channel: number;
-  max_downloads_per_user: number;
-  max_downloads_per_extractor: number;
-  max_downloads_per_video: number;
-  max_downloads_per_audio: number;
-  max_downloads_per_subtitle: number;
-  max_downloads_per_thumbnail: number;
-  max_downloads_per_description: number;
-  max_downloads_per_info: number;
-  max_downloads_per_annotations: number;
-  max_downloads_per_comments: number;
-  max_downloads_per_automatic_subtitles: number;
-  max_downloads_per_manual_subtitles: number;
   separation_settings: SeparationSettings;
   model_directory: string;
   enable_stem_extraction: boolean;
+  trash_retention_days: number;
+  history_scan_depth: number;
+  history_extensions: string[];
+  cleanup_policy: {
+    keep_days: number | null;
+    max_total_gb: number | null;
+    keep_favorites: boolean;
+    delete_downloads_with_stems: boolean;
+    automatic: boolean;
+  };
+  notifications: {
+    enabled: boolean;
+    only_when_unfocused: boolean;
+    downloads: boolean;
+    separations: boolean;
+    failures: boolean;
+  };
 }
 
 type InputType = "YouTube" | "Spotify" | "LocalFile" | "Unknown";
@@ -205,6 +277,11 @@ function App() {
   const [isLoadingVideo, setIsLoadingVideo] = useState(false);
   const [mediaDuration, setMediaDuration] = useState<number | null>(null);
   const [showSettings, setShowSettings] = useState(false);
+  const [expandedSets, setExpandedSets] = useState<Set<string>>(new Set());
+  const [selectedHistoryPaths, setSelectedHistoryPaths] = useState<Set<string>>(
+    new Set()
+  );
+  const [lastOutputFiles, setLastOutputFiles] = useState<string[]>([]);
   const [deletingFileIds, setDeletingFileIds] = useState<Set<string>>(
     new Set()
   );
@@ -219,37 +296,17 @@ function App() {
     download_path: "Documents/2",
     audio_format: "mp3",
     audio_quality: "0",
-    video_format: "mp4",
-    video_quality: "best",
-    extract_audio: true,
     write_subtitles: false,
     write_thumbnail: false,
     write_description: false,
     write_info: false,
-    write_annotations: false,
     write_comments: false,
     write_automatic_subtitles: false,
     write_manual_subtitles: false,
-    max_downloads: 1,
     retries: 10,
     fragment_retries: 10,
     file_access_retries: 3,
     concurrent_fragments: 1,
-    max_downloads_per_host: 0,
-    max_downloads_per_playlist: 0,
-    max_downloads_per_channel: 0,
-    max_downloads_per_user: 0,
-    max_downloads_per_extractor: 0,
-    max_downloads_per_video: 0,
-    max_downloads_per_audio: 0,
-    max_downloads_per_subtitle: 0,
-    max_downloads_per_thumbnail: 0,
-    max_downloads_per_description: 0,
-    max_downloads_per_info: 0,
-    max_downloads_per_annotations: 0,
-    max_downloads_per_comments: 0,
-    max_downloads_per_automatic_subtitles: 0,
-    max_downloads_per_manual_subtitles: 0,
     separation_settings: {
       model_filename: "model_bs_roformer_ep_317_sdr_12.9755.ckpt",
       output_format: "WAV",
@@ -281,9 +338,29 @@ function App() {
       mdxc_overlap: 8,
       mdxc_batch_size: 1,
       mdxc_pitch_shift: 0,
+      output_location: "managed",
+      output_name_template: "",
+      retry_on_out_of_memory: true,
     },
     model_directory: "Documents//Models",
     enable_stem_extraction: false,
+    trash_retention_days: 30,
+    history_scan_depth: 2,
+    history_extensions: ["mp3", "wav", "flac", "m4a", "aac", "ogg", "opus", "aiff", "aif", "wma"],
+    cleanup_policy: {
+      keep_days: null,
+      max_total_gb: null,
+      keep_favorites: true,
+      delete_downloads_with_stems: false,
+      automatic: false,
+    },
+    notifications: {
+      enabled: true,
+      only_when_unfocused: false,
+      downloads: true,
+      separations: true,
+      failures: true,
+    },
   });
 
   const [separationSettings] = useState<SeparationSettings>({
@@ -465,6 +542,32 @@ function App() {
     };
   }, []);
 
+  // Fill in durations and audio properties as the backend probes history items
+  useEffect(() => {
+    let unlistenFn: (() => void) | null = null;
+
+    const setupListener = async () => {
+      try {
+        unlistenFn = await listen("history-item-probed", (event) => {
+          const { id, ...properties } = event.payload as HistoryProbeUpdate;
+          setAudioFileHistory((prev) =>
+            prev.map((item) => (item.id === id ? { ...item, ...properties } : item))
+          );
+        });
+      } catch (error) {
+        console.error("Failed to setup history probe listener:", error);
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, []);
+
   const handleStemToggle = (stem: string) => {
     setSelectedStems((prev) =>
       prev.includes(stem) ? prev.filter((s) => s !== stem) : [...prev, stem]
@@ -488,7 +591,7 @@ function App() {
 
   const handleDeleteFile = async (
     uniqueFileId: string,
-    fileToDelete: AudioFileInfo
+    fileToDelete: Pick<AudioFileInfo, "name" | "file_path">
   ) => {
     if (!fileToDelete) return;
 
@@ -516,13 +619,13 @@ function App() {
 
         setConsoleMessages((prev) => [
           ...prev,
-          `Deleted file: ${fileToDelete.name}`,
+          `Moved to trash: ${fileToDelete.name}`,
         ]);
       } catch (error) {
         console.error("Failed to delete file:", error);
         setConsoleMessages((prev) => [
           ...prev,
-          `Failed to delete file: ${error}`,
+          `Failed to delete file: ${errorMessage(error)}`,
         ]);
       }
     } else {
@@ -590,17 +693,16 @@ function App() {
         setEndTime([enhancedVideoInfo.duration]);
       }
     } catch (error) {
-      const errorMessage = error as string;
       setVideoInfo({
         title: "Error",
-        error: errorMessage,
+        error: errorMessage(error),
         duration: undefined,
         thumbnail: undefined,
         uploader: undefined,
         view_count: undefined,
         video_url: undefined,
       });
-      setConsoleMessages((prev) => [...prev, `Error: ${errorMessage}`]);
+      setConsoleMessages((prev) => [...prev, `Error: ${errorMessage(error)}`]);
     } finally {
       setIsLoadingVideo(false);
     }
@@ -659,7 +761,7 @@ function App() {
           } catch (error) {
             setConsoleMessages((prev) => [
               ...prev,
-              `GPU detection failed, using CPU: ${error}`,
+              `GPU detection failed, using CPU: ${errorMessage(error)}`,
             ]);
             gpuInfo = {
               gpu_type: "cpu",
@@ -676,6 +778,13 @@ function App() {
                 ...separationSettings,
                 model_filename: selectedModel,
                 model_file_dir: settings.model_directory || "",
+                output_dir: settings.separation_settings?.output_dir || "",
+                output_location:
+                  settings.separation_settings?.output_location || "managed",
+                output_name_template:
+                  settings.separation_settings?.output_name_template || "",
+                retry_on_out_of_memory:
+                  settings.separation_settings?.retry_on_out_of_memory ?? true,
                 single_stem:
                   selectedStems.length === 1 ? selectedStems[0] : undefined,
                 use_gpu: gpuInfo.is_available,
@@ -687,6 +796,7 @@ function App() {
           );
 
           if (separationResult.success) {
+            setLastOutputFiles(separationResult.output_files);
             setConsoleMessages((prev) => [
               ...prev,
               "Stem separation completed successfully!",
@@ -701,7 +811,7 @@ function App() {
         } catch (error) {
           setConsoleMessages((prev) => [
             ...prev,
-            `Stem separation error: ${error}`,
+            `Stem separation error: ${errorMessage(error)}`,
           ]);
         } finally {
           setIsSeparating(false);
@@ -762,7 +872,7 @@ function App() {
             } catch (error) {
               setConsoleMessages((prev) => [
                 ...prev,
-                `GPU detection failed, using CPU: ${error}`,
+                `GPU detection failed, using CPU: ${errorMessage(error)}`,
               ]);
               gpuInfo = {
                 gpu_type: "cpu",
@@ -779,6 +889,13 @@ function App() {
                   ...separationSettings,
                   model_filename: selectedModel,
                   model_file_dir: settings.model_directory || "",
+                  output_dir: settings.separation_settings?.output_dir || "",
+                  output_location:
+                    settings.separation_settings?.output_location || "managed",
+                  output_name_template:
+                    settings.separation_settings?.output_name_template || "",
+                  retry_on_out_of_memory:
+                    settings.separation_settings?.retry_on_out_of_memory ?? true,
                   single_stem:
                     selectedStems.length === 1 ? selectedStems[0] : undefined,
                   use_gpu: gpuInfo.is_available,
@@ -790,6 +907,7 @@ function App() {
             );
 
             if (separationResult.success) {
+              setLastOutputFiles(separationResult.output_files);
               setConsoleMessages((prev) => [
                 ...prev,
                 "Stem separation completed successfully!",
@@ -805,7 +923,7 @@ function App() {
           } catch (error) {
             setConsoleMessages((prev) => [
               ...prev,
-              `Stem separation error: ${error}`,
+              `Stem separation error: ${errorMessage(error)}`,
             ]);
           } finally {
             setIsSeparating(false);
@@ -819,10 +937,9 @@ function App() {
         ]);
       }
     } catch (error) {
-      const errorMessage = error as string;
       setConsoleMessages((prev) => [
         ...prev,
-        `Processing failed: ${errorMessage}`,
+        `Processing failed: ${errorMessage(error)}`,
       ]);
       setProgress(0);
     } finally {
@@ -852,10 +969,9 @@ function App() {
 
       setConsoleMessages((prev) => [...prev, result]);
     } catch (error) {
-      const errorMessage = error as string;
       setConsoleMessages((prev) => [
         ...prev,
-        `Failed to stop process: ${errorMessage}`,
+        `Failed to stop process: ${errorMessage(error)}`,
       ]);
     }
   };
@@ -885,7 +1001,7 @@ function App() {
     } catch (error) {
       setConsoleMessages((prev) => [
         ...prev,
-        `Failed to select file: ${error}`,
+        `Failed to select file: ${errorMessage(error)}`,
       ]);
     }
   };
@@ -909,7 +1025,7 @@ function App() {
       setInputType("Unknown");
       setConsoleMessages((prev) => [
         ...prev,
-        `Failed to detect input type: ${error}`,
+        `Failed to detect input type: ${errorMessage(error)}`,
       ]);
     }
   };
@@ -1027,7 +1143,7 @@ function App() {
       console.error("Failed to load available stems and models:", error);
       setConsoleMessages((prev) => [
         ...prev,
-        `Failed to load models: ${error}`,
+        `Failed to load models: ${errorMessage(error)}`,
       ]);
     }
   };
@@ -1037,6 +1153,52 @@ function App() {
     loadAvailableStemsAndModels();
   }, []);
 
+  // Keep history and models in sync with changes made outside the app
+  useEffect(() => {
+    const unlistenFns: (() => void)[] = [];
+
+    const setupListeners = async () => {
+      try {
+        unlistenFns.push(
+          await listen("history-changed", (event) => {
+            const { added, modified, removed } = event.payload as HistoryChangedEvent;
+            const dropped = new Set([...removed, ...added.map((item) => item.id)]);
+            setAudioFileHistory((prev) => [
+              ...added,
+              ...prev
+                .filter((item) => !dropped.has(item.id))
+                .map((item) => modified.find((m) => m.id === item.id) ?? item),
+            ]);
+          })
+        );
+        unlistenFns.push(
+          await listen("models-changed", () => {
+            loadAvailableStemsAndModels();
+          })
+        );
+        unlistenFns.push(
+          await listen("cleanup-finished", (event) => {
+            const { candidates, failures } = event.payload as CleanupReport;
+            setConsoleMessages((prev) => [
+              ...prev,
+              `Automatic cleanup moved ${candidates.length} file${candidates.length === 1 ? "" : "s"} to the trash` +
+                (failures.length > 0 ? ` (${failures.length} failed)` : ""),
+              ...candidates.map((c) => `  ${c.name}`),
+            ]);
+          })
+        );
+      } catch (error) {
+        console.error("Failed to setup file watcher listeners:", error);
+      }
+    };
+
+    setupListeners();
+
+    return () => {
+      unlistenFns.forEach((unlisten) => unlisten());
+    };
+  }, []);
+
   // Listen for download progress events
   useEffect(() => {
     const unlisten = listen("download-progress", (event) => {
@@ -1251,6 +1413,305 @@ function App() {
   //   }
   // };
 
+  const handleExportLibrary = async (format: "csv" | "json") => {
+    try {
+      const report = await invoke<{ path: string; rows: number }>(
+        "export_library",
+        { query: {}, format }
+      );
+      setConsoleMessages((prev) => [
+        ...prev,
+        `Exported ${report.rows} items to ${report.path}`,
+      ]);
+    } catch (error) {
+      if (errorCode(error) === "cancelled") return;
+      setConsoleMessages((prev) => [
+        ...prev,
+        `Failed to export library: ${errorMessage(error)}`,
+      ]);
+    }
+  };
+
+  const copyToClipboard = async (
+    target: Pick<AudioFileInfo, "name" | "file_path">
+  ) => {
+    if (target.file_path) {
+      try {
+        const result = await invoke<string>(
+          "copy_audio_file_to_clipboard",
+          {
+            filePath: target.file_path,
+          }
+        );
+        setConsoleMessages((prev) => [...prev, result]);
+      } catch (error) {
+        // Fallback to copying file path as text
+        const textToCopy = target.file_path || target.name;
+        navigator.clipboard.writeText(textToCopy);
+        setConsoleMessages((prev) => [
+          ...prev,
+          `Fallback: Copied file path "${textToCopy}" to clipboard. Error: ${errorMessage(error)}`,
+        ]);
+      }
+    } else {
+      // If no file path, copy the name as before
+      navigator.clipboard.writeText(target.name);
+      setConsoleMessages((prev) => [
+        ...prev,
+        `Copied "${target.name}" to clipboard.`,
+      ]);
+    }
+  };
+
+  const copyFilesToClipboard = async (filePaths: string[]) => {
+    try {
+      const result = await invoke<string>("copy_files_to_clipboard", {
+        filePaths,
+      });
+      setConsoleMessages((prev) => [...prev, result]);
+    } catch (error) {
+      setConsoleMessages((prev) => [
+        ...prev,
+        `Failed to copy files to clipboard: ${errorMessage(error)}`,
+      ]);
+    }
+  };
+
+  const toggleHistorySelection = (filePath: string) => {
+    setSelectedHistoryPaths((prev) => {
+      const next = new Set(prev);
+      if (next.has(filePath)) {
+        next.delete(filePath);
+      } else {
+        next.add(filePath);
+      }
+      return next;
+    });
+  };
+
+  const openInExplorer = async (
+    target: Pick<AudioFileInfo, "name" | "file_path">
+  ) => {
+    try {
+      await invoke("open_in_explorer", {
+        path: target.file_path,
+      });
+      setConsoleMessages((prev) => [
+        ...prev,
+        `Opened file location: ${target.name}`,
+      ]);
+    } catch (error) {
+      setConsoleMessages((prev) => [
+        ...prev,
+        `Failed to open file location: ${errorMessage(error)}`,
+      ]);
+    }
+  };
+
+  const renderHistoryItem = (file: AudioFileInfo) => (
+    <div
+      key={getUniqueFileId(file)}
+      className="relative p-3 bg-slate-50 dark:bg-gray-800 rounded-lg hover:bg-slate-100 dark:hover:bg-gray-700 transition-colors cursor-pointer"
+      onClick={async (e) => {
+        // Don't copy if clicking on buttons
+        if ((e.target as Element).closest("button")) {
+          return;
+        }
+
+        await copyToClipboard(file);
+      }}
+    >
+      <div className="pr-16">
+        <div className="flex items-center gap-2">
+          <div
+            onClick={(e) => {
+              e.stopPropagation();
+              toggleHistorySelection(file.file_path);
+            }}
+            title="Select for multi-file copy"
+          >
+            <Checkbox
+              checked={selectedHistoryPaths.has(file.file_path)}
+              className="pointer-events-none"
+            />
+          </div>
+          <div className="font-medium text-sm text-gray-900 dark:text-gray-100 truncate">
+            {file.name}
+          </div>
+        </div>
+        <div className="flex items-center gap-2 mt-1">
+          <Badge variant="secondary" className="text-xs">
+            {file.directory_type === "downloads"
+              ? "Downloaded"
+              : "Separated"}
+          </Badge>
+          <span className="text-xs text-gray-500 dark:text-gray-400">
+            {file.created_display}
+          </span>
+          <span className="text-xs text-gray-500 dark:text-gray-400">
+            {(file.file_size / 1024 / 1024).toFixed(1)} MB
+          </span>
+        </div>
+        <div className="text-xs text-gray-400 dark:text-gray-500 mt-1">
+          Click to copy audio file for DAW pasting
+        </div>
+      </div>
+      <div className="absolute bottom-2 right-2 flex gap-1">
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={async (e) => {
+            e.stopPropagation();
+            await openInExplorer(file);
+          }}
+          className="text-blue-600 hover:text-blue-700 h-6 w-6 p-0"
+          title="Open in file explorer"
+        >
+          <FolderOpen className="h-3 w-3" />
+        </Button>
+        <Button
+          variant="outline"
+          size="sm"
+          onClick={(e) => {
+            e.preventDefault();
+            e.stopPropagation();
+            e.nativeEvent.stopImmediatePropagation();
+            handleDeleteFile(getUniqueFileId(file), file);
+          }}
+          className={`h-6 px-2 text-xs ${
+            deletingFileIds.has(getUniqueFileId(file))
+              ? "text-red-600 hover:text-red-700 bg-red-50 dark:bg-red-900/20"
+              : "text-red-600 hover:text-red-700"
+          }`}
+          title={
+            deletingFileIds.has(getUniqueFileId(file))
+              ? "Click again to confirm"
+              : "Delete file"
+          }
+        >
+          {deletingFileIds.has(getUniqueFileId(file)) ? (
+            "Confirm"
+          ) : (
+            <Trash2 className="h-3 w-3" />
+          )}
+        </Button>
+      </div>
+    </div>
+  );
+
+  const renderHistorySet = (set: HistorySetGroup) => {
+    const expanded = expandedSets.has(set.path);
+    const setTarget = { name: set.name, file_path: set.path };
+    const setDeleteId = `set-${set.path}`;
+    const totalSize = set.files.reduce((sum, f) => sum + f.file_size, 0);
+    return (
+      <div key={setDeleteId} className="space-y-2">
+        <div
+          className="relative p-3 bg-slate-100 dark:bg-gray-800 rounded-lg hover:bg-slate-200 dark:hover:bg-gray-700 transition-colors cursor-pointer"
+          onClick={(e) => {
+            if ((e.target as Element).closest("button")) {
+              return;
+            }
+            setExpandedSets((prev) => {
+              const next = new Set(prev);
+              if (next.has(set.path)) {
+                next.delete(set.path);
+              } else {
+                next.add(set.path);
+              }
+              return next;
+            });
+          }}
+        >
+          <div className="pr-24 flex items-center gap-2">
+            {expanded ? (
+              <ChevronDown className="h-4 w-4 shrink-0" />
+            ) : (
+              <ChevronRight className="h-4 w-4 shrink-0" />
+            )}
+            <div className="min-w-0">
+              <div className="font-medium text-sm text-gray-900 dark:text-gray-100 truncate">
+                {set.name}
+              </div>
+              <div className="text-xs text-gray-500 dark:text-gray-400 mt-1">
+                {set.files.length} files · {(totalSize / 1024 / 1024).toFixed(1)} MB
+              </div>
+            </div>
+          </div>
+          <div className="absolute bottom-2 right-2 flex gap-1">
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={async (e) => {
+                e.stopPropagation();
+                await copyToClipboard(setTarget);
+              }}
+              className="h-6 w-6 p-0"
+              title="Copy folder"
+            >
+              <Clipboard className="h-3 w-3" />
+            </Button>
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={async (e) => {
+                e.stopPropagation();
+                try {
+                  await invoke("open_files_in_explorer", {
+                    paths: set.files.map((f) => f.file_path),
+                  });
+                  setConsoleMessages((prev) => [
+                    ...prev,
+                    `Opened ${set.files.length} files in ${set.name}`,
+                  ]);
+                } catch (error) {
+                  setConsoleMessages((prev) => [
+                    ...prev,
+                    `Failed to open file location: ${errorMessage(error)}`,
+                  ]);
+                }
+              }}
+              className="text-blue-600 hover:text-blue-700 h-6 w-6 p-0"
+              title="Show all files in file explorer"
+            >
+              <FolderOpen className="h-3 w-3" />
+            </Button>
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={(e) => {
+                e.preventDefault();
+                e.stopPropagation();
+                handleDeleteFile(setDeleteId, setTarget);
+              }}
+              className={`h-6 px-2 text-xs ${
+                deletingFileIds.has(setDeleteId)
+                  ? "text-red-600 hover:text-red-700 bg-red-50 dark:bg-red-900/20"
+                  : "text-red-600 hover:text-red-700"
+              }`}
+              title={
+                deletingFileIds.has(setDeleteId)
+                  ? "Click again to confirm"
+                  : "Delete folder"
+              }
+            >
+              {deletingFileIds.has(setDeleteId) ? (
+                "Confirm"
+              ) : (
+                <Trash2 className="h-3 w-3" />
+              )}
+            </Button>
+          </div>
+        </div>
+        {expanded && (
+          <div className="pl-4 space-y-2">
+            {set.files.map((file) => renderHistoryItem(file))}
+          </div>
+        )}
+      </div>
+    );
+  };
+
   return (
     <div
       key={showSettings ? "settings" : "main"}
@@ -1338,7 +1799,7 @@ function App() {
                           } catch (error) {
                             setConsoleMessages((prev) => [
                               ...prev,
-                              `Failed to save settings: ${error}`,
+                              `Failed to save settings: ${errorMessage(error)}`,
                             ]);
                           }
                         }}
@@ -1358,7 +1819,7 @@ function App() {
                           } catch (error) {
                             setConsoleMessages((prev) => [
                               ...prev,
-                              `Failed to open directory: ${error}`,
+                              `Failed to open directory: ${errorMessage(error)}`,
                             ]);
                           }
                         }}
@@ -1384,7 +1845,7 @@ function App() {
                           } catch (error) {
                             setConsoleMessages((prev) => [
                               ...prev,
-                              `Failed to select folder: ${error}`,
+                              `Failed to select folder: ${errorMessage(error)}`,
                             ]);
                           }
                         }}
@@ -2128,125 +2589,58 @@ function App() {
 
             {/* Download History */}
             <Card className="flex-1 flex flex-col h-full">
-              <CardHeader className="pt-4 pb-4 flex items-left">
+              <CardHeader className="pt-4 pb-4 flex flex-row items-center justify-between">
                 <CardTitle className="text-lg">Download History</CardTitle>
-              </CardHeader>
-              <CardContent className="pt-0 flex-1 flex flex-col min-h-0">
-                <div className="flex-grow max-h-[calc(100vh-300px)] overflow-y-auto space-y-2 pr-2 scrollbar-thin scrollbar-thumb-gray-300 dark:scrollbar-thumb-gray-600 scrollbar-track-transparent">
-                  {audioFileHistory.map((file) => (
-                    <div
-                      key={getUniqueFileId(file)}
-                      className="relative p-3 bg-slate-50 dark:bg-gray-800 rounded-lg hover:bg-slate-100 dark:hover:bg-gray-700 transition-colors cursor-pointer"
-                      onClick={async (e) => {
-                        // Don't copy if clicking on buttons
-                        if ((e.target as Element).closest("button")) {
-                          return;
-                        }
-
-                        if (file.file_path) {
-                          try {
-                            const result = await invoke<string>(
-                              "copy_audio_file_to_clipboard",
-                              {
-                                filePath: file.file_path,
-                              }
-                            );
-                            setConsoleMessages((prev) => [...prev, result]);
-                          } catch (error) {
-                            // Fallback to copying file path as text
-                            const textToCopy = file.file_path || file.name;
-                            navigator.clipboard.writeText(textToCopy);
-                            setConsoleMessages((prev) => [
-                              ...prev,
-                              `Fallback: Copied file path "${textToCopy}" to clipboard. Error: ${error}`,
-                            ]);
-                          }
-                        } else {
-                          // If no file path, copy the name as before
-                          navigator.clipboard.writeText(file.name);
-                          setConsoleMessages((prev) => [
-                            ...prev,
-                            `Copied "${file.name}" to clipboard.`,
-                          ]);
-                        }
+                <div className="flex gap-1">
+                  {selectedHistoryPaths.size > 0 && (
+                    <Button
+                      variant="outline"
+                      size="sm"
+                      onClick={async () => {
+                        await copyFilesToClipboard(Array.from(selectedHistoryPaths));
+                        setSelectedHistoryPaths(new Set());
                       }}
+                      className="h-6 px-2 text-xs"
+                      title="Copy the selected files for DAW pasting"
                     >
-                      <div className="pr-16">
-                        <div className="font-medium text-sm text-gray-900 dark:text-gray-100 truncate">
-                          {file.name}
-                        </div>
-                        <div className="flex items-center gap-2 mt-1">
-                          <Badge variant="secondary" className="text-xs">
-                            {file.directory_type === "downloads"
-                              ? "Downloaded"
-                              : "Separated"}
-                          </Badge>
-                          <span className="text-xs text-gray-500 dark:text-gray-400">
-                            {file.created_display}
-                          </span>
-                          <span className="text-xs text-gray-500 dark:text-gray-400">
-                            {(file.file_size / 1024 / 1024).toFixed(1)} MB
-                          </span>
-                        </div>
-                        <div className="text-xs text-gray-400 dark:text-gray-500 mt-1">
-                          Click to copy audio file for DAW pasting
-                        </div>
-                      </div>
-                      <div className="absolute bottom-2 right-2 flex gap-1">
-                        <Button
-                          variant="outline"
-                          size="sm"
-                          onClick={async (e) => {
-                            e.stopPropagation();
-                            try {
-                              await invoke("open_in_explorer", {
-                                path: file.file_path,
-                              });
-                              setConsoleMessages((prev) => [
-                                ...prev,
-                                `Opened file location: ${file.name}`,
-                              ]);
-                            } catch (error) {
-                              setConsoleMessages((prev) => [
-                                ...prev,
-                                `Failed to open file location: ${error}`,
-                              ]);
-                            }
-                          }}
-                          className="text-blue-600 hover:text-blue-700 h-6 w-6 p-0"
-                          title="Open in file explorer"
-                        >
-                          <FolderOpen className="h-3 w-3" />
-                        </Button>
-                        <Button
-                          variant="outline"
-                          size="sm"
-                          onClick={(e) => {
-                            e.preventDefault();
-                            e.stopPropagation();
-                            e.nativeEvent.stopImmediatePropagation();
-                            handleDeleteFile(getUniqueFileId(file), file);
-                          }}
-                          className={`h-6 px-2 text-xs ${
-                            deletingFileIds.has(getUniqueFileId(file))
-                              ? "text-red-600 hover:text-red-700 bg-red-50 dark:bg-red-900/20"
-                              : "text-red-600 hover:text-red-700"
-                          }`}
-                          title={
-                            deletingFileIds.has(getUniqueFileId(file))
-                              ? "Click again to confirm"
-                              : "Delete file"
-                          }
-                        >
-                          {deletingFileIds.has(getUniqueFileId(file)) ? (
-                            "Confirm"
-                          ) : (
-                            <Trash2 className="h-3 w-3" />
-                          )}
-                        </Button>
-                      </div>
-                    </div>
+                      <Clipboard className="h-3 w-3 mr-1" />
+                      Copy {selectedHistoryPaths.size} selected
+                    </Button>
+                  )}
+                  {lastOutputFiles.length > 0 && (
+                    <Button
+                      variant="outline"
+                      size="sm"
+                      onClick={() => copyFilesToClipboard(lastOutputFiles)}
+                      className="h-6 px-2 text-xs"
+                      title="Copy every file from the last separation"
+                    >
+                      <Clipboard className="h-3 w-3 mr-1" />
+                      Copy last stems ({lastOutputFiles.length})
+                    </Button>
+                  )}
+                  {(["csv", "json"] as const).map((format) => (
+                    <Button
+                      key={format}
+                      variant="outline"
+                      size="sm"
+                      onClick={() => handleExportLibrary(format)}
+                      className="h-6 px-2 text-xs"
+                      title={`Export history with sources and models as ${format.toUpperCase()}`}
+                    >
+                      <FileDown className="h-3 w-3 mr-1" />
+                      {format.toUpperCase()}
+                    </Button>
                   ))}
+                </div>
+              </CardHeader>
+              <CardContent className="pt-0 flex-1 flex flex-col min-h-0">
+                <div className="flex-grow max-h-[calc(100vh-300px)] overflow-y-auto space-y-2 pr-2 scrollbar-thin scrollbar-thumb-gray-300 dark:scrollbar-thumb-gray-600 scrollbar-track-transparent">
+                  {groupHistory(audioFileHistory).map((entry) =>
+                    entry.kind === "file"
+                      ? renderHistoryItem(entry.file)
+                      : renderHistorySet(entry.set)
+                  )}
                   {audioFileHistory.length === 0 && (
                     <div className="text-center text-gray-500 dark:text-gray-400 py-8">
                       No audio files found. Download or separate some audio to
@@ -13,6 +13,7 @@ import {
 } from "./components/ui/select";
 import { Checkbox } from "./components/ui/checkbox";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import {
   X,
   Save,
@@ -23,9 +24,11 @@ import {
   RefreshCw,
   Trash2,
   Info,
+  FolderPlus,
 } from "lucide-react";
 import { useTheme } from "./hooks/use-theme";
 import { useModelCache } from "./hooks/use-model-cache";
+import { errorCode, errorMessage } from "./lib/utils";
 
 const TABS = [
   "General",
@@ -35,6 +38,34 @@ const TABS = [
 ] as const;
 type Tab = (typeof TABS)[number];
 
+interface HotFolder {
+  id: string;
+  path: string;
+  output_dir: string;
+  profile: { model_filename?: string };
+  stable_seconds: number;
+  enabled: boolean;
+  created_at: number;
+}
+
+interface HotFolderJobEvent {
+  folder_id: string;
+  file: string;
+  status: "processing" | "done" | "failed";
+  output_files: string[];
+  error: string | null;
+}
+
+interface CleanupReport {
+  dry_run: boolean;
+  candidates: { id: string; name: string; reason: string }[];
+  bytes_trashed: number;
+  bytes_deleted: number;
+  failures: string[];
+}
+
+const formatMegabytes = (bytes: number) => `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+
 interface ModelInfo {
   filename: string;
   arch: string;
@@ -50,37 +81,17 @@ interface Settings {
   download_path: string;
   audio_format: string;
   audio_quality: string;
-  video_format: string;
-  video_quality: string;
-  extract_audio: boolean;
   write_subtitles: boolean;
   write_thumbnail: boolean;
   write_description: boolean;
   write_info: boolean;
-  write_annotations: boolean;
   write_comments: boolean;
   write_automatic_subtitles: boolean;
   write_manual_subtitles: boolean;
-  max_downloads: number;
   retries: number;
   fragment_retries: number;
   file_access_retries: number;
   concurrent_fragments: number;
-  max_downloads_per_host: number;
-  max_downloads_per_playlist: number;
-  max_downloads_per_channel: number;
-  max_downloads_per_user: number;
-  max_downloads_per_extractor: number;
-  max_downloads_per_video: number;
-  max_downloads_per_audio: number;
-  max_downloads_per_subtitle: number;
-  max_downloads_per_thumbnail: number;
-  max_downloads_per_description: number;
-  max_downloads_per_info: number;
-  max_downloads_per_annotations: number;
-  max_downloads_per_comments: number;
-  max_downloads_per_automatic_subtitles: number;
-  max_downloads_per_manual_subtitles: number;
 
   // Stem separation settings (simplified - only output format for now)
   separation_settings: {
@@ -116,11 +127,31 @@ interface Settings {
     mdxc_overlap: number;
     mdxc_batch_size: number;
     mdxc_pitch_shift: number;
+    output_location: "managed" | "next_to_source";
+    output_name_template: string;
+    retry_on_out_of_memory: boolean;
   };
 
   // Model management settings
   model_directory: string;
   enable_stem_extraction: boolean;
+  trash_retention_days: number;
+  history_scan_depth: number;
+  history_extensions: string[];
+  cleanup_policy: {
+    keep_days: number | null;
+    max_total_gb: number | null;
+    keep_favorites: boolean;
+    delete_downloads_with_stems: boolean;
+    automatic: boolean;
+  };
+  notifications: {
+    enabled: boolean;
+    only_when_unfocused: boolean;
+    downloads: boolean;
+    separations: boolean;
+    failures: boolean;
+  };
 }
 
 const DEFAULT_SETTINGS: Settings = {
@@ -128,37 +159,17 @@ const DEFAULT_SETTINGS: Settings = {
   download_path: "Documents/Resample2", // This will be set properly by the backend
   audio_format: "mp3",
   audio_quality: "0",
-  video_format: "mp4",
-  video_quality: "best",
-  extract_audio: true,
   write_subtitles: false,
   write_thumbnail: false,
   write_description: false,
   write_info: false,
-  write_annotations: false,
   write_comments: false,
   write_automatic_subtitles: false,
   write_manual_subtitles: false,
-  max_downloads: 1,
   retries: 10,
   fragment_retries: 10,
   file_access_retries: 3,
   concurrent_fragments: 1,
-  max_downloads_per_host: 0,
-  max_downloads_per_playlist: 0,
-  max_downloads_per_channel: 0,
-  max_downloads_per_user: 0,
-  max_downloads_per_extractor: 0,
-  max_downloads_per_video: 0,
-  max_downloads_per_audio: 0,
-  max_downloads_per_subtitle: 0,
-  max_downloads_per_thumbnail: 0,
-  max_downloads_per_description: 0,
-  max_downloads_per_info: 0,
-  max_downloads_per_annotations: 0,
-  max_downloads_per_comments: 0,
-  max_downloads_per_automatic_subtitles: 0,
-  max_downloads_per_manual_subtitles: 0,
   separation_settings: {
     model_filename: "model_bs_roformer_ep_317_sdr_12.9755.ckpt",
     output_format: "FLAC",
@@ -190,9 +201,29 @@ const DEFAULT_SETTINGS: Settings = {
     mdxc_overlap: 8,
     mdxc_batch_size: 1,
     mdxc_pitch_shift: 0,
+    output_location: "managed",
+    output_name_template: "",
+    retry_on_out_of_memory: true,
   },
   model_directory: "Documents/Resample2/Models",
   enable_stem_extraction: false,
+  trash_retention_days: 30,
+  history_scan_depth: 2,
+  history_extensions: ["mp3", "wav", "flac", "m4a", "aac", "ogg", "opus", "aiff", "aif", "wma"],
+  cleanup_policy: {
+    keep_days: null,
+    max_total_gb: null,
+    keep_favorites: true,
+    delete_downloads_with_stems: false,
+    automatic: false,
+  },
+  notifications: {
+    enabled: true,
+    only_when_unfocused: false,
+    downloads: true,
+    separations: true,
+    failures: true,
+  },
 };
 
 const fieldWrapperClass = "space-y-4";
@@ -261,6 +292,16 @@ export function SettingsPage({
     "idle" | "saving" | "success" | "error"
   >("idle");
 
+  const [hotFolders, setHotFolders] = useState<HotFolder[]>([]);
+  const [hotFolderStableSeconds, setHotFolderStableSeconds] = useState(10);
+  const [hotFolderStatus, setHotFolderStatus] = useState<
+    Record<string, HotFolderJobEvent>
+  >({});
+  const [hotFolderError, setHotFolderError] = useState<string | null>(null);
+  const [diagnosticsStatus, setDiagnosticsStatus] = useState<string | null>(null);
+  const [cleanupStatus, setCleanupStatus] = useState<string | null>(null);
+  const [isCleaningUp, setIsCleaningUp] = useState(false);
+
   const { setTheme } = useTheme();
 
   // Load settings on component mount
@@ -268,6 +309,35 @@ export function SettingsPage({
     loadSettings();
   }, []);
 
+  // Hot folders are stored by the backend, separately from settings.json
+  useEffect(() => {
+    loadHotFolders();
+
+    let unlistenFn: (() => void) | null = null;
+    const setupListener = async () => {
+      try {
+        unlistenFn = await listen<HotFolderJobEvent>(
+          "hot-folder-job",
+          (event) => {
+            setHotFolderStatus((prev) => ({
+              ...prev,
+              [event.payload.folder_id]: event.payload,
+            }));
+          }
+        );
+      } catch (error) {
+        console.error("Failed to setup hot folder listener:", error);
+      }
+    };
+    setupListener();
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, []);
+
   // Auto-refresh downloaded models when settings page opens
   useEffect(() => {
     if (settings.model_directory) {
@@ -300,6 +370,93 @@ export function SettingsPage({
     }
   };
 
+  const loadHotFolders = async () => {
+    try {
+      setHotFolders(await invoke<HotFolder[]>("list_hot_folders"));
+    } catch (error) {
+      console.error("Failed to load hot folders:", error);
+    }
+  };
+
+  const addHotFolder = async () => {
+    setHotFolderError(null);
+    try {
+      // Opens the folder picker; the folder keeps a copy of the current
+      // separation settings as its profile
+      await invoke("add_hot_folder", {
+        settings: settings.separation_settings,
+        stableSeconds: hotFolderStableSeconds,
+      });
+      await loadHotFolders();
+    } catch (error) {
+      if (errorCode(error) !== "cancelled") {
+        setHotFolderError(errorMessage(error));
+      }
+    }
+  };
+
+  const exportDiagnostics = async () => {
+    try {
+      const path = await invoke<string>("export_diagnostics");
+      setDiagnosticsStatus(`Saved to ${path}`);
+    } catch (error) {
+      if (errorCode(error) !== "cancelled") {
+        setDiagnosticsStatus(`Export failed: ${errorMessage(error)}`);
+      }
+    }
+  };
+
+  const runCleanup = async (dryRun: boolean) => {
+    if (
+      !dryRun &&
+      cleanupPolicy.max_total_gb !== null &&
+      !confirm("Items over the size limit are deleted permanently, not moved to the trash. Continue?")
+    ) {
+      return;
+    }
+    setIsCleaningUp(true);
+    try {
+      const report = await invoke<CleanupReport>("run_cleanup", {
+        dryRun,
+        policy: cleanupPolicy,
+      });
+      const count = report.candidates.length;
+      let sizes = `${formatMegabytes(report.bytes_trashed)} to trash`;
+      if (report.bytes_deleted > 0) {
+        sizes += `, ${formatMegabytes(report.bytes_deleted)} deleted`;
+      }
+      let status = dryRun
+        ? `Would remove ${count} file${count === 1 ? "" : "s"} (${sizes})`
+        : `Removed ${count} file${count === 1 ? "" : "s"} (${sizes})`;
+      if (report.failures.length > 0) {
+        status += `; ${report.failures.length} failed: ${report.failures[0]}`;
+      }
+      setCleanupStatus(status);
+    } catch (error) {
+      setCleanupStatus(`Cleanup failed: ${errorMessage(error)}`);
+    } finally {
+      setIsCleaningUp(false);
+    }
+  };
+
+  const removeHotFolder = async (id: string) => {
+    try {
+      await invoke("remove_hot_folder", { id });
+      await loadHotFolders();
+    } catch (error) {
+      setHotFolderError(errorMessage(error));
+    }
+  };
+
+  const toggleHotFolder = async (id: string, enabled: boolean) => {
+    try {
+      await invoke("set_hot_folder_enabled", { id, enabled });
+      await loadHotFolders();
+    } catch (error) {
+      setHotFolderError(errorMessage(error));
+    }
+  };
+
   const saveSettings = async () => {
     setSaveStatus("saving");
     try {
@@ -358,6 +515,13 @@ export function SettingsPage({
     }
   };
 
+  // Settings saved before notifications existed don't have the section
+  const notificationSettings =
+    settings.notifications ?? DEFAULT_SETTINGS.notifications;
+  const cleanupPolicy = settings.cleanup_policy ?? DEFAULT_SETTINGS.cleanup_policy;
+  const updateCleanupPolicy = (changes: Partial<Settings["cleanup_policy"]>) =>
+    updateSetting("cleanup_policy", { ...cleanupPolicy, ...changes });
+
   const updateSetting = <K extends keyof Settings>(
     key: K,
     value: Settings[K]
@@ -532,77 +696,12 @@ export function SettingsPage({
                   </div>
                 </div>
 
-                {/* Video Settings */}
-                <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
-                  <div>
-                    <label className="block mb-2 font-semibold">
-                      Video Format
-                    </label>
-                    <Select
-                      value={settings.video_format}
-                      onValueChange={(val) =>
-                        updateSetting("video_format", val)
-                      }
-                    >
-                      <SelectTrigger>
-                        <SelectValue />
-                      </SelectTrigger>
-                      <SelectContent>
-                        <SelectItem value="mp4">MP4</SelectItem>
-                        <SelectItem value="webm">WebM</SelectItem>
-                        <SelectItem value="mkv">MKV</SelectItem>
-                        <SelectItem value="avi">AVI</SelectItem>
-                      </SelectContent>
-                    </Select>
-                  </div>
-
-                  <div>
-                    <label className="block mb-2 font-semibold">
-                      Video Quality
-                    </label>
-                    <Select
-                      value={settings.video_quality}
-                      onValueChange={(val) =>
-                        updateSetting("video_quality", val)
-                      }
-                    >
-                      <SelectTrigger>
-                        <SelectValue />
-                      </SelectTrigger>
-                      <SelectContent>
-                        <SelectItem value="best">Best</SelectItem>
-                        <SelectItem value="worst">Worst</SelectItem>
-                        <SelectItem value="bestvideo+bestaudio">
-                          Best Video + Audio
-                        </SelectItem>
-                        <SelectItem value="720p">720p</SelectItem>
-                        <SelectItem value="1080p">1080p</SelectItem>
-                        <SelectItem value="1440p">1440p</SelectItem>
-                        <SelectItem value="2160p">4K</SelectItem>
-                      </SelectContent>
-                    </Select>
-                  </div>
-                </div>
-
                 {/* Download Options */}
                 <div>
                   <label className="block mb-3 font-semibold">
                     Download Options
                   </label>
                   <div className="space-y-3">
-                    <div className="flex items-center space-x-3">
-                      <Checkbox
-                        id="extractAudio"
-                        checked={settings.extract_audio}
-                        onCheckedChange={(checked) =>
-                          updateSetting("extract_audio", checked as boolean)
-                        }
-                      />
-                      <label htmlFor="extractAudio" className="text-sm">
-                        Extract audio only
-                      </label>
-                    </div>
-
                     <div className="flex items-center space-x-3">
                       <Checkbox
                         id="writeSubtitles"
@@ -612,7 +711,7 @@ export function SettingsPage({
                         }
                       />
                       <label htmlFor="writeSubtitles" className="text-sm">
-                        Write subtitles
+                        Write subtitles (manual and automatic)
                       </label>
                     </div>
 
@@ -707,6 +806,70 @@ export function SettingsPage({
                   </Select>
                 </div>
 
+                {/* Output Location */}
+                <div>
+                  <InfoTooltip title="Where separated files are written. The Resample2 Separated folder keeps them in history; next to source writes a Separated folder beside the input file.">
+                    <label className="block mb-2 font-semibold">
+                      Output Location
+                    </label>
+                  </InfoTooltip>
+                  <Select
+                    value={settings.separation_settings.output_location || "managed"}
+                    onValueChange={(val) =>
+                      updateSetting("separation_settings", {
+                        ...settings.separation_settings,
+                        output_location: val as "managed" | "next_to_source",
+                      })
+                    }
+                  >
+                    <SelectTrigger>
+                      <SelectValue />
+                    </SelectTrigger>
+                    <SelectContent>
+                      <SelectItem value="managed">Resample2 Separated folder</SelectItem>
+                      <SelectItem value="next_to_source">Next to source file</SelectItem>
+                    </SelectContent>
+                  </Select>
+                </div>
+
+                {/* Output Naming */}
+                <div>
+                  <InfoTooltip title="Name for separated files, without extension. Placeholders: {title}, {stem}, {model}, {date}. Use / for subfolders, e.g. {title}/{stem}. Leave empty to keep audio-separator's names.">
+                    <label className="block mb-2 font-semibold">
+                      Output Naming
+                    </label>
+                  </InfoTooltip>
+                  <Input
+                    placeholder="{title} - {stem}"
+                    value={settings.separation_settings.output_name_template || ""}
+                    onChange={(e) =>
+                      updateSetting("separation_settings", {
+                        ...settings.separation_settings,
+                        output_name_template: e.target.value,
+                      })
+                    }
+                  />
+                </div>
+
+                {/* Out-of-memory retry */}
+                <div className="flex items-center space-x-3">
+                  <Checkbox
+                    id="retryOnOutOfMemory"
+                    checked={settings.separation_settings.retry_on_out_of_memory ?? true}
+                    onCheckedChange={(checked) =>
+                      updateSetting("separation_settings", {
+                        ...settings.separation_settings,
+                        retry_on_out_of_memory: checked as boolean,
+                      })
+                    }
+                  />
+                  <InfoTooltip title="If the GPU runs out of memory, run the separation again with smaller batch and segment sizes, then on the CPU. Slower, but finishes on cards with little memory.">
+                    <label htmlFor="retryOnOutOfMemory" className="text-sm">
+                      Retry with less memory after running out
+                    </label>
+                  </InfoTooltip>
+                </div>
+
                 {/* Audio Processing Settings - DISABLED FOR NOW */}
                 <div className="grid grid-cols-1 md:grid-cols-3 gap-4 opacity-50">
                   <div>
@@ -1305,6 +1468,104 @@ export function SettingsPage({
                 </div>
               </CardContent>
             </Card>
+
+            {/* Hot Folders */}
+            <Card>
+              <CardHeader>
+                <CardTitle>Hot Folders</CardTitle>
+              </CardHeader>
+              <CardContent className="space-y-4">
+                <p className="text-xs text-gray-500">
+                  Audio files dropped into a hot folder are separated
+                  automatically with the settings above, once they have not
+                  changed for the given number of seconds. Stems are written to
+                  a mirrored folder structure and sources are moved to
+                  &quot;done&quot; or &quot;failed&quot; subfolders.
+                </p>
+                <div className="flex items-center gap-2">
+                  <label className="text-sm whitespace-nowrap">
+                    Stable for (seconds)
+                  </label>
+                  <Input
+                    type="number"
+                    min={1}
+                    value={hotFolderStableSeconds}
+                    onChange={(e) =>
+                      setHotFolderStableSeconds(
+                        Math.max(1, parseInt(e.target.value) || 1)
+                      )
+                    }
+                    className="w-24"
+                  />
+                  <Button variant="outline" onClick={addHotFolder}>
+                    <FolderPlus className="h-4 w-4 mr-2" />
+                    Add Folder
+                  </Button>
+                </div>
+                {hotFolderError && (
+                  <p className="text-xs text-red-500">{hotFolderError}</p>
+                )}
+                {hotFolders.length === 0 ? (
+                  <p className="text-sm text-gray-500">No hot folders</p>
+                ) : (
+                  <div className="space-y-2">
+                    {hotFolders.map((folder) => {
+                      const status = hotFolderStatus[folder.id];
+                      return (
+                        <div
+                          key={folder.id}
+                          className="flex items-start gap-3 p-3 border rounded-lg"
+                        >
+                          <Checkbox
+                            checked={folder.enabled}
+                            onCheckedChange={(checked) =>
+                              toggleHotFolder(folder.id, checked as boolean)
+                            }
+                            title={folder.enabled ? "Pause" : "Resume"}
+                          />
+                          <div className="flex-1 min-w-0">
+                            <p className="text-sm font-medium truncate">
+                              {folder.path}
+                            </p>
+                            <p className="text-xs text-gray-500 truncate">
+                              → {folder.output_dir}
+                            </p>
+                            <p className="text-xs text-gray-500">
+                              {folder.profile.model_filename ?? "Unknown model"}{" "}
+                              · stable for {folder.stable_seconds}s
+                            </p>
+                            {status && (
+                              <p
+                                className={`text-xs truncate ${
+                                  status.status === "failed"
+                                    ? "text-red-500"
+                                    : "text-gray-500"
+                                }`}
+                              >
+                                {status.status === "processing"
+                                  ? "Separating"
+                                  : status.status === "done"
+                                  ? "Done"
+                                  : "Failed"}
+                                : {status.file.split(/[\\/]/).pop()}
+                              </p>
+                            )}
+                          </div>
+                          <Button
+                            variant="ghost"
+                            size="sm"
+                            onClick={() => removeHotFolder(folder.id)}
+                            title="Stop watching"
+                          >
+                            <Trash2 className="h-4 w-4" />
+                          </Button>
+                        </div>
+                      );
+                    })}
+                  </div>
+                )}
+              </CardContent>
+            </Card>
           </div>
         )}
 
@@ -1338,6 +1599,168 @@ export function SettingsPage({
               </CardContent>
             </Card>
 
+            <Card>
+              <CardHeader>
+                <CardTitle>Notifications</CardTitle>
+              </CardHeader>
+              <CardContent>
+                <div className="space-y-3">
+                  {(
+                    [
+                      ["enabled", "Show desktop notifications when jobs finish"],
+                      ["only_when_unfocused", "Only when the window is not focused"],
+                      ["downloads", "Notify about downloads"],
+                      ["separations", "Notify about separations"],
+                      ["failures", "Notify about failed and cancelled jobs"],
+                    ] as const
+                  ).map(([key, label]) => (
+                    <div key={key} className="flex items-center space-x-3">
+                      <Checkbox
+                        id={`notifications-${key}`}
+                        checked={notificationSettings[key]}
+                        disabled={key !== "enabled" && !notificationSettings.enabled}
+                        onCheckedChange={(checked) =>
+                          updateSetting("notifications", {
+                            ...notificationSettings,
+                            [key]: checked as boolean,
+                          })
+                        }
+                      />
+                      <label htmlFor={`notifications-${key}`} className="text-sm">
+                        {label}
+                      </label>
+                    </div>
+                  ))}
+                  <p className="text-xs text-gray-500 mt-1">
+                    On Linux, finished separations offer a "Copy stems" action
+                  </p>
+                </div>
+              </CardContent>
+            </Card>
+
+            <Card>
+              <CardHeader>
+                <CardTitle>Storage Cleanup</CardTitle>
+              </CardHeader>
+              <CardContent>
+                <div className="space-y-4">
+                  <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+                    <div>
+                      <label className="block mb-2 font-semibold">
+                        Remove items older than (days)
+                      </label>
+                      <Input
+                        type="number"
+                        min="1"
+                        placeholder="Off"
+                        value={cleanupPolicy.keep_days ?? ""}
+                        onChange={(e) =>
+                          updateCleanupPolicy({
+                            keep_days: e.target.value === "" ? null : Math.max(1, parseInt(e.target.value) || 1),
+                          })
+                        }
+                      />
+                    </div>
+                    <div>
+                      <label className="block mb-2 font-semibold">
+                        Keep downloads and stems under (GB)
+                      </label>
+                      <Input
+                        type="number"
+                        min="0"
+                        step="0.5"
+                        placeholder="Off"
+                        value={cleanupPolicy.max_total_gb ?? ""}
+                        onChange={(e) =>
+                          updateCleanupPolicy({
+                            max_total_gb: e.target.value === "" ? null : Math.max(0, parseFloat(e.target.value) || 0),
+                          })
+                        }
+                      />
+                    </div>
+                  </div>
+                  <div className="space-y-3">
+                    <div className="flex items-center space-x-3">
+                      <Checkbox
+                        id="cleanupKeepFavorites"
+                        checked={cleanupPolicy.keep_favorites}
+                        onCheckedChange={(checked) =>
+                          updateCleanupPolicy({ keep_favorites: checked as boolean })
+                        }
+                      />
+                      <label htmlFor="cleanupKeepFavorites" className="text-sm">
+                        Never remove favorites
+                      </label>
+                    </div>
+                    <div className="flex items-center space-x-3">
+                      <Checkbox
+                        id="cleanupDownloadsWithStems"
+                        checked={cleanupPolicy.delete_downloads_with_stems}
+                        onCheckedChange={(checked) =>
+                          updateCleanupPolicy({ delete_downloads_with_stems: checked as boolean })
+                        }
+                      />
+                      <label htmlFor="cleanupDownloadsWithStems" className="text-sm">
+                        Remove downloads once they have been separated
+                      </label>
+                    </div>
+                    <div className="flex items-center space-x-3">
+                      <Checkbox
+                        id="cleanupAutomatic"
+                        checked={cleanupPolicy.automatic}
+                        onCheckedChange={(checked) =>
+                          updateCleanupPolicy({ automatic: checked as boolean })
+                        }
+                      />
+                      <label htmlFor="cleanupAutomatic" className="text-sm">
+                        Clean up automatically when the app starts
+                      </label>
+                    </div>
+                  </div>
+                  <div className="flex gap-2">
+                    <Button variant="outline" disabled={isCleaningUp} onClick={() => runCleanup(true)}>
+                      <Search className="h-4 w-4 mr-2" />
+                      Preview
+                    </Button>
+                    <Button variant="outline" disabled={isCleaningUp} onClick={() => runCleanup(false)}>
+                      {isCleaningUp ? (
+                        <Loader2 className="h-4 w-4 mr-2 animate-spin" />
+                      ) : (
+                        <Trash2 className="h-4 w-4 mr-2" />
+                      )}
+                      Clean Up Now
+                    </Button>
+                  </div>
+                  <p className="text-xs text-gray-500">
+                    Old items go to the trash; items removed to stay under the size limit are deleted permanently, since trashed files still take up space. Automatic cleanup only moves items to the trash and leaves the size limit to Clean Up Now
+                  </p>
+                  {cleanupStatus && (
+                    <p className="text-xs text-gray-500">{cleanupStatus}</p>
+                  )}
+                </div>
+              </CardContent>
+            </Card>
+
+            <Card>
+              <CardHeader>
+                <CardTitle>Diagnostics</CardTitle>
+              </CardHeader>
+              <CardContent>
+                <div className="space-y-2">
+                  <Button variant="outline" onClick={exportDiagnostics}>
+                    <Download className="h-4 w-4 mr-2" />
+                    Export Diagnostics Bundle
+                  </Button>
+                  <p className="text-xs text-gray-500">
+                    Saves a zip with recent logs and your settings, with secrets and your home folder removed, to attach to a bug report
+                  </p>
+                  {diagnosticsStatus && (
+                    <p className="text-xs text-gray-500">{diagnosticsStatus}</p>
+                  )}
+                </div>
+              </CardContent>
+            </Card>
+
             <Card>
               <CardHeader>
                 <CardTitle>Key</CardTitle>
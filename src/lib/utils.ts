@@ -4,3 +4,33 @@ import { twMerge } from "tailwind-merge"
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+// Backend commands reject with an `AppError`; `code` is stable, `message` is for display
+export interface AppError {
+  code: string
+  message: string
+  details?: string | null
+  path?: string | null
+  hint?: string | null
+  // The failed download or separation; pass to `get_job_log` for its tool output
+  job_id?: string | null
+}
+
+function isAppError(error: unknown): error is AppError {
+  return !!error && typeof error === "object" && "code" in error && "message" in error
+}
+
+export function errorCode(error: unknown): string | undefined {
+  return isAppError(error) ? error.code : undefined
+}
+
+// The message followed by the hint, if the backend gave one
+export function errorMessage(error: unknown): string {
+  if (isAppError(error)) {
+    return error.hint ? `${error.message} (${error.hint})` : error.message
+  }
+  if (error && typeof error === "object" && "message" in error) {
+    return String((error as { message: unknown }).message)
+  }
+  return String(error)
+}